@@ -1,3 +1,4 @@
+use deck;
 use deck::Card;
 use player_id::PlayerId;
 
@@ -129,3 +130,29 @@ pub fn play_to_action(
         }
     }
 }
+
+
+/// Every play that could be made with `card`.
+///
+/// `current_player` is the one playing the card, and `targets` are the
+/// players who are still in the round (including `current_player`). Every
+/// play returned is accepted by `play_to_action`. Targeting a protected
+/// player is allowed, it just has no effect.
+pub fn plays_for_card(current_player: PlayerId, card: Card, targets: &[PlayerId]) -> Vec<Play> {
+    let others: Vec<PlayerId> = targets.iter().map(|&x| x).filter(|&x| x != current_player).collect();
+    match card {
+        Card::Priestess | Card::Minister | Card::Princess => vec![Play::NoEffect],
+        Card::Clown | Card::Knight | Card::General =>
+            others.iter().map(|&x| Play::Attack(x)).collect(),
+        Card::Wizard => targets.iter().map(|&x| Play::Attack(x)).collect(),
+        Card::Soldier => {
+            let mut plays = vec![];
+            for &target in others.iter() {
+                for &guess in deck::CARDS.iter().filter(|&&c| c != Card::Soldier) {
+                    plays.push(Play::Guess(target, guess));
+                }
+            }
+            plays
+        },
+    }
+}
@@ -0,0 +1,283 @@
+/// Automated players of Love Letter.
+///
+/// An `Agent` only ever gets to see a `PlayerView`, so it can't cheat by
+/// looking at other players' hands.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rand;
+use std::rand::{Rng, SeedableRng, XorShiftRng};
+use std::str;
+
+use action::Play;
+use analysis;
+use analysis::SearchAgent;
+use deck::Card;
+use player_id::PlayerId;
+use round::Round;
+use view::PlayerView;
+
+
+/// Something that can decide what to play.
+pub trait Agent {
+    /// Pick a card to play and the `Play` to make with it.
+    ///
+    /// Only called when it's the viewer's turn, so `view.draw` is always
+    /// set. Must return one of `view.legal_plays()`.
+    fn choose(&self, view: &PlayerView) -> (Card, Play);
+}
+
+
+/// Picks uniformly at random from the legal plays.
+pub struct RandomAgent {
+    _rng: RefCell<XorShiftRng>,
+}
+
+
+impl RandomAgent {
+    /// Create a new agent with a randomly seeded random number generator.
+    pub fn new() -> RandomAgent {
+        RandomAgent { _rng: RefCell::new(rand::thread_rng().gen()) }
+    }
+
+    /// Create a new agent that makes the same choices every time.
+    pub fn from_seed(seed: [u32; 4]) -> RandomAgent {
+        RandomAgent { _rng: RefCell::new(SeedableRng::from_seed(seed)) }
+    }
+}
+
+
+impl Agent for RandomAgent {
+    fn choose(&self, view: &PlayerView) -> (Card, Play) {
+        let plays = view.legal_plays();
+        *self._rng.borrow_mut().choose(plays.as_slice()).expect("No legal plays")
+    }
+}
+
+
+/// Plays sensibly, one turn at a time, without looking ahead.
+///
+/// Hangs on to the higher card where it can, never throws away the Princess,
+/// avoids Knight duels it's likely to lose, leaves protected players alone,
+/// and guesses whichever card it has seen least of with the Soldier.
+pub struct HeuristicAgent;
+
+
+impl HeuristicAgent {
+    /// How much we like making `play` with `card`. Higher is better.
+    fn score(&self, view: &PlayerView, unseen: &[Card], card: Card, play: Play) -> i32 {
+        let (hand, draw) = (view.hand.unwrap(), view.draw.unwrap());
+        let kept = if card == hand { draw } else { hand };
+        if card == Card::Princess {
+            return -1000;
+        }
+        let mut score = 10 * kept as i32;
+        let target = match play {
+            Play::NoEffect => None,
+            Play::Attack(p) | Play::Guess(p, _) => Some(p),
+        };
+        match target {
+            Some(p) if p == view.player => {
+                // Only the Wizard can target its player, and we'd rather not.
+                if kept == Card::Princess {
+                    return -1000;
+                }
+                score -= 30;
+            },
+            Some(p) => {
+                let seat = view.seat(p).expect("Target not in view");
+                if view.players[seat].protected {
+                    score -= 20;
+                }
+            },
+            None => (),
+        }
+        match (card, play) {
+            (Card::Priestess, _) => score += 5,
+            (Card::Knight, _) => if kept <= Card::Priestess { score -= 40 } else { score += 5 },
+            (Card::General, _) => score -= 5 * kept as i32,
+            (Card::Soldier, Play::Guess(_, guess)) =>
+                score += 2 * unseen.iter().filter(|&&c| c == guess).count() as i32,
+            _ => (),
+        }
+        score
+    }
+}
+
+
+impl Agent for HeuristicAgent {
+    fn choose(&self, view: &PlayerView) -> (Card, Play) {
+        let plays = view.legal_plays();
+        let unseen = view.unseen_cards();
+        let (first_card, first_play) = plays[0];
+        let mut best = plays[0];
+        let mut best_score = self.score(view, unseen.as_slice(), first_card, first_play);
+        for &(card, play) in plays.iter().skip(1) {
+            let score = self.score(view, unseen.as_slice(), card, play);
+            if score > best_score {
+                best = (card, play);
+                best_score = score;
+            }
+        }
+        best
+    }
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+/// The agents that can be asked for by name, e.g. to fill a seat at a table.
+pub enum AgentKind {
+    Random,
+    Heuristic,
+    Search,
+}
+
+
+/// Every kind of agent, weakest first.
+pub const AGENT_KINDS: [AgentKind; 3] = [AgentKind::Random, AgentKind::Heuristic, AgentKind::Search];
+
+
+impl AgentKind {
+    /// A new agent of this kind.
+    pub fn agent(&self) -> Box<Agent + 'static> {
+        match *self {
+            AgentKind::Random => Box::new(RandomAgent::new()) as Box<Agent>,
+            AgentKind::Heuristic => Box::new(HeuristicAgent) as Box<Agent>,
+            AgentKind::Search => Box::new(SearchAgent::new(analysis::DEFAULT_SEARCH_DEALS)) as Box<Agent>,
+        }
+    }
+}
+
+
+impl fmt::String for AgentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            AgentKind::Random => "random",
+            AgentKind::Heuristic => "heuristic",
+            AgentKind::Search => "search",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+
+impl str::FromStr for AgentKind {
+    fn from_str(name: &str) -> Option<AgentKind> {
+        match name {
+            "random" => Some(AgentKind::Random),
+            "heuristic" => Some(AgentKind::Heuristic),
+            "search" => Some(AgentKind::Search),
+            _ => None,
+        }
+    }
+}
+
+
+/// Ask the agent in charge of `turn_player` what to play.
+///
+/// `agents` has one agent per player, in the same order as `players`.
+pub fn decide(round: &Round, players: &[PlayerId], agents: &[&Agent], turn_player: PlayerId) -> (Card, Play) {
+    let seat = players
+        .iter()
+        .position(|&p| p == turn_player)
+        .expect("No agent for player");
+    let view = round.view(turn_player).ok().expect("Current player not in round");
+    agents[seat].choose(&view)
+}
+
+
+/// Play `round` until it's over, with `agents` making every decision.
+///
+/// `agents` has one agent per player, in order of play.
+pub fn play_round(round: &Round, agents: &[&Agent]) -> Round {
+    let players = round.player_ids();
+    assert_eq!(players.len(), agents.len());
+    let mut current = round.clone();
+    loop {
+        let result = current.handle_turn(
+            |r, turn| decide(r, players.as_slice(), agents, turn.player), |_, _| ());
+        match result {
+            Ok(None) => return current,
+            Ok(Some((new_round, _))) => current = new_round,
+            Err(e) => panic!("Agent made an invalid play: {:?}", e),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use action::Play;
+    use deck::Card;
+    use game;
+    use player_id::player_id_generator;
+    use round::Round;
+    use std::str;
+
+    use super::{Agent, AgentKind, AGENT_KINDS, HeuristicAgent, RandomAgent, play_round};
+
+    #[test]
+    fn test_random_agents_finish_round() {
+        let game = game::new_game(4).unwrap();
+        let round = game.new_round();
+        let agents: Vec<RandomAgent> = range(0, 4).map(|_| RandomAgent::new()).collect();
+        let agent_refs: Vec<&Agent> = agents.iter().map(|a| a as &Agent).collect();
+        let finished = play_round(&round, agent_refs.as_slice());
+        assert!(finished.winners().len() > 0);
+    }
+
+    #[test]
+    fn test_random_agent_chooses_legal_play() {
+        let game = game::new_game(2).unwrap();
+        let round = game.new_round();
+        let agent = RandomAgent::from_seed([1, 2, 3, 4]);
+        let result = round.handle_turn(
+            |r, turn| {
+                let view = r.view(turn.player).unwrap();
+                let choice = agent.choose(&view);
+                assert!(view.legal_plays().contains(&choice));
+                choice
+            },
+            |_, _| ());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_heuristic_agents_finish_round() {
+        let game = game::new_game(3).unwrap();
+        let round = game.new_round();
+        let agent = HeuristicAgent;
+        let finished = play_round(&round, &[&agent as &Agent, &agent, &agent]);
+        assert!(finished.winners().len() > 0);
+    }
+
+    #[test]
+    fn test_agent_kind_names() {
+        for &kind in AGENT_KINDS.iter() {
+            assert_eq!(Some(kind), str::FromStr::from_str(format!("{}", kind).as_slice()));
+        }
+        assert_eq!(None, str::FromStr::from_str("genius").map(|k: AgentKind| k));
+    }
+
+    #[test]
+    fn test_heuristic_keeps_princess() {
+        let players: Vec<_> = player_id_generator().take(2).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Princess)), (players[1], Some(Card::Soldier))],
+            &[Card::Clown, Card::Priestess], None).unwrap();
+        let (ready, turn) = round.peek_turn().unwrap();
+        let view = ready.view(turn.player).unwrap();
+        assert_eq!((Card::Priestess, Play::NoEffect), HeuristicAgent.choose(&view));
+    }
+
+    #[test]
+    fn test_heuristic_avoids_wizard_on_own_princess() {
+        let players: Vec<_> = player_id_generator().take(2).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Princess)), (players[1], Some(Card::Soldier))],
+            &[Card::Clown, Card::Wizard], None).unwrap();
+        let (ready, turn) = round.peek_turn().unwrap();
+        let view = ready.view(turn.player).unwrap();
+        assert_eq!((Card::Wizard, Play::Attack(players[1])), HeuristicAgent.choose(&view));
+    }
+}
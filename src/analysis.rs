@@ -0,0 +1,489 @@
+/// Work out how good each play is, from the acting player's point of view.
+///
+/// The acting player doesn't know the other players' hands, the order of the
+/// cards left in the deck, or which card was burned. Every way of dealing the
+/// cards they haven't seen is equally likely, so we can work out the odds of
+/// something happening by trying it against every possible deal.
+///
+/// Whether a play eliminates its target depends only on the other players'
+/// hands, so those odds are always exact. Whether a play wins the round
+/// depends on the order of the deck too, which can mean a lot of deals early
+/// in a round. If there are more than `max_deals` of them, we try a random
+/// sample of `max_deals` deals instead, and say so.
+///
+/// `SearchAgent` uses all this to play, by always picking the play most
+/// likely to win the round.
+
+use std::cell::RefCell;
+use std::cmp;
+use std::rand;
+use std::rand::{Rng, SeedableRng, XorShiftRng};
+
+use action::Play;
+use agent;
+use agent::{Agent, HeuristicAgent};
+use deck;
+use deck::Card;
+use player_id::PlayerId;
+use round::Round;
+use view::PlayerView;
+
+
+#[derive(Show, Clone, Copy, PartialEq)]
+/// How good a single play is.
+pub struct PlayAnalysis {
+    pub card: Card,
+    pub play: Play,
+    /// The chance that the play knocks its target out of the round. Always 0
+    /// for plays without a target other than the acting player.
+    pub eliminates_target: f64,
+    /// The chance that the acting player knocks themself out of the round by
+    /// making this play, e.g. by losing a Knight duel.
+    pub eliminates_self: f64,
+    /// The chance that the acting player wins the round, including sharing
+    /// the win with others.
+    pub wins_round: f64,
+}
+
+
+#[derive(Show, Clone, PartialEq)]
+/// How good every legal play is.
+pub struct Analysis {
+    /// One entry per legal play, in the same order as
+    /// `PlayerView::legal_plays`.
+    pub plays: Vec<PlayAnalysis>,
+    /// The number of deals we tried when working out `wins_round`.
+    pub deals: usize,
+    /// Whether `wins_round` was worked out from every possible deal, rather
+    /// than from a sample.
+    pub exact: bool,
+}
+
+
+impl Analysis {
+    /// The play most likely to win the round.
+    pub fn best(&self) -> Option<&PlayAnalysis> {
+        let mut best: Option<&PlayAnalysis> = None;
+        for play in self.plays.iter() {
+            best = match best {
+                Some(b) if b.wins_round >= play.wins_round => Some(b),
+                _ => Some(play),
+            };
+        }
+        best
+    }
+}
+
+
+/// Analyse every legal play for the player whose turn is next in `round`.
+///
+/// After the play being analysed, every player, including the acting one, is
+/// played by `policy`. Returns `None` if the round is over, or if the next
+/// player has no choice because they have busted out.
+pub fn analyse_round<R: Rng>(round: &Round, policy: &Agent, max_deals: usize, rng: &mut R) -> Option<Analysis> {
+    let (ready, turn) = match round.peek_turn() {
+        None => return None,
+        Some(x) => x,
+    };
+    if turn.busts() {
+        return None;
+    }
+    let view = ready.view(turn.player).ok().expect("Current player not in round");
+    Some(analyse(&view, policy, max_deals, rng))
+}
+
+
+/// Analyse every legal play open to the viewer.
+///
+/// `view` must be from the point of view of the player whose turn it is.
+/// After the play being analysed, every player, including the viewer, is
+/// played by `policy`.
+pub fn analyse<R: Rng>(view: &PlayerView, policy: &Agent, max_deals: usize, rng: &mut R) -> Analysis {
+    let plays = view.legal_plays();
+    let unseen = Unseen::new(view.unseen_cards().as_slice());
+    let opponents: Vec<PlayerId> = view
+        .active_players()
+        .into_iter()
+        .filter(|&p| p != view.player)
+        .collect();
+    let num_hidden = opponents.len() + view.deck_size;
+
+    // Immediate elimination only depends on what the opponents are holding.
+    let mut eliminations: Vec<(f64, f64)> = plays.iter().map(|_| (0.0, 0.0)).collect();
+    for (hands, probability) in unseen.hands(opponents.len()).into_iter() {
+        let deck = unseen.remove(hands.as_slice()).arbitrary(view.deck_size);
+        let round = rebuild(view, opponents.as_slice(), hands.as_slice(), deck.as_slice());
+        for (i, &(card, play)) in plays.iter().enumerate() {
+            let after = apply(&round, card, play);
+            let (target, own) = eliminations[i];
+            let target_out = match target_of(play) {
+                Some(t) if t != view.player && !is_active(&after, view.player, t) => probability,
+                _ => 0.0,
+            };
+            let self_out = if is_active(&after, view.player, view.player) { 0.0 } else { probability };
+            eliminations[i] = (target + target_out, own + self_out);
+        }
+    }
+
+    // Winning depends on everything we can't see. Each deal comes with how
+    // likely it is: sampled deals are all as likely as each other, but
+    // distinct deals needn't be.
+    let exact = unseen.num_arrangements(num_hidden) <= max_deals as f64;
+    let deals = if exact {
+        unseen.arrangements(num_hidden)
+    } else {
+        let mut deals = vec![];
+        for _ in range(0, max_deals) {
+            let mut cards = unseen.cards();
+            rng.shuffle(cards.as_mut_slice());
+            cards.truncate(num_hidden);
+            deals.push((cards, 1.0 / max_deals as f64));
+        }
+        deals
+    };
+    let mut wins: Vec<f64> = plays.iter().map(|_| 0.0).collect();
+    for &(ref cards, probability) in deals.iter() {
+        let (hands, deck) = cards.split_at(opponents.len());
+        let round = rebuild(view, opponents.as_slice(), hands, deck);
+        for (i, &(card, play)) in plays.iter().enumerate() {
+            let after = apply(&round, card, play);
+            let agents: Vec<&Agent> = view.players.iter().map(|_| policy).collect();
+            let finished = agent::play_round(&after, agents.as_slice());
+            if finished.winners().iter().any(|&(p, _)| p == view.player) {
+                wins[i] += probability;
+            }
+        }
+    }
+
+    let num_deals = deals.len();
+    Analysis {
+        plays: plays
+            .iter()
+            .zip(eliminations.iter())
+            .zip(wins.iter())
+            .map(|((&(card, play), &(target, own)), &won)| PlayAnalysis {
+                card: card,
+                play: play,
+                eliminates_target: target,
+                eliminates_self: own,
+                wins_round: won,
+            })
+            .collect(),
+        deals: num_deals,
+        exact: exact,
+    }
+}
+
+
+/// How many deals `SearchAgent` tries for each decision, unless told
+/// otherwise.
+pub const DEFAULT_SEARCH_DEALS: usize = 200;
+
+
+/// Plays whatever is most likely to win the round, assuming everyone plays
+/// like `HeuristicAgent` from then on.
+pub struct SearchAgent {
+    _max_deals: usize,
+    _rng: RefCell<XorShiftRng>,
+}
+
+
+impl SearchAgent {
+    /// Create a new agent that tries up to `max_deals` deals for each
+    /// decision.
+    pub fn new(max_deals: usize) -> SearchAgent {
+        SearchAgent { _max_deals: max_deals, _rng: RefCell::new(rand::thread_rng().gen()) }
+    }
+
+    /// Create a new agent that makes the same choices every time.
+    pub fn from_seed(max_deals: usize, seed: [u32; 4]) -> SearchAgent {
+        SearchAgent { _max_deals: max_deals, _rng: RefCell::new(SeedableRng::from_seed(seed)) }
+    }
+}
+
+
+impl Agent for SearchAgent {
+    fn choose(&self, view: &PlayerView) -> (Card, Play) {
+        let analysis = analyse(view, &HeuristicAgent, self._max_deals, &mut *self._rng.borrow_mut());
+        let best = analysis.best().expect("No legal plays");
+        (best.card, best.play)
+    }
+}
+
+
+fn target_of(play: Play) -> Option<PlayerId> {
+    match play {
+        Play::NoEffect => None,
+        Play::Attack(p) | Play::Guess(p, _) => Some(p),
+    }
+}
+
+
+fn is_active(round: &Round, viewer: PlayerId, player: PlayerId) -> bool {
+    let view = round.view(viewer).ok().expect("Viewer not in round");
+    let seat = view.seat(player).expect("Player not in round");
+    view.players[seat].active
+}
+
+
+/// Rebuild the round for a particular deal of the hidden cards. The deck is
+/// listed from the top down, so the first card is the next to be drawn.
+fn rebuild(view: &PlayerView, opponents: &[PlayerId], hands: &[Card], deck: &[Card]) -> Round {
+    let hands: Vec<(PlayerId, Card)> = opponents.iter().map(|&x| x).zip(hands.iter().map(|&x| x)).collect();
+    let mut stack: Vec<Card> = deck.iter().map(|&x| x).collect();
+    stack.reverse();
+    Round::from_view(view, hands.as_slice(), stack.as_slice())
+        .ok().expect("Could not rebuild round from view")
+}
+
+
+fn apply(round: &Round, card: Card, play: Play) -> Round {
+    match round.handle_turn(|_, _| (card, play), |_, _| ()) {
+        Ok(Some((new_round, _))) => new_round,
+        Ok(None) => panic!("Tried to play {:?} after round ended", card),
+        Err(e) => panic!("Invalid play {:?} {:?}: {:?}", card, play, e),
+    }
+}
+
+
+/// The cards a player hasn't seen, counted by kind.
+#[derive(Clone)]
+struct Unseen {
+    _counts: [usize; 8],
+}
+
+
+impl Unseen {
+    fn new(cards: &[Card]) -> Unseen {
+        let mut counts = [0; 8];
+        for &card in cards.iter() {
+            counts[card as usize] += 1;
+        }
+        Unseen { _counts: counts }
+    }
+
+    fn len(&self) -> usize {
+        self._counts.iter().fold(0, |acc, &n| acc + n)
+    }
+
+    fn cards(&self) -> Vec<Card> {
+        let mut cards = vec![];
+        for (i, &n) in self._counts.iter().enumerate() {
+            for _ in range(0, n) {
+                cards.push(deck::CARDS[i]);
+            }
+        }
+        cards
+    }
+
+    fn remove(&self, cards: &[Card]) -> Unseen {
+        let mut new = self.clone();
+        for &card in cards.iter() {
+            new._counts[card as usize] -= 1;
+        }
+        new
+    }
+
+    /// Any `n` of these cards.
+    fn arbitrary(&self, n: usize) -> Vec<Card> {
+        let mut cards = self.cards();
+        cards.truncate(n);
+        cards
+    }
+
+    /// Every distinct way of dealing one card to each of `n` players, along
+    /// with its probability.
+    fn hands(&self, n: usize) -> Vec<(Vec<Card>, f64)> {
+        self.arrangements(n)
+    }
+
+    /// The number of distinct sequences of `n` of these cards.
+    ///
+    /// Counting them one by one takes far too long early in a round, so
+    /// this is `n!` times the coefficient of `x^n` in the product, over each
+    /// kind of card, of `1 + x + x^2/2! + ... + x^c/c!`, where `c` is how
+    /// many of that kind there are.
+    fn num_arrangements(&self, n: usize) -> f64 {
+        let mut coefficients: Vec<f64> = range(0, n + 1).map(|i| if i == 0 { 1.0 } else { 0.0 }).collect();
+        for &count in self._counts.iter() {
+            let mut product: Vec<f64> = coefficients.iter().map(|_| 0.0).collect();
+            for (i, &c) in coefficients.iter().enumerate() {
+                let mut term = c;
+                for k in range(0, cmp::min(count, n - i) + 1) {
+                    if k > 0 {
+                        term /= k as f64;
+                    }
+                    product[i + k] += term;
+                }
+            }
+            coefficients = product;
+        }
+        let factorial = range(1, n + 1).fold(1.0, |acc, k| acc * k as f64);
+        (coefficients[n] * factorial).round()
+    }
+
+    /// Every distinct sequence of `n` of these cards, drawn from a shuffle
+    /// of all of them, along with its probability.
+    ///
+    /// Unless `n` is all of the cards, sequences aren't equally likely: one
+    /// that uses up several copies of a common card comes from more full
+    /// shuffles than one made of rare cards. So each card drawn multiplies
+    /// the probability by the chance of drawing it from what's left.
+    fn arrangements(&self, n: usize) -> Vec<(Vec<Card>, f64)> {
+        if n == 0 {
+            return vec![(vec![], 1.0)];
+        }
+        let total = self.len() as f64;
+        let mut result = vec![];
+        for (i, &count) in self._counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let card = deck::CARDS[i];
+            let p = count as f64 / total;
+            for (rest, q) in self.remove(&[card]).arrangements(n - 1).into_iter() {
+                let mut cards = vec![card];
+                cards.push_all(rest.as_slice());
+                result.push((cards, p * q));
+            }
+        }
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::rand::{SeedableRng, XorShiftRng};
+
+    use action::Play;
+    use agent::{Agent, RandomAgent};
+    use deck;
+    use deck::Card;
+    use game;
+    use player_id::player_id_generator;
+    use round::Round;
+    use super::{SearchAgent, Unseen, analyse_round};
+
+    fn make_rng() -> XorShiftRng {
+        SeedableRng::from_seed([1, 2, 3, 4])
+    }
+
+    #[test]
+    fn test_unseen_hands_probabilities() {
+        let unseen = Unseen::new(&[Card::Soldier, Card::Soldier, Card::Princess]);
+        let hands = unseen.hands(1);
+        assert_eq!(vec![(vec![Card::Soldier], 2.0 / 3.0), (vec![Card::Princess], 1.0 / 3.0)], hands);
+    }
+
+    #[test]
+    fn test_unseen_arrangements_distinct() {
+        let unseen = Unseen::new(&[Card::Soldier, Card::Soldier, Card::Princess]);
+        let seen = unseen.arrangements(3);
+        assert_eq!(3, seen.len());
+        assert_eq!(3.0, unseen.num_arrangements(3));
+        assert_eq!(3.0, unseen.num_arrangements(2));
+        assert_eq!(1.0, unseen.num_arrangements(0));
+        // Every distinct order of the whole deck: 16! / (5! 2! 2! 2! 2!).
+        let deck = Unseen::new(&deck::DECK);
+        assert_eq!(15.0 * 14.0 * 13.0 * 12.0 * 11.0 * 10.0 * 9.0 * 8.0 * 7.0 * 6.0,
+                   deck.num_arrangements(16));
+    }
+
+    #[test]
+    fn test_unseen_arrangements_probabilities() {
+        // Drawing one card is more likely to give a Soldier, even though
+        // there are as many distinct draws of each.
+        let unseen = Unseen::new(&[Card::Soldier, Card::Soldier, Card::Princess]);
+        assert_eq!(vec![(vec![Card::Soldier], 2.0 / 3.0), (vec![Card::Princess], 1.0 / 3.0)],
+                   unseen.arrangements(1));
+        let total = unseen.arrangements(2).iter().fold(0.0, |acc, &(_, p)| acc + p);
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_soldier_guess_odds() {
+        // Player 1 holds a Soldier and draws a Wizard. They've seen nothing
+        // else, so the other player could be holding anything.
+        let players: Vec<_> = player_id_generator().take(2).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Soldier)), (players[1], Some(Card::Princess))],
+            &[Card::Clown, Card::Wizard], None).unwrap();
+        let policy = RandomAgent::from_seed([4, 3, 2, 1]);
+        let analysis = analyse_round(&round, &policy as &Agent, 10, &mut make_rng()).unwrap();
+        let guess_princess = analysis.plays
+            .iter()
+            .find(|a| a.card == Card::Soldier && a.play == Play::Guess(players[1], Card::Princess))
+            .unwrap();
+        // 14 cards unseen, one of which is the Princess.
+        assert!((guess_princess.eliminates_target - 1.0 / 14.0).abs() < 1e-9);
+        let guess_clown = analysis.plays
+            .iter()
+            .find(|a| a.card == Card::Soldier && a.play == Play::Guess(players[1], Card::Clown))
+            .unwrap();
+        assert!((guess_clown.eliminates_target - 2.0 / 14.0).abs() < 1e-9);
+        // There are 60 ways to deal the other player's hand and the last
+        // card of the deck, which is more than we allowed.
+        assert!(!analysis.exact);
+        assert_eq!(10, analysis.deals);
+    }
+
+    #[test]
+    fn test_exact_late_in_round() {
+        // Player 1 holds a Knight and draws the last card, a Soldier. The
+        // round ends after their turn, so the policy never gets a say.
+        let players: Vec<_> = player_id_generator().take(2).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Knight)), (players[1], Some(Card::Princess))],
+            &[Card::Soldier], None).unwrap();
+        let policy = RandomAgent::from_seed([4, 3, 2, 1]);
+        let analysis = analyse_round(&round, &policy as &Agent, 1000, &mut make_rng()).unwrap();
+        assert!(analysis.exact);
+        // Player 2 could be holding any of the eight kinds of card, but
+        // they're not equally likely: 4 of the 14 unseen cards are Soldiers.
+        assert_eq!(8, analysis.deals);
+        // Playing the Knight leaves player 1 with a Soldier, which only
+        // survives the duel, and shares the round, against another Soldier.
+        let knight = analysis.plays.iter().find(|a| a.card == Card::Knight).unwrap();
+        assert!((knight.wins_round - 4.0 / 14.0).abs() < 1e-9);
+        // Guessing the Clown wins if it's right, and otherwise the Knight
+        // wins against a Soldier and ties with the last Knight.
+        let guess_clown = analysis.plays
+            .iter()
+            .find(|a| a.card == Card::Soldier && a.play == Play::Guess(players[1], Card::Clown))
+            .unwrap();
+        assert!((guess_clown.wins_round - 7.0 / 14.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fresh_round() {
+        // Far too many deals to count one by one, so they're sampled.
+        let round = game::new_game(2).unwrap().new_round();
+        let policy = RandomAgent::from_seed([4, 3, 2, 1]);
+        let analysis = analyse_round(&round, &policy as &Agent, 20, &mut make_rng()).unwrap();
+        assert!(!analysis.exact);
+        assert_eq!(20, analysis.deals);
+    }
+
+    #[test]
+    fn test_round_over() {
+        let players: Vec<_> = player_id_generator().take(2).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Soldier)), (players[1], None)], &[Card::Clown], None).unwrap();
+        let policy = RandomAgent::from_seed([4, 3, 2, 1]);
+        assert!(analyse_round(&round, &policy as &Agent, 10, &mut make_rng()).is_none());
+    }
+
+    #[test]
+    fn test_search_agent_chooses_legal_play() {
+        let players: Vec<_> = player_id_generator().take(2).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Soldier)), (players[1], Some(Card::Princess))],
+            &[Card::Clown, Card::Clown], None).unwrap();
+        let (ready, turn) = round.peek_turn().unwrap();
+        let view = ready.view(turn.player).unwrap();
+        let agent = SearchAgent::from_seed(50, [4, 3, 2, 1]);
+        let (card, play) = agent.choose(&view);
+        assert!(view.legal_plays().contains(&(card, play)));
+    }
+}
@@ -0,0 +1,106 @@
+// Added rust 1.0.0-alpha. So much unstable stuff.
+#![allow(unstable)]
+
+extern crate loveletter;
+
+use std::os;
+use std::str;
+
+use loveletter::server;
+
+
+const DEFAULT_ADDRESS: &'static str = "127.0.0.1:4321";
+
+
+fn usage(program: &str) -> String {
+    format!("Usage: {} [--address <host:port>] [--websocket <host:port>] [--players <2-4>] \
+             [--variant quick|classic] [--lobby] [--grace-period <seconds>] \
+             [--turn-limit <seconds>] [--omniscient-delay <seconds>] \
+             [--storage <directory>] [--bot random|heuristic|search]... \
+             [--think-delay <seconds>]", program)
+}
+
+
+/// Parse command-line arguments into a server configuration.
+fn parse_args(args: &[String]) -> Result<server::Config, String> {
+    let mut config = server::Config::new(DEFAULT_ADDRESS);
+    let mut iter = args.iter();
+    loop {
+        match iter.next().map(|a| a.as_slice()) {
+            None => break,
+            Some("--address") => match iter.next() {
+                Some(a) => config.address = a.clone(),
+                None => return Err("--address needs a value".to_string()),
+            },
+            Some("--websocket") => match iter.next() {
+                Some(a) => config.websocket_address = Some(a.clone()),
+                None => return Err("--websocket needs a value".to_string()),
+            },
+            Some("--players") => match iter.next().and_then(|n| str::FromStr::from_str(n.as_slice())) {
+                Some(n) if 2 <= n && n <= 4 => config.num_players = n,
+                _ => return Err("--players needs a number from 2 to 4".to_string()),
+            },
+            Some("--variant") => match iter.next().and_then(|v| str::FromStr::from_str(v.as_slice())) {
+                Some(variant) => config.variant = variant,
+                None => return Err("--variant needs to be quick or classic".to_string()),
+            },
+            Some("--lobby") => config.lobby = true,
+            Some("--storage") => match iter.next() {
+                Some(dir) => config.storage = Some(dir.clone()),
+                None => return Err("--storage needs a directory".to_string()),
+            },
+            Some("--grace-period") => match iter.next().and_then(|n| str::FromStr::from_str(n.as_slice())) {
+                Some(seconds) => config.grace_period = seconds,
+                None => return Err("--grace-period needs a number of seconds".to_string()),
+            },
+            Some("--turn-limit") => match iter.next().and_then(|n| str::FromStr::from_str(n.as_slice())) {
+                Some(seconds) if seconds > 0 => config.turn_limit = Some(seconds),
+                _ => return Err("--turn-limit needs a number of seconds".to_string()),
+            },
+            Some("--omniscient-delay") => match iter.next().and_then(|n| str::FromStr::from_str(n.as_slice())) {
+                Some(seconds) => config.omniscient_delay = Some(seconds),
+                None => return Err("--omniscient-delay needs a number of seconds".to_string()),
+            },
+            Some("--bot") => match iter.next().and_then(|k| str::FromStr::from_str(k.as_slice())) {
+                Some(kind) => config.bots.push(kind),
+                None => return Err("--bot needs to be random, heuristic or search".to_string()),
+            },
+            Some("--think-delay") => match iter.next().and_then(|n| str::FromStr::from_str(n.as_slice())) {
+                Some(seconds) => config.think_delay = seconds,
+                None => return Err("--think-delay needs a number of seconds".to_string()),
+            },
+            Some(other) => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+    if config.bots.len() >= config.num_players {
+        return Err("Leave at least one seat for a person".to_string());
+    }
+    Ok(config)
+}
+
+
+#[cfg(not(test))]
+fn main() {
+    let args = os::args();
+    let config = match parse_args(args.tail()) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("{}", e);
+            println!("{}", usage(args[0].as_slice()));
+            os::set_exit_status(2);
+            return;
+        },
+    };
+    println!("Waiting for {} players on {}", config.num_players - config.bots.len(), config.address);
+    match config.websocket_address {
+        Some(ref a) => println!("Accepting WebSocket connections on {}", a),
+        None => (),
+    }
+    match server::serve(&config) {
+        Ok(()) => println!("Game over"),
+        Err(e) => {
+            println!("Server failed: {}", e);
+            os::set_exit_status(1);
+        },
+    }
+}
@@ -0,0 +1,537 @@
+/// Approximate equilibrium strategies for the two-player game.
+///
+/// Uses Monte Carlo counterfactual regret minimisation with external
+/// sampling. Each iteration deals a fresh deck, then walks the game tree once
+/// for each player: every play is explored at the walking player's
+/// decisions, while their opponent's plays, and the deal, are sampled.
+///
+/// The solver works one round at a time, with a win worth 1, a loss worth -1
+/// and a shared win worth 0. A game is a series of independent rounds, so a
+/// strategy that does well in rounds does well in games.
+///
+/// ### Information sets
+///
+/// Players are told apart by their seat, and an information set is
+/// identified by everything in the acting player's `PlayerView`: their hand,
+/// their draw, each player's discards, who is protected, who is still in,
+/// and how many cards are left. Anything a player learned in private (what
+/// the Clown showed them, what they gave away with the General) isn't part
+/// of the information set, so this is an abstraction of the real game, and
+/// the resulting strategy is only approximately an equilibrium.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::{BufferedReader, File, IoError};
+use std::rand;
+use std::rand::{Rng, SeedableRng, XorShiftRng};
+use std::str;
+
+use action::Play;
+use agent;
+use agent::Agent;
+use deck;
+use deck::Card;
+use player_id::{PlayerId, player_id_generator};
+use round::Round;
+use view::PlayerView;
+
+
+/// The number of players the solver works with.
+const NUM_PLAYERS: usize = 2;
+
+
+/// The name of the information set that `view` belongs to.
+pub fn info_set_key(view: &PlayerView) -> String {
+    let seat = view.seat(view.player).expect("Viewer not in view");
+    let mut key = format!(
+        "{}:{}{}", seat, card_code(view.hand), card_code(view.draw));
+    for player in view.players.iter() {
+        key.push('|');
+        if !player.active {
+            key.push('x');
+        }
+        if player.protected {
+            key.push('*');
+        }
+        for &card in player.discards.iter() {
+            key.push_str(card_code(Some(card)).as_slice());
+        }
+    }
+    key.push_str(format!("|{}", view.deck_size).as_slice());
+    key
+}
+
+
+/// Cards are written as their value, from 1 (Soldier) to 8 (Princess).
+fn card_code(card: Option<Card>) -> String {
+    match card {
+        Some(c) => format!("{}", c as usize + 1),
+        None => "-".to_string(),
+    }
+}
+
+
+#[derive(Show, Clone)]
+struct Node {
+    regret_sum: Vec<f64>,
+    strategy_sum: Vec<f64>,
+}
+
+
+impl Node {
+    fn new(num_actions: usize) -> Node {
+        Node {
+            regret_sum: range(0, num_actions).map(|_| 0.0).collect(),
+            strategy_sum: range(0, num_actions).map(|_| 0.0).collect(),
+        }
+    }
+
+    /// The strategy we'd play now, by regret matching.
+    fn current_strategy(&self) -> Vec<f64> {
+        let positive: Vec<f64> = self.regret_sum.iter().map(|&r| if r > 0.0 { r } else { 0.0 }).collect();
+        normalize(positive.as_slice())
+    }
+
+    /// The average strategy over all iterations so far.
+    fn average_strategy(&self) -> Vec<f64> {
+        normalize(self.strategy_sum.as_slice())
+    }
+}
+
+
+/// Scale `weights` so they add up to one. If they're all zero, return the
+/// uniform distribution.
+fn normalize(weights: &[f64]) -> Vec<f64> {
+    let total = weights.iter().fold(0.0, |acc, &w| acc + w);
+    if total > 0.0 {
+        weights.iter().map(|&w| w / total).collect()
+    } else {
+        let n = weights.len() as f64;
+        weights.iter().map(|_| 1.0 / n).collect()
+    }
+}
+
+
+/// Pick an index at random, where the chance of picking `i` is `probs[i]`.
+fn sample<R: Rng>(rng: &mut R, probs: &[f64]) -> usize {
+    let mut x = rng.gen::<f64>();
+    for (i, &p) in probs.iter().enumerate() {
+        if x < p {
+            return i;
+        }
+        x -= p;
+    }
+    probs.len() - 1
+}
+
+
+/// Play `(card, play)` as the next turn of `round`.
+fn apply(round: &Round, card: Card, play: Play) -> Round {
+    match round.handle_turn(|_, _| (card, play), |_, _| ()) {
+        Ok(Some((new_round, _))) => new_round,
+        Ok(None) => panic!("Tried to play {:?} after round ended", card),
+        Err(e) => panic!("Invalid play {:?} {:?}: {:?}", card, play, e),
+    }
+}
+
+
+/// How well `player` did in a finished round.
+fn payoff(round: &Round, player: PlayerId) -> f64 {
+    let winners = round.winners();
+    let won = winners.iter().any(|&(p, _)| p == player);
+    if winners.len() > 1 {
+        0.0
+    } else if won {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+
+/// Finds an approximate equilibrium by playing against itself.
+pub struct Solver {
+    _nodes: HashMap<String, Node>,
+    _players: Vec<PlayerId>,
+    _rng: XorShiftRng,
+    _iterations: usize,
+}
+
+
+impl Solver {
+    /// Create a new solver with a randomly seeded random number generator.
+    pub fn new() -> Solver {
+        Solver::from_rng(rand::thread_rng().gen())
+    }
+
+    /// Create a new solver that gives the same results every time.
+    pub fn from_seed(seed: [u32; 4]) -> Solver {
+        Solver::from_rng(SeedableRng::from_seed(seed))
+    }
+
+    fn from_rng(rng: XorShiftRng) -> Solver {
+        Solver {
+            _nodes: HashMap::new(),
+            _players: player_id_generator().take(NUM_PLAYERS).collect(),
+            _rng: rng,
+            _iterations: 0,
+        }
+    }
+
+    /// How many iterations we've run so far.
+    pub fn iterations(&self) -> usize {
+        self._iterations
+    }
+
+    /// How many information sets we've visited so far.
+    pub fn num_info_sets(&self) -> usize {
+        self._nodes.len()
+    }
+
+    /// Run `n` more iterations.
+    pub fn iterate(&mut self, n: usize) {
+        for _ in range(0, n) {
+            let deck = deck::Deck::with_rng(&mut self._rng);
+            let round = Round::from_deck(self._players.as_slice(), deck);
+            for i in range(0, NUM_PLAYERS) {
+                let traverser = self._players[i];
+                self.traverse(&round, traverser);
+            }
+            self._iterations += 1;
+        }
+    }
+
+    /// The average strategy so far, which is what converges to an
+    /// equilibrium.
+    pub fn strategy(&self) -> Strategy {
+        let mut table = HashMap::new();
+        for (key, node) in self._nodes.iter() {
+            table.insert(key.clone(), node.average_strategy());
+        }
+        Strategy { _table: table }
+    }
+
+    fn current_strategy(&self, key: &String, num_actions: usize) -> Vec<f64> {
+        match self._nodes.get(key) {
+            Some(node) => node.current_strategy(),
+            None => normalize(range(0, num_actions).map(|_| 0.0).collect::<Vec<f64>>().as_slice()),
+        }
+    }
+
+    fn node_mut(&mut self, key: String, num_actions: usize) -> &mut Node {
+        match self._nodes.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Node::new(num_actions)),
+        }
+    }
+
+    /// Walk the tree below `round`, updating regrets for `traverser`.
+    ///
+    /// Returns the expected payoff for `traverser`.
+    fn traverse(&mut self, round: &Round, traverser: PlayerId) -> f64 {
+        let (ready, turn) = match round.peek_turn() {
+            None => return payoff(round, traverser),
+            Some(x) => x,
+        };
+        if turn.busts() {
+            return self.traverse(&apply(round, turn.hand, Play::NoEffect), traverser);
+        }
+        let view = ready.view(turn.player).ok().expect("Current player not in round");
+        let plays = view.legal_plays();
+        let key = info_set_key(&view);
+        let strategy = self.current_strategy(&key, plays.len());
+
+        if turn.player == traverser {
+            let values: Vec<f64> = plays
+                .iter()
+                .map(|&(card, play)| self.traverse(&apply(round, card, play), traverser))
+                .collect();
+            let expected = values.iter().zip(strategy.iter()).fold(0.0, |acc, (&v, &p)| acc + v * p);
+            let node = self.node_mut(key, plays.len());
+            for (regret, &value) in node.regret_sum.iter_mut().zip(values.iter()) {
+                *regret += value - expected;
+            }
+            expected
+        } else {
+            {
+                let node = self.node_mut(key, plays.len());
+                for (total, &p) in node.strategy_sum.iter_mut().zip(strategy.iter()) {
+                    *total += p;
+                }
+            }
+            let i = sample(&mut self._rng, strategy.as_slice());
+            let (card, play) = plays[i];
+            self.traverse(&apply(round, card, play), traverser)
+        }
+    }
+}
+
+
+#[derive(Show)]
+/// Errors that can occur while reading or writing a strategy.
+pub enum Error {
+    Io(IoError),
+    /// The strategy file has a line we don't understand. Line numbers start
+    /// at 1.
+    Malformed(usize),
+}
+
+
+#[derive(Show, Clone, PartialEq)]
+/// A strategy table: for each information set, the probability of making
+/// each play in `PlayerView::legal_plays`.
+///
+/// Saved as a text file with one information set per line: the key, a tab,
+/// and then the probabilities separated by spaces.
+pub struct Strategy {
+    _table: HashMap<String, Vec<f64>>,
+}
+
+
+impl Strategy {
+    /// How many information sets this strategy covers.
+    pub fn len(&self) -> usize {
+        self._table.len()
+    }
+
+    /// The probability of making each legal play, if we know about this
+    /// information set.
+    pub fn probabilities(&self, view: &PlayerView) -> Option<&[f64]> {
+        self._table.get(&info_set_key(view)).map(|x| x.as_slice())
+    }
+
+    /// Load a strategy from `path`.
+    pub fn load(path: &Path) -> Result<Strategy, Error> {
+        let file = try!(File::open(path).map_err(Error::Io));
+        let mut reader = BufferedReader::new(file);
+        let mut table = HashMap::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = try!(line.map_err(Error::Io));
+            let line = line.trim_right_matches('\n');
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 2 {
+                return Err(Error::Malformed(i + 1));
+            }
+            let mut probs = vec![];
+            for p in fields[1].split(' ') {
+                match str::FromStr::from_str(p) {
+                    Some(x) => probs.push(x),
+                    None => return Err(Error::Malformed(i + 1)),
+                }
+            }
+            table.insert(fields[0].to_string(), probs);
+        }
+        Ok(Strategy { _table: table })
+    }
+
+    /// Write this strategy to `path`, replacing whatever was there.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut file = try!(File::create(path).map_err(Error::Io));
+        let mut keys: Vec<&String> = self._table.keys().collect();
+        keys.sort();
+        for key in keys.iter() {
+            let probs: Vec<String> = self._table.get(*key).unwrap().iter().map(|p| p.to_string()).collect();
+            try!(file.write_line(format!("{}\t{}", key, probs.connect(" ")).as_slice()).map_err(Error::Io));
+        }
+        Ok(())
+    }
+}
+
+
+/// Plays according to a strategy table, and at random when the table doesn't
+/// cover the current position.
+pub struct EquilibriumAgent {
+    _strategy: Strategy,
+    _rng: RefCell<XorShiftRng>,
+}
+
+
+impl EquilibriumAgent {
+    pub fn new(strategy: Strategy) -> EquilibriumAgent {
+        EquilibriumAgent { _strategy: strategy, _rng: RefCell::new(rand::thread_rng().gen()) }
+    }
+
+    pub fn from_seed(strategy: Strategy, seed: [u32; 4]) -> EquilibriumAgent {
+        EquilibriumAgent { _strategy: strategy, _rng: RefCell::new(SeedableRng::from_seed(seed)) }
+    }
+}
+
+
+impl Agent for EquilibriumAgent {
+    fn choose(&self, view: &PlayerView) -> (Card, Play) {
+        let plays = view.legal_plays();
+        let mut rng = self._rng.borrow_mut();
+        match self._strategy.probabilities(view) {
+            Some(probs) if probs.len() == plays.len() => plays[sample(&mut *rng, probs)],
+            _ => *rng.choose(plays.as_slice()).expect("No legal plays"),
+        }
+    }
+}
+
+
+/// Estimate how exploitable `agent` is, by trying to beat it.
+///
+/// Plays `num_rounds` rounds from each seat against `agent`. At each of its
+/// decisions, the challenger tries every legal play against `num_samples`
+/// guesses at the hidden cards, finishes each of those rounds with `agent`
+/// playing both sides, and makes whichever play did best. The guesses are
+/// drawn uniformly from the cards the challenger hasn't seen.
+///
+/// Returns the challenger's average payoff, between -1 and 1. An
+/// equilibrium strategy can't be beaten, so would score 0. This is a lower
+/// bound on the true exploitability: a smarter challenger might do better.
+pub fn estimate_exploitability(agent: &Agent, num_rounds: usize, num_samples: usize, seed: [u32; 4]) -> f64 {
+    let mut rng: XorShiftRng = SeedableRng::from_seed(seed);
+    let players: Vec<PlayerId> = player_id_generator().take(NUM_PLAYERS).collect();
+    let mut total = 0.0;
+    for _ in range(0, num_rounds) {
+        for &challenger in players.iter() {
+            let deck = deck::Deck::with_rng(&mut rng);
+            let mut round = Round::from_deck(players.as_slice(), deck);
+            loop {
+                let (ready, turn) = match round.peek_turn() {
+                    None => break,
+                    Some(x) => x,
+                };
+                let view = ready.view(turn.player).ok().expect("Current player not in round");
+                let (card, play) = if turn.busts() {
+                    (turn.hand, Play::NoEffect)
+                } else if turn.player == challenger {
+                    best_response(agent, &view, num_samples, &mut rng)
+                } else {
+                    agent.choose(&view)
+                };
+                round = apply(&round, card, play);
+            }
+            total += payoff(&round, challenger);
+        }
+    }
+    total / ((num_rounds * NUM_PLAYERS) as f64)
+}
+
+
+/// Find the play that does best against `agent`, by trying each play against
+/// random guesses at the hidden cards.
+fn best_response<R: Rng>(agent: &Agent, view: &PlayerView, num_samples: usize, rng: &mut R) -> (Card, Play) {
+    let plays = view.legal_plays();
+    let mut totals: Vec<f64> = plays.iter().map(|_| 0.0).collect();
+    for _ in range(0, num_samples) {
+        let round = determinize(view, rng);
+        for (i, &(card, play)) in plays.iter().enumerate() {
+            let finished = agent::play_round(&apply(&round, card, play), &[agent, agent]);
+            totals[i] += payoff(&finished, view.player);
+        }
+    }
+    let mut best = 0;
+    for (i, &total) in totals.iter().enumerate() {
+        if total > totals[best] {
+            best = i;
+        }
+    }
+    plays[best]
+}
+
+
+/// Make up a round consistent with `view`, dealing the unseen cards at
+/// random.
+pub fn determinize<R: Rng>(view: &PlayerView, rng: &mut R) -> Round {
+    let mut unseen = view.unseen_cards();
+    rng.shuffle(unseen.as_mut_slice());
+    let mut hands = vec![];
+    for player in view.players.iter() {
+        if player.active && player.id != view.player {
+            hands.push((player.id, unseen.pop().expect("Not enough unseen cards")));
+        }
+    }
+    let deck = unseen.slice_to(view.deck_size);
+    Round::from_view(view, hands.as_slice(), deck).ok().expect("Could not rebuild round from view")
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::rand::{SeedableRng, XorShiftRng};
+
+    use agent::{Agent, RandomAgent};
+    use deck::Card;
+    use game;
+    use super::{Solver, EquilibriumAgent, determinize, estimate_exploitability, info_set_key, normalize};
+
+    #[test]
+    fn test_normalize_zero_is_uniform() {
+        assert_eq!(vec![0.25, 0.25, 0.25, 0.25], normalize(&[0.0, 0.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(vec![0.25, 0.75], normalize(&[1.0, 3.0]));
+    }
+
+    #[test]
+    fn test_info_set_key() {
+        let game = game::new_game(2).unwrap();
+        let round = game.new_round();
+        let (ready, turn) = round.peek_turn().unwrap();
+        let view = ready.view(turn.player).unwrap();
+        let expected = format!(
+            "0:{}{}|||{}", turn.hand as usize + 1, turn.draw as usize + 1, view.deck_size);
+        assert_eq!(expected, info_set_key(&view));
+    }
+
+    #[test]
+    fn test_determinize_consistent_with_view() {
+        let game = game::new_game(3).unwrap();
+        let round = game.new_round();
+        let (ready, turn) = round.peek_turn().unwrap();
+        let view = ready.view(turn.player).unwrap();
+        let mut rng: XorShiftRng = SeedableRng::from_seed([1, 2, 3, 4]);
+        let guess = determinize(&view, &mut rng);
+        let (guess_ready, guess_turn) = guess.peek_turn().unwrap();
+        assert_eq!(turn, guess_turn);
+        assert_eq!(view, guess_ready.view(turn.player).unwrap());
+    }
+
+    #[test]
+    fn test_solver_strategies_are_distributions() {
+        let mut solver = Solver::from_seed([1, 2, 3, 4]);
+        solver.iterate(20);
+        assert_eq!(20, solver.iterations());
+        let strategy = solver.strategy();
+        assert_eq!(solver.num_info_sets(), strategy.len());
+        for probs in strategy._table.values() {
+            let total = probs.iter().fold(0.0, |acc, &p| acc + p);
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_equilibrium_agent_plays_legally() {
+        let mut solver = Solver::from_seed([1, 2, 3, 4]);
+        solver.iterate(20);
+        let agent = EquilibriumAgent::from_seed(solver.strategy(), [4, 3, 2, 1]);
+        let game = game::new_game(2).unwrap();
+        let round = game.new_round();
+        let (ready, turn) = round.peek_turn().unwrap();
+        let view = ready.view(turn.player).unwrap();
+        assert!(view.legal_plays().contains(&agent.choose(&view)));
+    }
+
+    #[test]
+    fn test_random_agent_is_exploitable() {
+        let agent = RandomAgent::from_seed([1, 2, 3, 4]);
+        let exploitability = estimate_exploitability(&agent as &Agent, 20, 2, [5, 6, 7, 8]);
+        assert!(exploitability > 0.0);
+        assert!(exploitability <= 1.0);
+    }
+
+    #[test]
+    fn test_card_code() {
+        assert_eq!("1", super::card_code(Some(Card::Soldier)).as_slice());
+        assert_eq!("8", super::card_code(Some(Card::Princess)).as_slice());
+        assert_eq!("-", super::card_code(None).as_slice());
+    }
+}
@@ -0,0 +1,138 @@
+/// Talking at the table.
+///
+/// Players can say what they like, within reason, or send one of a fixed
+/// set of emotes. Either way, nobody gets to flood the table.
+
+use std::fmt;
+use std::str;
+
+
+/// The longest chat message we'll pass on, in characters.
+pub const MAX_CHAT_LENGTH: usize = 200;
+
+/// How many chat messages or emotes a player can send in a row...
+pub const CHAT_BURST: usize = 5;
+
+/// ... within this many seconds.
+pub const CHAT_WINDOW: u64 = 10;
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+pub enum Emote {
+    Smile,
+    Laugh,
+    Gasp,
+    Sigh,
+    Think,
+    Bow,
+}
+
+
+/// Every emote, in the order they should be offered.
+pub const EMOTES: [Emote; 6] = [
+    Emote::Smile, Emote::Laugh, Emote::Gasp, Emote::Sigh, Emote::Think, Emote::Bow];
+
+
+impl fmt::String for Emote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Emote::Smile => "smile",
+            Emote::Laugh => "laugh",
+            Emote::Gasp => "gasp",
+            Emote::Sigh => "sigh",
+            Emote::Think => "think",
+            Emote::Bow => "bow",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+
+impl str::FromStr for Emote {
+    fn from_str(name: &str) -> Option<Emote> {
+        match name {
+            "smile" => Some(Emote::Smile),
+            "laugh" => Some(Emote::Laugh),
+            "gasp" => Some(Emote::Gasp),
+            "sigh" => Some(Emote::Sigh),
+            "think" => Some(Emote::Think),
+            "bow" => Some(Emote::Bow),
+            _ => None,
+        }
+    }
+}
+
+
+/// Tidy up what someone wants to say. Returns `None` if there's nothing
+/// left to say.
+pub fn clean_message(text: &str) -> Option<String> {
+    let text: String = text
+        .trim()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_CHAT_LENGTH)
+        .collect();
+    if text.len() > 0 { Some(text) } else { None }
+}
+
+
+#[derive(Show, Clone)]
+/// Keeps track of how much someone has said recently.
+pub struct RateLimit {
+    /// When each recent message was sent, oldest first.
+    _sent: Vec<u64>,
+}
+
+
+impl RateLimit {
+    pub fn new() -> RateLimit {
+        RateLimit { _sent: vec![] }
+    }
+
+    /// Try to send a message at time `now`. Returns whether it's allowed.
+    pub fn allow(&mut self, now: u64) -> bool {
+        self._sent.retain(|&at| at + CHAT_WINDOW > now);
+        if self._sent.len() < CHAT_BURST {
+            self._sent.push(now);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::iter::repeat;
+    use std::str;
+
+    use super::{clean_message, Emote, EMOTES, MAX_CHAT_LENGTH, CHAT_BURST, CHAT_WINDOW, RateLimit};
+
+    #[test]
+    fn test_emote_names() {
+        for &emote in EMOTES.iter() {
+            assert_eq!(Some(emote), str::FromStr::from_str(format!("{}", emote).as_slice()));
+        }
+        assert_eq!(None, str::FromStr::from_str("wink").map(|e: Emote| e));
+    }
+
+    #[test]
+    fn test_clean_message() {
+        assert_eq!(None, clean_message("   "));
+        assert_eq!(Some("hello there".to_string()), clean_message(" hello\x07 there\n"));
+        let long: String = repeat('a').take(MAX_CHAT_LENGTH + 10).collect();
+        assert_eq!(MAX_CHAT_LENGTH, clean_message(long.as_slice()).unwrap().len());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut limit = RateLimit::new();
+        for _ in range(0, CHAT_BURST) {
+            assert!(limit.allow(100));
+        }
+        assert!(!limit.allow(100));
+        assert!(!limit.allow(100 + CHAT_WINDOW - 1));
+        assert!(limit.allow(100 + CHAT_WINDOW));
+    }
+}
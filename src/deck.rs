@@ -1,6 +1,7 @@
 use std::rand;
 use std::rand::Rng;
 use std::slice;
+use std::str;
 
 use util;
 
@@ -20,6 +21,27 @@ pub enum Card {
 }
 
 
+impl str::FromStr for Card {
+    /// Parse the name of a card, e.g. "Soldier".
+    fn from_str(name: &str) -> Option<Card> {
+        CARDS.iter().find(|c| format!("{:?}", c).as_slice() == name).map(|&c| c)
+    }
+}
+
+
+/// Every kind of card, from lowest to highest.
+pub const CARDS: [Card; 8] = [
+    Card::Soldier,
+    Card::Clown,
+    Card::Knight,
+    Card::Priestess,
+    Card::Wizard,
+    Card::General,
+    Card::Minister,
+    Card::Princess,
+    ];
+
+
 const CARDS_IN_DECK: usize = 16;
 
 /// In the Love Letter deck, there are:
@@ -33,7 +55,7 @@ const CARDS_IN_DECK: usize = 16;
 /// - 1 Princess
 ///
 /// Altogether, there are 16 cards.
-const DECK: [Card; CARDS_IN_DECK] = [
+pub const DECK: [Card; CARDS_IN_DECK] = [
     Card::Soldier,
     Card::Soldier,
     Card::Soldier,
@@ -84,11 +106,21 @@ impl Deck {
         }
     }
 
+    /// Returns a new deck, shuffled with the given random number generator.
+    pub fn with_rng<R: Rng>(rng: &mut R) -> Deck {
+        Deck::from_slice(&DECK).unwrap().shuffled_with(rng)
+    }
+
     /// Return a shuffled version of this deck.
     pub fn shuffled(&self) -> Deck {
+        self.shuffled_with(&mut rand::thread_rng())
+    }
+
+    /// Return a version of this deck shuffled with the given random number
+    /// generator.
+    pub fn shuffled_with<R: Rng>(&self, rng: &mut R) -> Deck {
         let &Deck(ref cards) = self;
         let mut new_cards = cards.clone();
-        let mut rng = rand::thread_rng();
         rng.shuffle(new_cards.as_mut_slice());
         Deck(new_cards)
     }
@@ -124,6 +156,7 @@ pub fn is_valid_subdeck(cards: &[Card]) -> bool {
 
 #[cfg(test)]
 mod test {
+    use std::rand::{SeedableRng, StdRng};
     use super::{Card, DECK, Deck, DeckError};
 
     #[test]
@@ -153,6 +186,16 @@ mod test {
         assert_eq!(old_cards.as_slice(), new_cards.as_slice());
     }
 
+    #[test]
+    fn test_deck_with_rng_repeatable() {
+        let seed: &[_] = &[1, 2, 3, 4];
+        let mut rng1: StdRng = SeedableRng::from_seed(seed);
+        let mut rng2: StdRng = SeedableRng::from_seed(seed);
+        let Deck(cards1) = Deck::with_rng(&mut rng1);
+        let Deck(cards2) = Deck::with_rng(&mut rng2);
+        assert_eq!(cards1, cards2);
+    }
+
     #[test]
     fn test_deck_fixed_good() {
         match Deck::from_slice(DECK.as_slice()) {
@@ -241,6 +284,12 @@ mod test {
         assert_eq!(*cards, new_cards);
     }
 
+    #[test]
+    fn test_card_from_str() {
+        assert_eq!(Some(Card::Priestess), "Priestess".parse());
+        assert_eq!(None, "Queen".parse::<Card>());
+    }
+
     #[test]
     fn test_card_ordering() {
         assert!(Card::Soldier <= Card::Soldier);
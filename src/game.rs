@@ -4,29 +4,96 @@
 /// round receive a token of affection from the princess. The first players to
 /// receive four tokens of affection are declared to have won her heart, and
 /// thus, the game.
+///
+/// Each game has a `Roster`, saying what to call its players, and can keep
+/// a record of each round played, so that it can be written down and played
+/// again.
+
+use std::fmt;
+use std::rand::{Rng, SeedableRng, XorShiftRng};
+use std::str;
 
+use deck;
 use player_id;
 use player_id::{PlayerId, Players};
+use record::RoundRecord;
+use roster::Roster;
 use round;
 
 
-// XXX: Should this be a configuration parameter?
+/// How many tokens it takes to win a quick game.
 const WINNING_SCORE: u32 = 4;
 
 
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+/// Ways of deciding when the game is over.
+pub enum Variant {
+    /// The first to four tokens wins, however many are playing.
+    Quick,
+    /// As in the printed rules: seven tokens to win with two players, five
+    /// with three and four with four.
+    Classic,
+}
+
+
+impl Variant {
+    /// The number of tokens needed to win with `num_players` players.
+    pub fn winning_score(&self, num_players: usize) -> u32 {
+        match (*self, num_players) {
+            (Variant::Quick, _) => WINNING_SCORE,
+            (Variant::Classic, 2) => 7,
+            (Variant::Classic, 3) => 5,
+            (Variant::Classic, _) => 4,
+        }
+    }
+}
+
+
+impl fmt::String for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Variant::Quick => write!(f, "quick"),
+            Variant::Classic => write!(f, "classic"),
+        }
+    }
+}
+
+
+impl str::FromStr for Variant {
+    fn from_str(name: &str) -> Option<Variant> {
+        match name {
+            "quick" => Some(Variant::Quick),
+            "classic" => Some(Variant::Classic),
+            _ => None,
+        }
+    }
+}
+
+
 #[derive(Clone)]
 pub struct Game {
     // XXX: Possibly Game should not own Config. In the only current non-test
     // use case, Config can easily last longer than Game. The only reason we
     // want to own this is for the helper `make_game` function.
     _players: Vec<(PlayerId, u32)>,
+    _variant: Variant,
+    _winning_score: u32,
+    _roster: Roster,
+    _history: Vec<RoundRecord>,
 }
 
 
 impl Game {
-    fn new(players: Players) -> Game {
-        let players: Vec<(PlayerId, u32)> = players.iter().map(|&p| (p, 0)).collect();
-        Game { _players: players }
+    fn new(players: Players, variant: Variant) -> Game {
+        let ids: Vec<PlayerId> = players.iter().map(|&p| p).collect();
+        let players: Vec<(PlayerId, u32)> = ids.iter().map(|&p| (p, 0)).collect();
+        Game {
+            _winning_score: variant.winning_score(players.len()),
+            _players: players,
+            _variant: variant,
+            _roster: Roster::new(ids.as_slice()),
+            _history: vec![],
+        }
     }
 
     fn num_players(&self) -> usize {
@@ -46,6 +113,21 @@ impl Game {
         }
     }
 
+    /// Like `next_round`, but shuffling the deck with `rng`.
+    pub fn next_round_with_rng<R: Rng>(&self, rng: &mut R) -> Option<round::Round> {
+        self.next_round_from_deck(deck::Deck::with_rng(rng))
+    }
+
+    /// Like `next_round`, but dealing from `deck`, which must already be
+    /// shuffled.
+    pub fn next_round_from_deck(&self, deck: deck::Deck) -> Option<round::Round> {
+        if self.winners().len() == 0 {
+            Some(round::Round::from_deck(self.players().as_slice(), deck))
+        } else {
+            None
+        }
+    }
+
     pub fn players(&self) -> Vec<PlayerId> {
         self._players.iter().map(|&(p, _)| p).collect()
     }
@@ -54,9 +136,61 @@ impl Game {
         self._players.iter().map(|&(_, x)| x).collect()
     }
 
+    /// The number of tokens needed to win.
+    pub fn winning_score(&self) -> u32 {
+        self._winning_score
+    }
+
+    pub fn variant(&self) -> Variant {
+        self._variant
+    }
+
+    /// Every round that's been recorded, oldest first.
+    pub fn history(&self) -> &[RoundRecord] {
+        self._history.as_slice()
+    }
+
+    /// This game, with `round` added to its history. Doesn't change anyone's
+    /// score: that's what `players_won` is for.
+    pub fn record_round(&self, round: RoundRecord) -> Game {
+        let mut new_game = self.clone();
+        new_game._history.push(round);
+        new_game
+    }
+
+    /// What to call the players.
+    pub fn roster(&self) -> &Roster {
+        &self._roster
+    }
+
+    /// This game, with its players called whatever `roster` says. Returns
+    /// `None` unless `roster` has the same players, in the same order.
+    pub fn with_roster(&self, roster: Roster) -> Option<Game> {
+        if roster.players() != self.players() {
+            return None;
+        }
+        let mut new_game = self.clone();
+        new_game._roster = roster;
+        Some(new_game)
+    }
+
+    /// This game, with everyone's scores replaced by `scores`, in order of
+    /// play. Returns `None` if there isn't one score per player.
+    pub fn with_scores(&self, scores: &[u32]) -> Option<Game> {
+        if scores.len() != self.num_players() {
+            return None;
+        }
+        let mut new_game = self.clone();
+        for (player, &score) in new_game._players.iter_mut().zip(scores.iter()) {
+            let (id, _) = *player;
+            *player = (id, score);
+        }
+        Some(new_game)
+    }
+
     fn player_won_mut(&mut self, player_id: PlayerId) {
         // XXX: Will panic if player_id wrong
-        // XXX: What if score exceeds WINNING_SCORE
+        // XXX: What if score exceeds the winning score
         let idx = self._players
             .iter()
             .position(|&(id, _)| id == player_id)
@@ -78,26 +212,61 @@ impl Game {
         new_game
     }
 
-    fn winners(&self) -> Vec<PlayerId> {
+    /// Has somebody won the game?
+    pub fn is_over(&self) -> bool {
+        self.winners().len() != 0
+    }
+
+    /// The players who have won the game. Empty if the game is not over.
+    pub fn winners(&self) -> Vec<PlayerId> {
         self._players
             .iter()
-            .filter_map(|&(i, n)| if n >= WINNING_SCORE { Some(i) } else { None })
+            .filter_map(|&(i, n)| if n >= self._winning_score { Some(i) } else { None })
             .collect()
     }
 }
 
 
-/// Create a new game with the given number of arbitrary players.
+/// A random number generator that always deals the same rounds for the same
+/// `seed`, so that games can be played again.
+pub fn seeded_rng(seed: u32) -> XorShiftRng {
+    // XorShift can't be seeded with all zeroes.
+    SeedableRng::from_seed([0x9e3779b9, 0x243f6a88, 0xb7e15162, seed])
+}
+
+
+/// Create a new quick game with the given number of arbitrary players.
 pub fn new_game(num_players: usize) -> Option<Game> {
-    player_id::make_players(num_players).map(|players| Game::new(players))
+    new_game_with_variant(num_players, Variant::Quick)
+}
+
+
+/// Create a new game with the given number of arbitrary players, played to
+/// the rules of `variant`.
+pub fn new_game_with_variant(num_players: usize, variant: Variant) -> Option<Game> {
+    player_id::make_players(num_players)
+        .map(|players| Game::new(players, variant))
+}
+
+
+/// Create a new game for players with the given names, in order of play,
+/// played to the rules of `variant`.
+pub fn new_game_with_names(names: &[String], variant: Variant) -> Option<Game> {
+    new_game_with_variant(names.len(), variant).and_then(|game| {
+        let roster = Roster::with_names(game.players().as_slice(), names).expect("One name per player");
+        game.with_roster(roster)
+    })
 }
 
 
 #[cfg(test)]
 mod test {
 
+    use std::str;
+
     use player_id::{player_id_generator, PlayerId, Players};
-    use super::Game;
+    use roster::Roster;
+    use super::{Game, Variant};
 
     // XXX: Duplicated from round.rs
     fn make_player_ids(num_players: usize) -> Vec<PlayerId> {
@@ -105,7 +274,7 @@ mod test {
     }
 
     fn make_game_from_players(players: &[PlayerId]) -> Game {
-        Players::new(players.as_slice()).map(|players| Game::new(players)).ok().unwrap()
+        Players::new(players.as_slice()).map(|players| Game::new(players, Variant::Quick)).ok().unwrap()
     }
 
     fn make_game(num_players: usize) -> Game {
@@ -166,4 +335,64 @@ mod test {
         assert_eq!(vec![], game.winners());
     }
 
+    #[test]
+    fn game_over_after_winning_score() {
+        let players = make_player_ids(2);
+        let mut game = make_game_from_players(players.as_slice());
+        for _ in range(0, super::WINNING_SCORE) {
+            assert!(!game.is_over());
+            game.player_won_mut(players[0]);
+        }
+        assert!(game.is_over());
+        assert_eq!(vec![players[0]], game.winners());
+    }
+
+    #[test]
+    fn classic_winning_scores() {
+        assert_eq!(7, super::new_game_with_variant(2, Variant::Classic).unwrap().winning_score());
+        assert_eq!(5, super::new_game_with_variant(3, Variant::Classic).unwrap().winning_score());
+        assert_eq!(4, super::new_game_with_variant(4, Variant::Classic).unwrap().winning_score());
+        assert_eq!(4, make_game(2).winning_score());
+    }
+
+    #[test]
+    fn variant_names() {
+        for &variant in [Variant::Quick, Variant::Classic].iter() {
+            assert_eq!(Some(variant), str::FromStr::from_str(format!("{}", variant).as_slice()));
+        }
+        assert_eq!(None, str::FromStr::from_str("long").map(|v: Variant| v));
+    }
+
+    #[test]
+    fn test_with_scores() {
+        let game = make_game(3);
+        assert_eq!(None, game.with_scores(&[1, 2]).map(|g| g.scores()));
+        let game = game.with_scores(&[1, 4, 2]).unwrap();
+        assert_eq!(vec![1, 4, 2], game.scores());
+        assert_eq!(vec![game.players()[1]], game.winners());
+    }
+
+    #[test]
+    fn test_roster() {
+        let names = vec!["alice".to_string(), "bob".to_string(), "carol".to_string()];
+        let game = super::new_game_with_names(names.as_slice(), Variant::Quick).unwrap();
+        assert_eq!(names, game.roster().names());
+        // Scores don't change the names.
+        let game = game.players_won(&[game.players()[2]]);
+        assert_eq!("carol", game.roster().name(game.players()[2]).as_slice());
+        let others = make_player_ids(4);
+        assert!(game.with_roster(Roster::new(others.as_slice())).is_none());
+        assert!(super::new_game_with_names(&["alice".to_string()], Variant::Quick).is_none());
+    }
+
+    #[test]
+    fn test_seeded_rounds() {
+        let game = make_game(3);
+        let mut rng1 = super::seeded_rng(42);
+        let mut rng2 = super::seeded_rng(42);
+        let first = game.next_round_with_rng(&mut rng1).unwrap();
+        assert_eq!(first, game.next_round_with_rng(&mut rng2).unwrap());
+        assert_eq!(3, first.num_players());
+        assert!(game.with_scores(&[4, 0, 0]).unwrap().next_round_with_rng(&mut rng1).is_none());
+    }
 }
@@ -0,0 +1,79 @@
+/// A reference to the cards and rules, for players who've forgotten them.
+///
+/// Everything that can be is worked out from what the engine itself uses:
+/// card values from `deck::CARDS`, how many of each there are from
+/// `deck::DECK`, and which cards bust the Minister from
+/// `round::minister_bust`. Only the words come from the catalogue.
+
+use deck::{Card, CARDS, DECK};
+use game::Game;
+use locale::Catalogue;
+use round::minister_bust;
+
+
+/// What `card` is worth at the end of a round: 1 for the Soldier, up to 8
+/// for the Princess.
+fn value(card: Card) -> usize {
+    CARDS.iter().position(|&c| c == card).expect("Card missing from CARDS") + 1
+}
+
+
+/// How many of `card` are in the deck.
+fn copies(card: Card) -> usize {
+    DECK.iter().filter(|&&c| c == card).count()
+}
+
+
+/// Every card, with its value, how many there are and what it does, and then
+/// the rules for a round of `num_players`. If the number of tokens needed to
+/// win isn't known, say so.
+pub fn reference(text: &Catalogue, num_players: usize, winning_score: Option<u32>) -> String {
+    let mut lines = vec![text.cards_heading()];
+    for &card in CARDS.iter() {
+        lines.push(format!("  {}. {} (x{}): {}", value(card), text.card(card), copies(card), text.effect(card)));
+    }
+    lines.push("".to_string());
+    lines.push(text.rules_heading());
+    let busts: Vec<&str> = CARDS.iter()
+        .filter(|&&c| c != Card::Minister && minister_bust(Card::Minister, c))
+        .map(|&c| text.card(c))
+        .collect();
+    lines.push(format!("  {}", text.minister_rule(busts.connect(", ").as_slice())));
+    lines.push(format!("  {}", text.set_aside_rule(num_players)));
+    lines.push(format!("  {}", text.token_target(winning_score)));
+    lines.connect("\n")
+}
+
+
+/// The reference for a local `game`, which also says which variant is
+/// being played.
+pub fn game_reference(text: &Catalogue, game: &Game) -> String {
+    format!("{}\n  {}",
+            reference(text, game.players().len(), Some(game.winning_score())),
+            text.variant_rule(format!("{}", game.variant()).as_slice()))
+}
+
+
+#[cfg(test)]
+mod test {
+    use deck::Card;
+    use locale::Language;
+    use super::{copies, reference, value};
+
+    #[test]
+    fn test_values_and_copies() {
+        assert_eq!(1, value(Card::Soldier));
+        assert_eq!(8, value(Card::Princess));
+        assert_eq!(5, copies(Card::Soldier));
+        assert_eq!(1, copies(Card::Minister));
+    }
+
+    #[test]
+    fn test_reference() {
+        let help = reference(Language::English.catalogue(), 2, Some(7));
+        assert!(help.contains("1. Soldier (x5): "));
+        assert!(help.contains("8. Princess (x1): "));
+        assert!(help.contains("Wizard, General, Princess"));
+        assert!(help.contains(" 7 "));
+    }
+}
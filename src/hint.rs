@@ -0,0 +1,143 @@
+/// Hints for a player trying to decide what to play.
+///
+/// Everything here comes from the player's own `PlayerView`, so hints never
+/// give away anything the player couldn't have worked out for themselves.
+
+use action;
+use action::{Play, PlayError};
+use agent::Agent;
+use deck;
+use deck::Card;
+use player_id::PlayerId;
+use round;
+use view::PlayerView;
+
+
+#[derive(Show, Clone, PartialEq)]
+pub struct Hints {
+    /// How many of each kind of card the player hasn't seen yet, lowest
+    /// first. Kinds they've seen all of are left out.
+    pub unseen: Vec<(Card, usize)>,
+    /// Opponents who are protected by the Priestess.
+    pub protected: Vec<PlayerId>,
+    /// Opponents who are out of the round.
+    pub eliminated: Vec<PlayerId>,
+    /// Plays the engine won't accept, with the reason why. There's one entry
+    /// for each card and reason, rather than for every possible play.
+    pub illegal: Vec<(Card, PlayError)>,
+    /// If the player can keep the Minister, the chance that their next draw
+    /// will bust them out.
+    pub minister_risk: Option<f64>,
+    /// What `agent` would play.
+    pub suggestion: (Card, Play),
+}
+
+
+impl Hints {
+    /// Work out hints for the player whose view this is, suggesting what
+    /// `agent` would play.
+    ///
+    /// `view` must be from the point of view of the player whose turn it is.
+    pub fn new(view: &PlayerView, agent: &Agent) -> Hints {
+        let unseen_cards = view.unseen_cards();
+        let unseen: Vec<(Card, usize)> = deck::CARDS
+            .iter()
+            .map(|&card| (card, unseen_cards.iter().filter(|&&c| c == card).count()))
+            .filter(|&(_, n)| n > 0)
+            .collect();
+        let opponents: Vec<_> = view.players.iter().filter(|p| p.id != view.player).collect();
+        Hints {
+            unseen: unseen,
+            protected: opponents.iter().filter(|p| p.active && p.protected).map(|p| p.id).collect(),
+            eliminated: opponents.iter().filter(|p| !p.active).map(|p| p.id).collect(),
+            illegal: illegal_plays(view),
+            minister_risk: minister_risk(view, unseen_cards.as_slice()),
+            suggestion: agent.choose(view),
+        }
+    }
+}
+
+
+/// Try every sort of play with the cards the viewer is holding, and find out
+/// why the ones that won't work are rejected.
+fn illegal_plays(view: &PlayerView) -> Vec<(Card, PlayError)> {
+    let mut cards: Vec<Card> = view.hand.iter().chain(view.draw.iter()).map(|&x| x).collect();
+    cards.dedup();
+    let mut illegal = vec![];
+    for &card in cards.iter() {
+        if action::plays_for_card(view.player, card, &[view.player]) == vec![Play::NoEffect] {
+            // Cards that don't target anyone can always be played.
+            continue;
+        }
+        for player in view.players.iter() {
+            let mut plays = vec![Play::Attack(player.id)];
+            for &guess in deck::CARDS.iter() {
+                plays.push(Play::Guess(player.id, guess));
+            }
+            for &play in plays.iter() {
+                let error = match action::play_to_action(view.player, card, play) {
+                    // Playing a card the wrong way is a mistake the command
+                    // line won't let you make, so isn't worth mentioning.
+                    Err(PlayError::BadActionForCard(..)) => continue,
+                    Err(e) => e,
+                    Ok(_) if !player.active => PlayError::InactivePlayer(player.id),
+                    Ok(_) => continue,
+                };
+                if !illegal.contains(&(card, error)) {
+                    illegal.push((card, error));
+                }
+            }
+        }
+    }
+    illegal
+}
+
+
+/// If the viewer can keep the Minister, the chance that they'll draw a card
+/// that busts them out next turn.
+fn minister_risk(view: &PlayerView, unseen: &[Card]) -> Option<f64> {
+    match (view.hand, view.draw) {
+        (Some(Card::Minister), Some(_)) | (Some(_), Some(Card::Minister)) => (),
+        _ => return None,
+    }
+    if unseen.len() == 0 {
+        return None;
+    }
+    let busting = unseen.iter().filter(|&&c| round::minister_bust(Card::Minister, c)).count();
+    Some(busting as f64 / unseen.len() as f64)
+}
+
+
+#[cfg(test)]
+mod test {
+    use action::{Play, PlayError};
+    use agent::HeuristicAgent;
+    use deck::Card;
+    use player_id::player_id_generator;
+    use round::Round;
+    use super::Hints;
+
+    #[test]
+    fn test_hints() {
+        let players: Vec<_> = player_id_generator().take(3).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Minister)),
+              (players[1], Some(Card::Soldier)),
+              (players[2], None)],
+            &[Card::Clown, Card::Soldier], None).unwrap();
+        let (ready, turn) = round.peek_turn().unwrap();
+        let view = ready.view(turn.player).unwrap();
+        let hints = Hints::new(&view, &HeuristicAgent);
+        assert_eq!(vec![players[2]], hints.eliminated);
+        assert_eq!(vec![], hints.protected);
+        assert!(hints.illegal.contains(&(Card::Soldier, PlayError::SelfTarget(players[0], Card::Soldier))));
+        assert!(hints.illegal.contains(&(Card::Soldier, PlayError::InactivePlayer(players[2]))));
+        assert!(hints.illegal.contains(&(Card::Soldier, PlayError::BadGuess)));
+        // Minister and a Soldier seen; the Wizards, General and Princess
+        // would bust, out of 14 unseen cards.
+        assert_eq!(Some(4.0 / 14.0), hints.minister_risk);
+        assert!(!hints.illegal.iter().any(|&(c, _)| c == Card::Minister));
+        assert_eq!((Card::Soldier, Play::Guess(players[1], Card::Clown)), hints.suggestion);
+        assert_eq!(Some(&(Card::Soldier, 4)), hints.unseen.iter().find(|&&(c, _)| c == Card::Soldier));
+    }
+}
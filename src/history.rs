@@ -0,0 +1,173 @@
+/// A round, along with every state it's been in since it was dealt.
+///
+/// A `Round` never changes: playing a turn makes a new one. Keeping the old
+/// ones around means turns can be taken back, and the round wound back to
+/// how it was after any turn.
+
+use round::{Round, TurnOutcome};
+
+
+#[derive(Show, Clone)]
+pub struct RoundHistory {
+    /// The round as dealt, then after each turn.
+    _states: Vec<Round>,
+    /// Every turn played, in order.
+    _turns: Vec<TurnOutcome>,
+}
+
+
+impl RoundHistory {
+    /// Start keeping track of `round`, which hasn't had any turns yet.
+    pub fn new(round: Round) -> RoundHistory {
+        RoundHistory { _states: vec![round], _turns: vec![] }
+    }
+
+    /// The round as it is now.
+    pub fn current(&self) -> &Round {
+        &self._states[self._states.len() - 1]
+    }
+
+    /// Every turn played so far, oldest first.
+    pub fn turns(&self) -> &[TurnOutcome] {
+        self._turns.as_slice()
+    }
+
+    pub fn num_turns(&self) -> usize {
+        self._turns.len()
+    }
+
+    /// Move on to `round`, which is what `outcome` made of the current round.
+    pub fn push(&mut self, round: Round, outcome: TurnOutcome) {
+        self._states.push(round);
+        self._turns.push(outcome);
+    }
+
+    /// The round as it was after `turn` turns, where 0 is as it was dealt.
+    /// Returns `None` if there haven't been that many turns.
+    pub fn at(&self, turn: usize) -> Option<&Round> {
+        self._states.get(turn)
+    }
+
+    /// Take back every turn after the first `turn`. Returns `false`, and
+    /// changes nothing, if there haven't been that many turns.
+    pub fn rewind(&mut self, turn: usize) -> bool {
+        if turn > self.num_turns() {
+            return false;
+        }
+        self._states.truncate(turn + 1);
+        self._turns.truncate(turn);
+        true
+    }
+
+    /// Take back the last turn. Returns what it was, or `None` if nobody's
+    /// played yet.
+    pub fn undo(&mut self) -> Option<TurnOutcome> {
+        let last = self._turns.last().map(|t| t.clone());
+        if last.is_some() {
+            let turns = self.num_turns();
+            self.rewind(turns - 1);
+        }
+        last
+    }
+
+    /// Where in `turns()` the last turn is that a player chose how to play.
+    /// Busting out with the Minister isn't a choice.
+    pub fn last_decision(&self) -> Option<usize> {
+        self._turns.iter().rposition(|t| match *t {
+            TurnOutcome::Played(..) => true,
+            TurnOutcome::BustedOut(..) => false,
+        })
+    }
+
+    /// Take back the last turn a player chose how to play, along with any
+    /// busts since, which would only happen again. Returns the turn taken
+    /// back, or `None` if nobody's chosen anything yet.
+    pub fn undo_decision(&mut self) -> Option<TurnOutcome> {
+        let turn = match self.last_decision() {
+            Some(turn) => turn,
+            None => return None,
+        };
+        let outcome = self._turns[turn].clone();
+        self.rewind(turn);
+        Some(outcome)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use action::Play;
+    use deck::Card;
+    use player_id::{player_id_generator, PlayerId};
+    use round::{Round, TurnOutcome};
+    use super::RoundHistory;
+
+    /// Play a turn of `history`'s current round, always the Priestess.
+    fn play_priestess(history: &mut RoundHistory) -> TurnOutcome {
+        let (round, outcome) = history.current()
+            .handle_turn(|_, _| (Card::Priestess, Play::NoEffect), |_, _| ())
+            .unwrap().unwrap();
+        history.push(round, outcome.clone());
+        outcome
+    }
+
+    fn make_round() -> (Vec<PlayerId>, Round) {
+        let players: Vec<PlayerId> = player_id_generator().take(2).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Priestess)), (players[1], Some(Card::Priestess))],
+            &[Card::Soldier, Card::Soldier, Card::Soldier], None).unwrap();
+        (players, round)
+    }
+
+    #[test]
+    fn test_undo() {
+        let (_, round) = make_round();
+        let mut history = RoundHistory::new(round.clone());
+        assert_eq!(None, history.undo());
+        let outcome = play_priestess(&mut history);
+        assert_eq!(1, history.num_turns());
+        assert!(history.current() != &round);
+        assert_eq!(Some(outcome), history.undo());
+        assert_eq!(&round, history.current());
+        assert_eq!(0, history.num_turns());
+    }
+
+    #[test]
+    fn test_undo_skips_busts() {
+        let players: Vec<PlayerId> = player_id_generator().take(2).collect();
+        // Player 1 plays the Priestess, then player 2 draws the Princess to
+        // go with their Minister.
+        let round = Round::from_manual(
+            &[(players[0], Some(Card::Priestess)), (players[1], Some(Card::Minister))],
+            &[Card::Soldier, Card::Princess, Card::Soldier], None).unwrap();
+        let mut history = RoundHistory::new(round.clone());
+        let played = play_priestess(&mut history);
+        let (after_bust, bust) = history.current()
+            .handle_turn(|_, _| panic!("Busted player asked to play"), |_, _| ())
+            .unwrap().unwrap();
+        history.push(after_bust, bust);
+        assert_eq!(Some(0), history.last_decision());
+        assert_eq!(Some(played), history.undo_decision());
+        assert_eq!(&round, history.current());
+        assert_eq!(None, history.undo_decision());
+    }
+
+    #[test]
+    fn test_rewind() {
+        let (players, round) = make_round();
+        let mut history = RoundHistory::new(round);
+        play_priestess(&mut history);
+        let after_one = history.current().clone();
+        play_priestess(&mut history);
+        assert_eq!(Some(&after_one), history.at(1));
+        assert!(!history.rewind(3));
+        assert_eq!(2, history.num_turns());
+        assert!(history.rewind(1));
+        assert_eq!(&after_one, history.current());
+        // The same player gets to play again.
+        match play_priestess(&mut history) {
+            TurnOutcome::Played(player, _, _, _) => assert_eq!(players[1], player),
+            other => panic!("Unexpected turn: {:?}", other),
+        }
+    }
+}
@@ -3,22 +3,47 @@
 
 /// loveletter: implementation of [Love Letter](http://boardgamegeek.com/boardgame/129622/love-letter)
 
+extern crate serialize;
+
 pub use action::{Event, Play, PlayError};
 pub use deck::Card;
 pub use game::Game;
 pub use player_id::PlayerId;
-pub use round::{Round, Turn, TurnOutcome};
+pub use roster::Roster;
+pub use round::{minister_bust, Round, RoundSnapshot, Turn, TurnOutcome};
 pub use round::Error as RoundError;
+pub use view::{PlayerView, PublicPlayer, SpectatorView};
 
+pub mod agent;
+pub mod analysis;
+pub mod cfr;
+pub mod chat;
 pub mod deck;
 pub mod game;
+pub mod help;
+pub mod hint;
+pub mod history;
+pub mod locale;
+pub mod lobby;
+pub mod notation;
 pub mod prompt;
+pub mod protocol;
+pub mod rating;
+pub mod record;
+pub mod roster;
+pub mod selfplay;
+pub mod server;
+pub mod storage;
+pub mod table;
+pub mod tui;
+pub mod websocket;
 
 mod action;
 mod round;
 mod player;
 mod player_id;
 mod util;
+mod view;
 
 
 #[cfg(test)]
@@ -0,0 +1,388 @@
+/// Many tables, each playing its own game.
+///
+/// Like `Table`, a `Lobby` knows nothing about networks. Tables are numbered
+/// in the order they were created. Every table has its own game, and so its
+/// own set of players: the player in seat 0 at one table has the same
+/// `PlayerId` as the player in seat 0 at any other.
+///
+/// Everyone who sits down gets a session token, which they can use to get
+/// their seat back if they lose their connection. Bots don't need one.
+
+use std::collections::{BTreeMap, HashMap};
+use std::rand::{Rng, thread_rng};
+
+use agent::AgentKind;
+use chat::Emote;
+use deck::Card;
+use game::Variant;
+use protocol::{SeatPlay, ServerMessage};
+use table;
+use table::{Outgoing, Table};
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+/// What someone in the lobby can see of a table.
+pub struct TableSummary {
+    pub id: usize,
+    pub num_players: usize,
+    pub variant: Variant,
+    /// Tokens needed to win.
+    pub winning_score: u32,
+    /// The name of the player in each seat, or `None` if it's free.
+    pub names: Vec<Option<String>>,
+    /// The first round has been dealt.
+    pub started: bool,
+    /// Somebody has won.
+    pub over: bool,
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+pub enum Error {
+    NoSuchTable(usize),
+    /// Love Letter can't be played by this many.
+    InvalidPlayers(usize),
+    /// Asked to join any table, but every table is full.
+    NoOpenTables,
+    /// Nobody has been given this session token.
+    UnknownSession,
+    Table(table::Error),
+}
+
+
+pub struct Lobby {
+    _tables: BTreeMap<usize, Table>,
+    _next_id: usize,
+    /// Which table and seat each session token is for.
+    _sessions: HashMap<String, (usize, usize)>,
+    _grace_period: u64,
+    _turn_limit: Option<u64>,
+    _omniscient_delay: Option<u64>,
+    _think_delay: u64,
+}
+
+
+impl Lobby {
+    pub fn new() -> Lobby {
+        Lobby::with_grace_period(table::DEFAULT_GRACE_PERIOD)
+    }
+
+    /// A lobby whose tables wait `seconds` for a disconnected player before
+    /// handing their seat to a bot.
+    pub fn with_grace_period(seconds: u64) -> Lobby {
+        Lobby {
+            _tables: BTreeMap::new(),
+            _next_id: 0,
+            _sessions: HashMap::new(),
+            _grace_period: seconds,
+            _turn_limit: None,
+            _omniscient_delay: None,
+            _think_delay: 0,
+        }
+    }
+
+    /// Give players at tables created from now on `seconds` to make each
+    /// play, or as long as they like if `None`.
+    pub fn set_turn_limit(&mut self, seconds: Option<u64>) {
+        self._turn_limit = seconds;
+    }
+
+    /// Make omniscient spectators at tables created from now on wait
+    /// `seconds` to see hands, or until the end of the round if `None`.
+    pub fn set_omniscient_delay(&mut self, seconds: Option<u64>) {
+        self._omniscient_delay = seconds;
+    }
+
+    /// Make bots at tables created from now on spend `seconds` thinking
+    /// about each play.
+    pub fn set_think_delay(&mut self, seconds: u64) {
+        self._think_delay = seconds;
+    }
+
+    /// Set up a new, empty table and return its number.
+    pub fn create(&mut self, num_players: usize, variant: Variant) -> Result<usize, Error> {
+        let mut table = match Table::with_variant(num_players, variant) {
+            Some(table) => table,
+            None => return Err(Error::InvalidPlayers(num_players)),
+        };
+        table.set_grace_period(self._grace_period);
+        table.set_turn_limit(self._turn_limit);
+        table.set_omniscient_delay(self._omniscient_delay);
+        table.set_think_delay(self._think_delay);
+        let id = self._next_id;
+        self._next_id += 1;
+        self._tables.insert(id, table);
+        Ok(id)
+    }
+
+    /// Put back table `id`, as it was before the server restarted, along
+    /// with the session tokens of the players sitting at it.
+    pub fn restore(&mut self, id: usize, mut table: Table, sessions: &[(String, usize)]) {
+        table.set_grace_period(self._grace_period);
+        table.set_turn_limit(self._turn_limit);
+        table.set_omniscient_delay(self._omniscient_delay);
+        table.set_think_delay(self._think_delay);
+        self._tables.insert(id, table);
+        if id >= self._next_id {
+            self._next_id = id + 1;
+        }
+        for &(ref token, seat) in sessions.iter() {
+            self._sessions.insert(token.clone(), (id, seat));
+        }
+    }
+
+    /// The session tokens for everyone sitting at table `id`, with their
+    /// seats.
+    pub fn sessions(&self, id: usize) -> Vec<(String, usize)> {
+        self._sessions
+            .iter()
+            .filter(|&(_, &(table, _))| table == id)
+            .map(|(token, &(_, seat))| (token.clone(), seat))
+            .collect()
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Table> {
+        self._tables.get(&id)
+    }
+
+    /// Every table whose game isn't over yet, oldest first.
+    ///
+    /// XXX: Finished tables are kept around forever.
+    pub fn list(&self) -> Vec<TableSummary> {
+        self._tables
+            .keys()
+            .filter_map(|&id| self.summary(id))
+            .filter(|summary| !summary.over)
+            .collect()
+    }
+
+    pub fn summary(&self, id: usize) -> Option<TableSummary> {
+        self._tables.get(&id).map(|table| TableSummary {
+            id: id,
+            num_players: table.num_players(),
+            variant: table.variant(),
+            winning_score: table.winning_score(),
+            names: table.names(),
+            started: table.is_started(),
+            over: table.is_over(),
+        })
+    }
+
+    /// Have all the games been won?
+    pub fn is_over(&self) -> bool {
+        self._tables.values().all(|table| table.is_over())
+    }
+
+    /// Sit `name` at table `id`, or at the oldest table with a free seat if
+    /// `id` is `None`. Returns the table, the seat, a session token, and the
+    /// messages to send to that table. The first round is dealt when the
+    /// table fills up.
+    pub fn join(&mut self, id: Option<usize>, name: &str)
+                -> Result<(usize, usize, String, Vec<Outgoing>), Error> {
+        let id = match id {
+            Some(id) => id,
+            None => match self._tables.iter().find(|&(_, t)| !t.is_full() && !t.is_started()) {
+                Some((&id, _)) => id,
+                None => return Err(Error::NoOpenTables),
+            },
+        };
+        let (seat, messages) = match self._tables.get_mut(&id) {
+            Some(table) => try!(table.join(name).map_err(Error::Table)),
+            None => return Err(Error::NoSuchTable(id)),
+        };
+        let token = new_token();
+        self._sessions.insert(token.clone(), (id, seat));
+        Ok((id, seat, token, messages))
+    }
+
+    /// Give the next free seat at table `id` to a bot of the given kind.
+    /// Returns the seat, and the messages to send to that table.
+    pub fn add_bot(&mut self, id: usize, kind: AgentKind) -> Result<(usize, Vec<Outgoing>), Error> {
+        match self._tables.get_mut(&id) {
+            Some(table) => table.add_bot(kind).map_err(Error::Table),
+            None => Err(Error::NoSuchTable(id)),
+        }
+    }
+
+    /// Free up `seat` at table `id`. Its session token stops working.
+    pub fn leave(&mut self, id: usize, seat: usize) -> Result<Vec<Outgoing>, Error> {
+        let messages = match self._tables.get_mut(&id) {
+            Some(table) => try!(table.leave(seat).map_err(Error::Table)),
+            None => return Err(Error::NoSuchTable(id)),
+        };
+        let tokens: Vec<String> = self._sessions
+            .iter()
+            .filter(|&(_, &place)| place == (id, seat))
+            .map(|(token, _)| token.clone())
+            .collect();
+        for token in tokens.iter() {
+            self._sessions.remove(token);
+        }
+        Ok(messages)
+    }
+
+    /// The player in `seat` at table `id` has lost their connection, at time
+    /// `now`. They keep their seat for now.
+    pub fn disconnect(&mut self, id: usize, seat: usize, now: u64) -> Result<(), Error> {
+        match self._tables.get_mut(&id) {
+            Some(table) => table.disconnect(seat, now).map_err(Error::Table),
+            None => Err(Error::NoSuchTable(id)),
+        }
+    }
+
+    /// Someone with a session token wants their seat back. Returns the
+    /// table, the seat, and what to tell them so they can catch up.
+    pub fn rejoin(&mut self, token: &str) -> Result<(usize, usize, Vec<Outgoing>), Error> {
+        let (id, seat) = match self._sessions.get(token) {
+            Some(&place) => place,
+            None => return Err(Error::UnknownSession),
+        };
+        match self._tables.get_mut(&id) {
+            Some(table) => table.reconnect(seat).map(|messages| (id, seat, messages)).map_err(Error::Table),
+            None => Err(Error::NoSuchTable(id)),
+        }
+    }
+
+    /// Start watching table `id`. Returns what a new spectator needs to know.
+    pub fn spectate(&self, id: usize) -> Result<Vec<ServerMessage>, Error> {
+        match self._tables.get(&id) {
+            Some(table) => Ok(table.spectate()),
+            None => Err(Error::NoSuchTable(id)),
+        }
+    }
+
+    /// Time has passed. Returns messages for each table where something
+    /// has happened as a result.
+    pub fn tick(&mut self, now: u64) -> Vec<(usize, Vec<Outgoing>)> {
+        self._tables
+            .iter_mut()
+            .map(|(&id, table)| (id, table.tick(now)))
+            .filter(|&(_, ref messages)| messages.len() > 0)
+            .collect()
+    }
+
+    /// The player in `seat` at table `id` wants to say something.
+    pub fn chat(&mut self, id: usize, seat: usize, text: &str) -> Result<Vec<Outgoing>, Error> {
+        match self._tables.get_mut(&id) {
+            Some(table) => Ok(table.chat(seat, text)),
+            None => Err(Error::NoSuchTable(id)),
+        }
+    }
+
+    pub fn emote(&mut self, id: usize, seat: usize, emote: Emote) -> Result<Vec<Outgoing>, Error> {
+        match self._tables.get_mut(&id) {
+            Some(table) => Ok(table.emote(seat, emote)),
+            None => Err(Error::NoSuchTable(id)),
+        }
+    }
+
+    /// The player in `seat` at table `id` wants to play `card`.
+    pub fn submit(&mut self, id: usize, seat: usize, card: Card, play: SeatPlay) -> Result<Vec<Outgoing>, Error> {
+        match self._tables.get_mut(&id) {
+            Some(table) => Ok(table.submit(seat, card, play)),
+            None => Err(Error::NoSuchTable(id)),
+        }
+    }
+}
+
+
+/// A new, unguessable session token.
+fn new_token() -> String {
+    let mut rng = thread_rng();
+    range(0, 16).map(|_| format!("{:02x}", rng.gen::<u8>())).collect::<Vec<String>>().concat()
+}
+
+
+#[cfg(test)]
+mod test {
+    use agent::AgentKind;
+    use game::Variant;
+    use table;
+    use super::{Error, Lobby};
+
+    #[test]
+    fn test_create_and_list() {
+        let mut lobby = Lobby::new();
+        assert_eq!(Ok(0), lobby.create(2, Variant::Quick));
+        assert_eq!(Ok(1), lobby.create(4, Variant::Classic));
+        assert_eq!(Err(Error::InvalidPlayers(5)), lobby.create(5, Variant::Quick));
+        let tables = lobby.list();
+        assert_eq!(vec![0, 1], tables.iter().map(|t| t.id).collect::<Vec<usize>>());
+        assert_eq!(4, tables[1].num_players);
+        assert_eq!(Variant::Classic, tables[1].variant);
+        assert_eq!(4, tables[1].winning_score);
+        assert_eq!(vec![None, None, None, None], tables[1].names);
+    }
+
+    #[test]
+    fn test_join_any_table() {
+        let mut lobby = Lobby::new();
+        assert_eq!(Err(Error::NoOpenTables), lobby.join(None, "alice").map(|(t, s, _, _)| (t, s)));
+        lobby.create(2, Variant::Quick).unwrap();
+        lobby.create(2, Variant::Classic).unwrap();
+        let seats: Vec<(usize, usize)> = ["alice", "bob", "carol"]
+            .iter()
+            .map(|name| lobby.join(None, *name).map(|(t, s, _, _)| (t, s)).unwrap())
+            .collect();
+        assert_eq!(vec![(0, 0), (0, 1), (1, 0)], seats);
+        assert!(lobby.summary(0).unwrap().started);
+        assert!(!lobby.summary(1).unwrap().started);
+    }
+
+    #[test]
+    fn test_tables_are_separate() {
+        let mut lobby = Lobby::new();
+        lobby.create(2, Variant::Quick).unwrap();
+        lobby.create(2, Variant::Quick).unwrap();
+        lobby.join(Some(1), "alice").unwrap();
+        lobby.join(Some(1), "bob").unwrap();
+        assert_eq!(Err(Error::Table(table::Error::TableFull)),
+                   lobby.join(Some(1), "carol").map(|(t, s, _, _)| (t, s)));
+        assert!(lobby.get(1).unwrap().waiting_for().is_some());
+        assert_eq!(None, lobby.get(0).unwrap().waiting_for());
+        assert_eq!(Err(Error::NoSuchTable(7)), lobby.leave(7, 0));
+    }
+
+    #[test]
+    fn test_rejoin_with_token() {
+        let mut lobby = Lobby::new();
+        lobby.create(2, Variant::Quick).unwrap();
+        let (_, _, alice, _) = lobby.join(Some(0), "alice").unwrap();
+        let (_, _, bob, _) = lobby.join(Some(0), "bob").unwrap();
+        assert!(alice != bob);
+        lobby.disconnect(0, 1, 0).unwrap();
+        let (table, seat, messages) = lobby.rejoin(bob.as_slice()).unwrap();
+        assert_eq!((0, 1), (table, seat));
+        assert!(messages.len() > 0);
+        assert_eq!(Err(Error::UnknownSession), lobby.rejoin("nonsense").map(|(t, s, _)| (t, s)));
+        lobby.leave(0, 0).unwrap();
+        assert_eq!(Err(Error::UnknownSession), lobby.rejoin(alice.as_slice()).map(|(t, s, _)| (t, s)));
+    }
+
+    #[test]
+    fn test_add_bot() {
+        let mut lobby = Lobby::new();
+        lobby.create(2, Variant::Quick).unwrap();
+        assert_eq!(Ok(0), lobby.add_bot(0, AgentKind::Search).map(|(seat, _)| seat));
+        assert_eq!(Err(Error::NoSuchTable(1)), lobby.add_bot(1, AgentKind::Search).map(|(seat, _)| seat));
+        let (_, seat, _, _) = lobby.join(None, "alice").unwrap();
+        assert_eq!(1, seat);
+        assert!(lobby.summary(0).unwrap().started);
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut lobby = Lobby::new();
+        lobby.create(2, Variant::Quick).unwrap();
+        let (_, _, alice, _) = lobby.join(Some(0), "alice").unwrap();
+        let table = lobby.get(0).unwrap().clone();
+        let sessions = lobby.sessions(0);
+        assert_eq!(vec![(alice.clone(), 0)], sessions);
+
+        let mut restarted = Lobby::new();
+        restarted.restore(3, table, sessions.as_slice());
+        assert_eq!(Ok(4), restarted.create(2, Variant::Quick));
+        assert_eq!(Some(vec![Some("alice".to_string()), None]), restarted.summary(3).map(|s| s.names));
+        assert_eq!(Ok((3, 0)), restarted.rejoin(alice.as_slice()).map(|(t, s, _)| (t, s)));
+    }
+}
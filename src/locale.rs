@@ -0,0 +1,643 @@
+/// What the command line says to players, in each language it speaks.
+///
+/// Each language is a `Catalogue`: one method per message, taking whatever
+/// needs filling in. Players and cards come in already written out, so a
+/// catalogue doesn't need to know about rosters, only about words.
+///
+/// Usage messages, and errors that aren't about the game itself, like not
+/// being able to reach a server, are left in English.
+
+use std::str;
+
+use deck::Card;
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+pub enum Language {
+    English,
+    French,
+}
+
+
+pub const LANGUAGES: [Language; 2] = [Language::English, Language::French];
+
+
+impl Language {
+    /// The code to ask for this language by, like `en`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+        }
+    }
+
+    pub fn catalogue(self) -> &'static (Catalogue + 'static) {
+        match self {
+            Language::English => &ENGLISH as &Catalogue,
+            Language::French => &FRENCH as &Catalogue,
+        }
+    }
+}
+
+
+impl str::FromStr for Language {
+    fn from_str(code: &str) -> Option<Language> {
+        match code {
+            "en" => Some(Language::English),
+            "fr" => Some(Language::French),
+            _ => None,
+        }
+    }
+}
+
+
+/// Every message, in one language.
+pub trait Catalogue {
+    /// The name of the language, in itself.
+    fn name(&self) -> &'static str;
+    fn card(&self, card: Card) -> &'static str;
+
+    /// Read a yes or a no.
+    fn yes_or_no(&self, answer: &str) -> Result<bool, String>;
+    /// Ask again for a number from 1 to `upper`.
+    fn pick_number(&self, upper: usize) -> String;
+
+    // Plays, and what they did.
+    fn play_on(&self, card: Card, target: &str) -> String;
+    fn play_guessing(&self, card: Card, target: &str, guess: Card) -> String;
+    fn played(&self, player: &str, play: &str) -> String;
+    fn busted_out(&self, player: &str, a: Card, b: Card) -> String;
+    fn no_change(&self) -> String;
+    fn protected(&self) -> String;
+    fn swapped_hands(&self, other: &str) -> String;
+    fn eliminated(&self, player: &str) -> String;
+    fn showed_card(&self, player: &str, to: &str) -> String;
+    fn forced_discard(&self, player: &str, card: Card) -> String;
+    fn secret(&self, player: &str, card: Card) -> String;
+
+    // Why a play isn't allowed.
+    fn not_playing(&self, player: &str) -> String;
+    fn not_holding(&self, card: Card) -> String;
+    fn out_of_round(&self, player: &str) -> String;
+    fn self_target(&self, card: Card) -> String;
+    fn bad_action(&self, card: Card) -> String;
+    fn guessed_soldier(&self) -> String;
+    fn invalid_move(&self) -> String;
+
+    // Hints.
+    fn hints(&self) -> String;
+    fn unseen(&self, cards: &str) -> String;
+    fn protected_players(&self, players: &str) -> String;
+    fn eliminated_players(&self, players: &str) -> String;
+    fn illegal(&self, card: Card, why: &str) -> String;
+    fn minister_risk(&self, percent: f64) -> String;
+    fn suggestion(&self, play: &str) -> String;
+
+    // Questions.
+    fn pick_card(&self, example: &str) -> String;
+    fn or_undo(&self) -> String;
+    fn cant_make_play(&self) -> String;
+    fn pick_target(&self, count: usize) -> String;
+    fn pick_guess(&self) -> String;
+    fn pass_to(&self, player: &str) -> String;
+    fn hide_secret(&self) -> String;
+    fn agree_to_undo(&self, player: &str, asker: &str) -> String;
+
+    // Taking back turns.
+    fn nothing_to_undo(&self) -> String;
+    fn took_back(&self, turn: &str) -> String;
+    fn undo_refused(&self) -> String;
+
+    // Rounds and games.
+    fn all_discards(&self) -> String;
+    fn new_round(&self) -> String;
+    fn round_over(&self) -> String;
+    fn no_winners(&self) -> String;
+    fn round_won(&self, player: &str, card: Card) -> String;
+    fn round_tied(&self, count: usize) -> String;
+    fn tied_holding(&self, player: &str, card: Card) -> String;
+    fn scores(&self) -> String;
+    fn game_over(&self) -> String;
+
+    // The reference given by `help`.
+    fn help_available(&self) -> String;
+    fn effect(&self, card: Card) -> &'static str;
+    fn cards_heading(&self) -> String;
+    fn rules_heading(&self) -> String;
+    /// `cards` are everything the Minister busts with.
+    fn minister_rule(&self, cards: &str) -> String;
+    fn set_aside_rule(&self, num_players: usize) -> String;
+    fn token_target(&self, score: Option<u32>) -> String;
+    fn variant_rule(&self, variant: &str) -> String;
+
+    // Records and ratings.
+    fn saved_record(&self, path: &str) -> String;
+    fn leaderboard(&self) -> String;
+    fn rated_games(&self, count: u32) -> String;
+
+    // The full-screen interface.
+    fn help_key(&self) -> String;
+    fn back_to_game(&self) -> String;
+    fn tui_title(&self, round: usize) -> String;
+    fn round_dealt(&self, round: usize) -> String;
+    fn which_card(&self) -> String;
+    fn which_target(&self) -> String;
+    /// Playing a card that needs a target when nobody can be targeted.
+    fn nobody(&self) -> String;
+    fn deck_size(&self, count: usize) -> String;
+    fn status_out(&self) -> String;
+    fn status_protected(&self) -> String;
+    fn you_are_out(&self) -> String;
+    fn hand(&self, card: Card) -> String;
+    fn hand_and_draw(&self, hand: Card, draw: Card) -> String;
+    fn deal_next_round(&self) -> String;
+    fn finish_game(&self) -> String;
+
+    // Replays.
+    fn no_rounds(&self) -> String;
+    fn pick_viewer(&self, count: usize) -> String;
+    fn replay_position(&self, round: usize, num_rounds: usize, turn: usize, num_turns: usize) -> String;
+    fn cards_dealt(&self) -> String;
+    fn holding(&self, card: Card) -> String;
+    fn still_to_draw(&self, cards: &str) -> String;
+    fn replay_command(&self) -> String;
+    fn replay_keys(&self) -> String;
+    fn end_of_game(&self) -> String;
+    fn start_of_game(&self) -> String;
+
+    // Playing on a server.
+    fn ask_name(&self) -> String;
+    fn need_name(&self) -> String;
+    fn joined_table(&self, table: usize, num_players: usize, score: u32) -> String;
+    fn watching_table(&self, table: usize, num_players: usize, score: u32) -> String;
+    fn all_hands(&self) -> String;
+    fn rejoin_with(&self) -> String;
+    fn says(&self, player: &str, message: &str) -> String;
+    fn hurry_up(&self, seconds: u64) -> String;
+    fn timed_out(&self, player: &str) -> String;
+    fn bot_takeover(&self, player: &str) -> String;
+    fn you_are(&self, player: &str) -> String;
+    fn waiting(&self, player: &str) -> String;
+    fn server_says(&self, message: &str) -> String;
+    fn server_closed(&self) -> String;
+}
+
+
+pub struct English;
+
+static ENGLISH: English = English;
+
+
+impl Catalogue for English {
+    fn name(&self) -> &'static str { "English" }
+
+    fn card(&self, card: Card) -> &'static str {
+        match card {
+            Card::Soldier => "Soldier",
+            Card::Clown => "Clown",
+            Card::Knight => "Knight",
+            Card::Priestess => "Priestess",
+            Card::Wizard => "Wizard",
+            Card::General => "General",
+            Card::Minister => "Minister",
+            Card::Princess => "Princess",
+        }
+    }
+
+    fn yes_or_no(&self, answer: &str) -> Result<bool, String> {
+        match answer.trim() {
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err("Please answer y or n".to_string()),
+        }
+    }
+    fn pick_number(&self, upper: usize) -> String { format!("Please enter a number between 1 and {}", upper) }
+
+    fn play_on(&self, card: Card, target: &str) -> String {
+        format!("{} on {}", self.card(card), target)
+    }
+    fn play_guessing(&self, card: Card, target: &str, guess: Card) -> String {
+        format!("{} on {}, guessing {}", self.card(card), target, self.card(guess))
+    }
+    fn played(&self, player: &str, play: &str) -> String { format!("{} played {}.", player, play) }
+    fn busted_out(&self, player: &str, a: Card, b: Card) -> String {
+        format!("{} busted out with {} and {}!", player, self.card(a), self.card(b))
+    }
+    fn no_change(&self) -> String { "Nothing happened. ".to_string() }
+    fn protected(&self) -> String { "Now protected until their next turn. ".to_string() }
+    fn swapped_hands(&self, other: &str) -> String { format!("Swapped hands with {}. ", other) }
+    fn eliminated(&self, player: &str) -> String { format!("{} eliminated. ", player) }
+    fn showed_card(&self, player: &str, to: &str) -> String {
+        format!("{} showed their card to {}. ", player, to)
+    }
+    fn forced_discard(&self, player: &str, card: Card) -> String {
+        format!("{} forced to discard {}. ", player, self.card(card))
+    }
+    fn secret(&self, player: &str, card: Card) -> String {
+        format!("SECRET: {} has a {}", player, self.card(card))
+    }
+
+    fn not_playing(&self, player: &str) -> String { format!("{} isn't playing", player) }
+    fn not_holding(&self, card: Card) -> String { format!("you aren't holding the {}", self.card(card)) }
+    fn out_of_round(&self, player: &str) -> String { format!("{} is out of the round", player) }
+    fn self_target(&self, card: Card) -> String {
+        format!("you can't play the {} on yourself", self.card(card))
+    }
+    fn bad_action(&self, card: Card) -> String { format!("that's not how the {} works", self.card(card)) }
+    fn guessed_soldier(&self) -> String { "you can't guess the Soldier".to_string() }
+    fn invalid_move(&self) -> String { "Invalid move".to_string() }
+
+    fn hints(&self) -> String { "Hints".to_string() }
+    fn unseen(&self, cards: &str) -> String { format!("Unseen cards: {}", cards) }
+    fn protected_players(&self, players: &str) -> String { format!("Protected: {}", players) }
+    fn eliminated_players(&self, players: &str) -> String { format!("Out of the round: {}", players) }
+    fn illegal(&self, card: Card, why: &str) -> String {
+        format!("Can't play the {} that way: {}", self.card(card), why)
+    }
+    fn minister_risk(&self, percent: f64) -> String {
+        format!("If you keep the Minister, you have a {:.0}% chance of busting out next turn", percent)
+    }
+    fn suggestion(&self, play: &str) -> String { format!("Suggestion: {}", play) }
+
+    fn pick_card(&self, example: &str) -> String {
+        format!("Pick a card, or write out the whole play, like {}", example)
+    }
+    fn or_undo(&self) -> String { " (or 'undo' to take back the last turn)".to_string() }
+    fn cant_make_play(&self) -> String { "That's not a play you can make".to_string() }
+    fn pick_target(&self, count: usize) -> String { format!("Who are you playing it on? (1-{})", count) }
+    fn pick_guess(&self) -> String { "Which card do you guess?".to_string() }
+    fn pass_to(&self, player: &str) -> String {
+        format!("Pass to {}, and press Enter when nobody else is looking ", player)
+    }
+    fn hide_secret(&self) -> String { "Press Enter to hide it ".to_string() }
+    fn agree_to_undo(&self, player: &str, asker: &str) -> String {
+        format!("{}, will you let {} take back the last turn? (y/n) ", player, asker)
+    }
+
+    fn nothing_to_undo(&self) -> String { "Nothing to take back this round".to_string() }
+    fn took_back(&self, turn: &str) -> String { format!("Took back: {}", turn) }
+    fn undo_refused(&self) -> String { "Not everyone agreed, so the last turn stands".to_string() }
+
+    fn all_discards(&self) -> String { "All Discards".to_string() }
+    fn new_round(&self) -> String { "NEW ROUND".to_string() }
+    fn round_over(&self) -> String { "ROUND OVER: ".to_string() }
+    fn no_winners(&self) -> String {
+        "Something went wrong. No winners at all. Is the game over yet?".to_string()
+    }
+    fn round_won(&self, player: &str, card: Card) -> String {
+        format!("{} wins, holding {}", player, self.card(card))
+    }
+    fn round_tied(&self, count: usize) -> String { format!("Round tied between {} players.", count) }
+    fn tied_holding(&self, player: &str, card: Card) -> String {
+        format!("{} holds a {}", player, self.card(card))
+    }
+    fn scores(&self) -> String { "Scores".to_string() }
+    fn game_over(&self) -> String { "GAME OVER".to_string() }
+
+    fn help_available(&self) -> String {
+        "Type 'help' at any question to see what each card does and the rules.".to_string()
+    }
+    fn effect(&self, card: Card) -> &'static str {
+        match card {
+            Card::Soldier => "Guess another player's card. If you're right, they're out. You can't guess the Soldier.",
+            Card::Clown => "Look at another player's card.",
+            Card::Knight => "Compare cards with another player. Whoever's is lower is out.",
+            Card::Priestess => "Nobody can play anything on you until your next turn.",
+            Card::Wizard => "Make any player, yourself included, discard their card and draw another.",
+            Card::General => "Swap cards with another player.",
+            Card::Minister => "Nothing happens when you play it, but see below.",
+            Card::Princess => "If you play or discard her, you're out.",
+        }
+    }
+    fn cards_heading(&self) -> String { "Cards".to_string() }
+    fn rules_heading(&self) -> String { "Rules".to_string() }
+    fn minister_rule(&self, cards: &str) -> String {
+        format!("Holding the Minister with any of {} busts you out of the round straight away; \
+                 you aren't just made to discard it.", cards)
+    }
+    fn set_aside_rule(&self, num_players: usize) -> String {
+        format!("Each round, one card is set aside face down before dealing. With {} players, \
+                 no cards are set aside face up.", num_players)
+    }
+    fn token_target(&self, score: Option<u32>) -> String {
+        match score {
+            Some(score) => format!("Each round's winner gets a token. The first to {} tokens wins the game.", score),
+            None => "Each round's winner gets a token. The server says how many win the game.".to_string(),
+        }
+    }
+    fn variant_rule(&self, variant: &str) -> String { format!("Variant: {}", variant) }
+
+    fn saved_record(&self, path: &str) -> String { format!("Saved a record of the game to {}", path) }
+    fn leaderboard(&self) -> String { "Leaderboard".to_string() }
+    fn rated_games(&self, count: u32) -> String { format!("({} games)", count) }
+
+    fn help_key(&self) -> String {
+        "Press ? at any time to see what each card does and the rules.".to_string()
+    }
+    fn back_to_game(&self) -> String { "Press Enter to go back to the game".to_string() }
+    fn tui_title(&self, round: usize) -> String { format!("Love Letter: round {}", round) }
+    fn round_dealt(&self, round: usize) -> String { format!("Round {} dealt", round) }
+    fn which_card(&self) -> String { "Pick a card".to_string() }
+    fn which_target(&self) -> String { "Who are you playing it on?".to_string() }
+    fn nobody(&self) -> String { "Nobody".to_string() }
+    fn deck_size(&self, count: usize) -> String { format!("Deck: {} cards", count) }
+    fn status_out(&self) -> String { "out".to_string() }
+    fn status_protected(&self) -> String { "protected".to_string() }
+    fn you_are_out(&self) -> String { "You're out of the round".to_string() }
+    fn hand(&self, card: Card) -> String { format!("Hand: {}", self.card(card)) }
+    fn hand_and_draw(&self, hand: Card, draw: Card) -> String {
+        format!("Hand: {}    Drew: {}", self.card(hand), self.card(draw))
+    }
+    fn deal_next_round(&self) -> String { "Press Enter to deal the next round".to_string() }
+    fn finish_game(&self) -> String { "Game over. Press Enter to finish".to_string() }
+
+    fn no_rounds(&self) -> String { "No rounds were recorded".to_string() }
+    fn pick_viewer(&self, count: usize) -> String {
+        format!("Player must be a number between 1 and {}", count)
+    }
+    fn replay_position(&self, round: usize, num_rounds: usize, turn: usize, num_turns: usize) -> String {
+        format!("Round {} of {}, turn {} of {}", round, num_rounds, turn, num_turns)
+    }
+    fn cards_dealt(&self) -> String { "Cards dealt.".to_string() }
+    fn holding(&self, card: Card) -> String { format!(", holding {}", self.card(card)) }
+    fn still_to_draw(&self, cards: &str) -> String { format!("Still to draw: {}", cards) }
+    fn replay_command(&self) -> String { "[n]ext, [p]revious or [q]uit? ".to_string() }
+    fn replay_keys(&self) -> String { "Please enter n, p or q".to_string() }
+    fn end_of_game(&self) -> String { "That's the end of the game.".to_string() }
+    fn start_of_game(&self) -> String { "That's the start of the game.".to_string() }
+
+    fn ask_name(&self) -> String { "What's your name?".to_string() }
+    fn need_name(&self) -> String { "Please enter a name".to_string() }
+    fn joined_table(&self, table: usize, num_players: usize, score: u32) -> String {
+        format!("Joined table {}: {} players, first to {} tokens wins", table, num_players, score)
+    }
+    fn watching_table(&self, table: usize, num_players: usize, score: u32) -> String {
+        format!("Watching table {}: {} players, first to {} tokens wins", table, num_players, score)
+    }
+    fn all_hands(&self) -> String { "All Hands".to_string() }
+    fn rejoin_with(&self) -> String {
+        "If you lose your connection, you can get your seat back with:".to_string()
+    }
+    fn says(&self, player: &str, message: &str) -> String { format!("{} says: {}", player, message) }
+    fn hurry_up(&self, seconds: u64) -> String { format!("Hurry up! {} seconds left to play", seconds) }
+    fn timed_out(&self, player: &str) -> String {
+        format!("{} ran out of time, so a bot is playing for them", player)
+    }
+    fn bot_takeover(&self, player: &str) -> String {
+        format!("{} has gone, and a bot is playing for them", player)
+    }
+    fn you_are(&self, player: &str) -> String { format!("You are {}", player) }
+    fn waiting(&self, player: &str) -> String { format!("{} (waiting)", player) }
+    fn server_says(&self, message: &str) -> String { format!("Server says: {}", message) }
+    fn server_closed(&self) -> String { "Server closed the connection".to_string() }
+}
+
+
+pub struct French;
+
+static FRENCH: French = French;
+
+
+impl Catalogue for French {
+    fn name(&self) -> &'static str { "Français" }
+
+    fn card(&self, card: Card) -> &'static str {
+        match card {
+            Card::Soldier => "Soldat",
+            Card::Clown => "Bouffon",
+            Card::Knight => "Chevalier",
+            Card::Priestess => "Prêtresse",
+            Card::Wizard => "Sorcier",
+            Card::General => "Général",
+            Card::Minister => "Ministre",
+            Card::Princess => "Princesse",
+        }
+    }
+
+    fn yes_or_no(&self, answer: &str) -> Result<bool, String> {
+        match answer.trim() {
+            "o" | "oui" => Ok(true),
+            "n" | "non" => Ok(false),
+            _ => Err("Répondez o ou n".to_string()),
+        }
+    }
+    fn pick_number(&self, upper: usize) -> String { format!("Entrez un nombre entre 1 et {}", upper) }
+
+    fn play_on(&self, card: Card, target: &str) -> String {
+        format!("{} sur {}", self.card(card), target)
+    }
+    fn play_guessing(&self, card: Card, target: &str, guess: Card) -> String {
+        format!("{} sur {}, en devinant {}", self.card(card), target, self.card(guess))
+    }
+    fn played(&self, player: &str, play: &str) -> String { format!("{} a joué {}.", player, play) }
+    fn busted_out(&self, player: &str, a: Card, b: Card) -> String {
+        format!("{} est éliminé avec {} et {} en main !", player, self.card(a), self.card(b))
+    }
+    fn no_change(&self) -> String { "Rien ne s'est passé. ".to_string() }
+    fn protected(&self) -> String { "Protégé jusqu'à son prochain tour. ".to_string() }
+    fn swapped_hands(&self, other: &str) -> String { format!("A échangé sa main avec {}. ", other) }
+    fn eliminated(&self, player: &str) -> String { format!("{} est éliminé. ", player) }
+    fn showed_card(&self, player: &str, to: &str) -> String {
+        format!("{} a montré sa carte à {}. ", player, to)
+    }
+    fn forced_discard(&self, player: &str, card: Card) -> String {
+        format!("{} doit défausser : {}. ", player, self.card(card))
+    }
+    fn secret(&self, player: &str, card: Card) -> String {
+        format!("SECRET : {} a en main : {}", player, self.card(card))
+    }
+
+    fn not_playing(&self, player: &str) -> String { format!("{} ne joue pas", player) }
+    fn not_holding(&self, card: Card) -> String { format!("vous n'avez pas de {} en main", self.card(card)) }
+    fn out_of_round(&self, player: &str) -> String { format!("{} est éliminé de la manche", player) }
+    fn self_target(&self, card: Card) -> String {
+        format!("vous ne pouvez pas jouer la carte {} sur vous-même", self.card(card))
+    }
+    fn bad_action(&self, card: Card) -> String {
+        format!("la carte {} ne fonctionne pas comme ça", self.card(card))
+    }
+    fn guessed_soldier(&self) -> String { "vous ne pouvez pas deviner le Soldat".to_string() }
+    fn invalid_move(&self) -> String { "Coup invalide".to_string() }
+
+    fn hints(&self) -> String { "Conseils".to_string() }
+    fn unseen(&self, cards: &str) -> String { format!("Cartes pas encore vues : {}", cards) }
+    fn protected_players(&self, players: &str) -> String { format!("Protégés : {}", players) }
+    fn eliminated_players(&self, players: &str) -> String { format!("Éliminés de la manche : {}", players) }
+    fn illegal(&self, card: Card, why: &str) -> String {
+        format!("Impossible de jouer la carte {} ainsi : {}", self.card(card), why)
+    }
+    fn minister_risk(&self, percent: f64) -> String {
+        format!("Si vous gardez le Ministre, vous avez {:.0} % de risque d'être éliminé au prochain tour",
+                percent)
+    }
+    fn suggestion(&self, play: &str) -> String { format!("Suggestion : {}", play) }
+
+    fn pick_card(&self, example: &str) -> String {
+        format!("Choisissez une carte, ou écrivez tout le coup, comme {}", example)
+    }
+    fn or_undo(&self) -> String { " (ou 'undo' pour reprendre le dernier tour)".to_string() }
+    fn cant_make_play(&self) -> String { "Vous ne pouvez pas jouer ce coup".to_string() }
+    fn pick_target(&self, count: usize) -> String { format!("Sur qui la jouez-vous ? (1-{})", count) }
+    fn pick_guess(&self) -> String { "Quelle carte devinez-vous ?".to_string() }
+    fn pass_to(&self, player: &str) -> String {
+        format!("Passez à {}, et appuyez sur Entrée quand personne d'autre ne regarde ", player)
+    }
+    fn hide_secret(&self) -> String { "Appuyez sur Entrée pour la cacher ".to_string() }
+    fn agree_to_undo(&self, player: &str, asker: &str) -> String {
+        format!("{}, laissez-vous {} reprendre le dernier tour ? (o/n) ", player, asker)
+    }
+
+    fn nothing_to_undo(&self) -> String { "Rien à reprendre dans cette manche".to_string() }
+    fn took_back(&self, turn: &str) -> String { format!("Repris : {}", turn) }
+    fn undo_refused(&self) -> String {
+        "Tout le monde n'est pas d'accord, donc le dernier tour est maintenu".to_string()
+    }
+
+    fn all_discards(&self) -> String { "Défausses".to_string() }
+    fn new_round(&self) -> String { "NOUVELLE MANCHE".to_string() }
+    fn round_over(&self) -> String { "FIN DE LA MANCHE : ".to_string() }
+    fn no_winners(&self) -> String {
+        "Quelque chose s'est mal passé : personne n'a gagné. La partie est-elle finie ?".to_string()
+    }
+    fn round_won(&self, player: &str, card: Card) -> String {
+        format!("{} gagne avec {} en main", player, self.card(card))
+    }
+    fn round_tied(&self, count: usize) -> String { format!("Égalité entre {} joueurs.", count) }
+    fn tied_holding(&self, player: &str, card: Card) -> String {
+        format!("{} a en main : {}", player, self.card(card))
+    }
+    fn scores(&self) -> String { "Scores".to_string() }
+    fn game_over(&self) -> String { "PARTIE TERMINÉE".to_string() }
+
+    fn help_available(&self) -> String {
+        "Tapez 'help' à n'importe quelle question pour voir l'effet de chaque carte et les règles.".to_string()
+    }
+    fn effect(&self, card: Card) -> &'static str {
+        match card {
+            Card::Soldier =>
+                "Devinez la carte d'un autre joueur. Si c'est la bonne, il est éliminé. Impossible de deviner le Soldat.",
+            Card::Clown => "Regardez la carte d'un autre joueur.",
+            Card::Knight => "Comparez votre carte avec celle d'un autre joueur. La plus faible est éliminée.",
+            Card::Priestess => "Personne ne peut jouer de carte sur vous jusqu'à votre prochain tour.",
+            Card::Wizard => "Un joueur, vous compris, défausse sa carte et en pioche une autre.",
+            Card::General => "Échangez votre carte avec celle d'un autre joueur.",
+            Card::Minister => "Rien ne se passe quand vous la jouez, mais voir plus bas.",
+            Card::Princess => "Si vous la jouez ou la défaussez, vous êtes éliminé.",
+        }
+    }
+    fn cards_heading(&self) -> String { "Cartes".to_string() }
+    fn rules_heading(&self) -> String { "Règles".to_string() }
+    fn minister_rule(&self, cards: &str) -> String {
+        format!("Avoir le Ministre en main avec une de ces cartes : {} vous élimine aussitôt de la manche ; \
+                 vous n'êtes pas simplement obligé de le défausser.", cards)
+    }
+    fn set_aside_rule(&self, num_players: usize) -> String {
+        format!("À chaque manche, une carte est mise de côté face cachée avant la distribution. \
+                 À {} joueurs, aucune carte n'est mise de côté face visible.", num_players)
+    }
+    fn token_target(&self, score: Option<u32>) -> String {
+        match score {
+            Some(score) => format!("Le gagnant de chaque manche reçoit un jeton. \
+                                    Le premier à {} jetons gagne la partie.", score),
+            None => "Le gagnant de chaque manche reçoit un jeton. \
+                     Le serveur décide combien il en faut pour gagner.".to_string(),
+        }
+    }
+    fn variant_rule(&self, variant: &str) -> String { format!("Variante : {}", variant) }
+
+    fn saved_record(&self, path: &str) -> String { format!("Partie enregistrée dans {}", path) }
+    fn leaderboard(&self) -> String { "Classement".to_string() }
+    fn rated_games(&self, count: u32) -> String { format!("({} parties)", count) }
+
+    fn help_key(&self) -> String {
+        "Appuyez sur ? à tout moment pour voir l'effet de chaque carte et les règles.".to_string()
+    }
+    fn back_to_game(&self) -> String { "Appuyez sur Entrée pour revenir à la partie".to_string() }
+    fn tui_title(&self, round: usize) -> String { format!("Love Letter : manche {}", round) }
+    fn round_dealt(&self, round: usize) -> String { format!("Manche {} distribuée", round) }
+    fn which_card(&self) -> String { "Choisissez une carte".to_string() }
+    fn which_target(&self) -> String { "Sur qui la jouez-vous ?".to_string() }
+    fn nobody(&self) -> String { "Personne".to_string() }
+    fn deck_size(&self, count: usize) -> String { format!("Pioche : {} cartes", count) }
+    fn status_out(&self) -> String { "éliminé".to_string() }
+    fn status_protected(&self) -> String { "protégé".to_string() }
+    fn you_are_out(&self) -> String { "Vous êtes éliminé de la manche".to_string() }
+    fn hand(&self, card: Card) -> String { format!("Main : {}", self.card(card)) }
+    fn hand_and_draw(&self, hand: Card, draw: Card) -> String {
+        format!("Main : {}    Pioché : {}", self.card(hand), self.card(draw))
+    }
+    fn deal_next_round(&self) -> String {
+        "Appuyez sur Entrée pour distribuer la manche suivante".to_string()
+    }
+    fn finish_game(&self) -> String { "Partie terminée. Appuyez sur Entrée pour finir".to_string() }
+
+    fn no_rounds(&self) -> String { "Aucune manche n'a été enregistrée".to_string() }
+    fn pick_viewer(&self, count: usize) -> String {
+        format!("Le joueur doit être un nombre entre 1 et {}", count)
+    }
+    fn replay_position(&self, round: usize, num_rounds: usize, turn: usize, num_turns: usize) -> String {
+        format!("Manche {} sur {}, tour {} sur {}", round, num_rounds, turn, num_turns)
+    }
+    fn cards_dealt(&self) -> String { "Cartes distribuées.".to_string() }
+    fn holding(&self, card: Card) -> String { format!(", avec {} en main", self.card(card)) }
+    fn still_to_draw(&self, cards: &str) -> String { format!("Reste à piocher : {}", cards) }
+    fn replay_command(&self) -> String { "[n] suivant, [p] précédent ou [q] quitter ? ".to_string() }
+    fn replay_keys(&self) -> String { "Tapez n, p ou q".to_string() }
+    fn end_of_game(&self) -> String { "C'est la fin de la partie.".to_string() }
+    fn start_of_game(&self) -> String { "C'est le début de la partie.".to_string() }
+
+    fn ask_name(&self) -> String { "Comment vous appelez-vous ?".to_string() }
+    fn need_name(&self) -> String { "Entrez un nom".to_string() }
+    fn joined_table(&self, table: usize, num_players: usize, score: u32) -> String {
+        format!("Table {} rejointe : {} joueurs, le premier à {} jetons gagne", table, num_players, score)
+    }
+    fn watching_table(&self, table: usize, num_players: usize, score: u32) -> String {
+        format!("Vous regardez la table {} : {} joueurs, le premier à {} jetons gagne",
+                table, num_players, score)
+    }
+    fn all_hands(&self) -> String { "Toutes les mains".to_string() }
+    fn rejoin_with(&self) -> String {
+        "Si vous perdez la connexion, vous pouvez reprendre votre place avec :".to_string()
+    }
+    fn says(&self, player: &str, message: &str) -> String { format!("{} dit : {}", player, message) }
+    fn hurry_up(&self, seconds: u64) -> String {
+        format!("Dépêchez-vous ! Plus que {} secondes pour jouer", seconds)
+    }
+    fn timed_out(&self, player: &str) -> String {
+        format!("{} n'a plus de temps, donc un robot joue à sa place", player)
+    }
+    fn bot_takeover(&self, player: &str) -> String {
+        format!("{} est parti, et un robot joue à sa place", player)
+    }
+    fn you_are(&self, player: &str) -> String { format!("Vous êtes {}", player) }
+    fn waiting(&self, player: &str) -> String { format!("{} (en attente)", player) }
+    fn server_says(&self, message: &str) -> String { format!("Le serveur dit : {}", message) }
+    fn server_closed(&self) -> String { "Le serveur a fermé la connexion".to_string() }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::str;
+    use deck::CARDS;
+    use super::{Language, LANGUAGES};
+
+    #[test]
+    fn test_codes() {
+        for &language in LANGUAGES.iter() {
+            assert_eq!(Some(language), str::FromStr::from_str(language.code()));
+        }
+        assert_eq!(None, str::FromStr::from_str("xx") as Option<Language>);
+    }
+
+    #[test]
+    fn test_card_names_distinct() {
+        for &language in LANGUAGES.iter() {
+            let text = language.catalogue();
+            let mut names: Vec<&str> = CARDS.iter().map(|&c| text.card(c)).collect();
+            names.sort();
+            names.dedup();
+            assert_eq!(CARDS.len(), names.len());
+        }
+    }
+}
@@ -3,124 +3,435 @@
 
 extern crate loveletter;
 
+use std::cell::{Cell, RefCell};
 use std::io;
+use std::iter;
 use std::os;
-use loveletter::{Card, Event, PlayerId};
+use std::io::{BufferedReader, File, TcpStream};
+use std::io::process::Command;
+use std::rand;
+use std::rand::XorShiftRng;
+use std::str;
+use loveletter::{Card, Event, PlayError, PlayerId, PlayerView, Roster};
+use loveletter::agent::{Agent, HeuristicAgent, RandomAgent};
+use loveletter::cfr;
+use loveletter::help;
+use loveletter::deck::Deck;
+use loveletter::hint::Hints;
+use loveletter::history::RoundHistory;
+use loveletter::locale::{Catalogue, Language};
+use loveletter::notation;
+use loveletter::protocol;
+use loveletter::protocol::{ClientMessage, SeatPlay, ServerMessage};
+use loveletter::game::Variant;
+use loveletter::prompt::Prompt;
+use loveletter::rating::Ratings;
+use loveletter::record::{GameRecord, Replay, RoundRecord};
+use loveletter::roster::Colour;
+use loveletter::tui::{Chooser, Key, Terminal, Ui, render_page};
 
 
+/// Where we keep everyone's ratings between games.
+const RATINGS_FILE: &'static str = "loveletter-ratings.txt";
+
+
+/// Where players' answers come from: the terminal, or a script.
+type CliPrompt = Prompt<BufferedReader<Box<Reader + 'static>>, io::LineBufferedWriter<io::stdio::StdWriter>>;
+
+
+/// Options that change how a game is played on the command line.
+struct Options {
+    /// Show hints before each player picks a card.
+    hints: bool,
+    /// Everyone is sharing one terminal, so hide each player's hand from
+    /// the others.
+    hotseat: bool,
+    /// Take over the whole terminal, rather than printing as we go.
+    tui: bool,
+    /// The name of each player in a local game, in order of play, and the
+    /// colour to show it in. If empty, two unnamed players play.
+    names: Vec<(String, Option<Colour>)>,
+    /// Read answers from this file, rather than asking at the terminal.
+    script: Option<Path>,
+    /// Deal the same rounds as every other game with this seed.
+    seed: Option<u32>,
+    /// Write a record of the game here once it's over.
+    record: Option<Path>,
+    /// What to say everything in.
+    language: Language,
+}
+
+
+/// Split command-line arguments into options and everything else.
+fn parse_options(args: &[String]) -> Result<(Options, Vec<String>), String> {
+    let mut options = Options {
+        hints: false, hotseat: false, tui: false, names: vec![], script: None, seed: None, record: None,
+        language: Language::English };
+    let mut rest = vec![];
+    for arg in args.iter() {
+        match arg.as_slice() {
+            "--hints" => options.hints = true,
+            "--hotseat" => options.hotseat = true,
+            "--tui" => options.tui = true,
+            x if x.starts_with("--names=") => options.names = try!(parse_names(x.slice_from("--names=".len()))),
+            x if x.starts_with("--record=") => options.record = Some(Path::new(x.slice_from("--record=".len()))),
+            x if x.starts_with("--script=") => options.script = Some(Path::new(x.slice_from("--script=".len()))),
+            x if x.starts_with("--language=") => match str::FromStr::from_str(x.slice_from("--language=".len())) {
+                Some(language) => options.language = language,
+                None => return Err(format!("Unknown language: {}", x)),
+            },
+            x if x.starts_with("--seed=") => match str::FromStr::from_str(x.slice_from("--seed=".len())) {
+                Some(seed) => options.seed = Some(seed),
+                None => return Err(format!("Seed must be a number: {}", x)),
+            },
+            x if x.starts_with("--") => return Err(format!("Unknown option: {}", x)),
+            _ => rest.push(arg.clone()),
+        }
+    }
+    Ok((options, rest))
+}
+
+
+/// Parse a list of players like `alice:red,bob,carol:blue`.
+fn parse_names(names: &str) -> Result<Vec<(String, Option<Colour>)>, String> {
+    let mut players = vec![];
+    for player in names.split(',') {
+        let parts: Vec<&str> = player.split(':').collect();
+        let (name, colour) = match parts.as_slice() {
+            [name] => (name, None),
+            [name, colour] => match str::FromStr::from_str(colour) {
+                Some(colour) => (name, Some(colour)),
+                None => return Err(format!("Unknown colour: {}", colour)),
+            },
+            _ => return Err(format!("Can't make sense of player: {}", player)),
+        };
+        if name.trim().len() == 0 {
+            return Err("Players need names".to_string());
+        }
+        players.push((name.trim().to_string(), colour));
+    }
+    Ok(players)
+}
+
+
+/// Ask at the terminal, unless `options` has a script of answers.
+fn open_prompt(options: &Options) -> Result<CliPrompt, String> {
+    match options.script {
+        Some(ref path) => match File::open(path) {
+            Ok(file) => Ok(Prompt::scripted(BufferedReader::new(Box::new(file) as Box<Reader>), io::stdout())),
+            Err(e) => Err(format!("Could not open script: {}", e)),
+        },
+        None => Ok(Prompt::new(BufferedReader::new(Box::new(io::stdin()) as Box<Reader>), io::stdout())),
+    }
+}
+
+
+/// What someone answered, if they did.
+type Answer<T> = Result<T, loveletter::prompt::Error>;
+
+
+/// There's no carrying on without an answer, so say why there isn't one.
+fn give_up(error: loveletter::prompt::Error) {
+    match error {
+        loveletter::prompt::Error::Io(e) => println!("Could not read an answer: {}", e),
+        loveletter::prompt::Error::BadAnswer(answer, why) => println!("Bad answer {:?}: {}", answer, why),
+    }
+    os::set_exit_status(1);
+}
+
+
+/// The value of an `Answer`, or, if there isn't one, give up and return.
+macro_rules! answer {
+    ($result:expr) => (
+        match $result {
+            Ok(value) => value,
+            Err(e) => {
+                give_up(e);
+                return;
+            },
+        }
+    )
+}
+
+
+/// Shuffles the decks for a local game.
+fn new_rng(options: &Options) -> XorShiftRng {
+    match options.seed {
+        Some(seed) => loveletter::game::seeded_rng(seed),
+        None => rand::weak_rng(),
+    }
+}
+
+
+/// A new local game for the players named in `options`.
+fn new_game(options: &Options) -> Result<loveletter::Game, String> {
+    if options.names.len() == 0 {
+        return Ok(loveletter::game::new_game(2).expect("Two players can't play"));
+    }
+    let names: Vec<String> = options.names.iter().map(|&(ref name, _)| name.clone()).collect();
+    let game = match loveletter::game::new_game_with_names(names.as_slice(), Variant::Quick) {
+        Some(game) => game,
+        None => return Err(format!("Love Letter needs 2 to 4 players, not {}", names.len())),
+    };
+    let mut roster = game.roster().clone();
+    for (&player, &(_, colour)) in game.players().iter().zip(options.names.iter()) {
+        roster.set_colour(player, colour);
+    }
+    Ok(game.with_roster(roster).expect("Roster has the wrong players"))
+}
+
+
+/// What a player typed when asked for a card.
+enum CardChoice {
+    Card(Card),
+    /// A whole play, written in notation.
+    Play(Card, loveletter::Play),
+    Undo,
+}
+
+
+/// Ask which card to play. Players who know the notation can make the whole
+/// play at once, and, if `can_undo`, anyone can ask to take back the last
+/// turn.
 #[cfg(not(test))]
-fn choose_card(turn: &loveletter::Turn) -> loveletter::Card {
+fn choose_card(text: &Catalogue, prompt: &mut CliPrompt, players: &[PlayerId], turn: &loveletter::Turn,
+               can_undo: bool) -> Answer<CardChoice> {
     let list = [turn.hand, turn.draw];
-    let choice = loveletter::prompt::choose_from_list("Pick a card", &list);
-    *choice
+    let example = notation::format_play_short(players, turn.player, Card::Priestess, loveletter::Play::NoEffect);
+    let question = format!(
+        "{}{}\n  1. {}\n  2. {}\n>>> ",
+        text.pick_card(example.as_slice()),
+        if can_undo { text.or_undo() } else { "".to_string() },
+        text.card(turn.hand), text.card(turn.draw));
+    prompt.repeated_prompt(
+        question.as_slice(),
+        |x| match loveletter::prompt::read_int_in_range(text, x, list.len()) {
+            Ok(i) => Ok(CardChoice::Card(list[i])),
+            Err(..) if can_undo && x.trim() == "undo" => Ok(CardChoice::Undo),
+            Err(e) => match notation::parse_play(players, x) {
+                Ok((player, card, play)) if player == turn.player && list.contains(&card) =>
+                    Ok(CardChoice::Play(card, play)),
+                Ok(..) => Err(text.cant_make_play()),
+                Err(..) => Err(e),
+            },
+        })
 }
 
 
+/// Ask everyone but `asker` whether the last turn can be taken back. It
+/// only is if they all agree. In `hotseat` games, each of them is handed
+/// the keyboard in turn.
 #[cfg(not(test))]
-fn choose_target(game: &loveletter::Game) -> PlayerId {
-    let players = game.players();
-    *loveletter::prompt::choose_from_list(
-        format!(
-            "Who are you playing it on? (1-{:?})\n>>> ",
-            players.len()).as_slice(),
-        players.as_slice())
+fn agree_to_undo(text: &Catalogue, prompt: &mut CliPrompt, roster: &Roster, asker: PlayerId,
+                 hotseat: bool) -> Answer<bool> {
+    if hotseat {
+        // The asker's hand is still on screen.
+        try!(prompt.clear_screen());
+    }
+    for &player in roster.players().iter().filter(|&&p| p != asker) {
+        if hotseat {
+            try!(pass_to(text, prompt, roster, player));
+        }
+        let question = text.agree_to_undo(roster.display(player).as_slice(), roster.display(asker).as_slice());
+        let agreed = try!(prompt.repeated_prompt(question.as_slice(), |x| text.yes_or_no(x)));
+        if !agreed {
+            return Ok(false);
+        }
+    }
+    Ok(true)
 }
 
 
 #[cfg(not(test))]
-fn choose_guess() -> Card {
-    *loveletter::prompt::choose_from_list(
-        "Which card do you guess?",
+fn choose_target(text: &Catalogue, prompt: &mut CliPrompt, players: &[PlayerId], roster: &Roster)
+                 -> Answer<PlayerId> {
+    prompt.choose_labelled(
+        text,
+        format!("{}\n>>> ", text.pick_target(players.len())).as_slice(),
+        players,
+        |&p| roster.display(p)).map(|&p| p)
+}
+
+
+#[cfg(not(test))]
+fn choose_guess(text: &Catalogue, prompt: &mut CliPrompt) -> Answer<Card> {
+    prompt.choose_labelled(
+        text,
+        text.pick_guess().as_slice(),
         &[Card::Clown,
           Card::Knight,
           Card::Priestess,
           Card::Wizard,
           Card::General,
           Card::Minister,
-          Card::Princess])
+          Card::Princess],
+        |&c| text.card(c).to_string()).map(|&c| c)
+}
+
+
+fn describe_play_error(text: &Catalogue, roster: &Roster, error: &PlayError) -> String {
+    match *error {
+        PlayError::InvalidPlayer(p) => text.not_playing(roster.display(p).as_slice()),
+        PlayError::CardNotFound(card, _) => text.not_holding(card),
+        PlayError::InactivePlayer(p) => text.out_of_round(roster.display(p).as_slice()),
+        PlayError::SelfTarget(_, card) => text.self_target(card),
+        PlayError::BadActionForCard(_, card) => text.bad_action(card),
+        PlayError::BadGuess => text.guessed_soldier(),
+    }
+}
+
+
+fn format_play(text: &Catalogue, roster: &Roster, card: Card, play: loveletter::Play) -> String {
+    match play {
+        loveletter::Play::NoEffect => text.card(card).to_string(),
+        loveletter::Play::Attack(p) => text.play_on(card, roster.name(p).as_slice()),
+        loveletter::Play::Guess(p, guess) => text.play_guessing(card, roster.name(p).as_slice(), guess),
+    }
+}
+
+
+/// `cards`, by name.
+fn list_cards(text: &Catalogue, cards: &[Card]) -> String {
+    cards.iter().map(|&c| text.card(c)).collect::<Vec<&str>>().connect(", ")
+}
+
+
+/// Print `heading`, underlined.
+fn print_heading(heading: &str) {
+    println!("{}", heading);
+    println!("{}", iter::repeat("-").take(heading.chars().count()).collect::<String>());
+}
+
+
+/// Everyone in `players`, by name.
+fn list_players(roster: &Roster, players: &[PlayerId]) -> String {
+    players.iter().map(|&p| roster.display(p)).collect::<Vec<String>>().connect(", ")
+}
+
+
+fn show_hints(text: &Catalogue, roster: &Roster, hints: &Hints) {
+    print_heading(text.hints().as_slice());
+    let unseen: Vec<String> = hints.unseen.iter().map(|&(c, n)| format!("{} x{}", text.card(c), n)).collect();
+    println!("  {}", text.unseen(unseen.connect(", ").as_slice()));
+    if hints.protected.len() > 0 {
+        println!("  {}", text.protected_players(list_players(roster, hints.protected.as_slice()).as_slice()));
+    }
+    if hints.eliminated.len() > 0 {
+        println!("  {}", text.eliminated_players(list_players(roster, hints.eliminated.as_slice()).as_slice()));
+    }
+    for &(card, ref error) in hints.illegal.iter() {
+        println!("  {}", text.illegal(card, describe_play_error(text, roster, error).as_slice()));
+    }
+    match hints.minister_risk {
+        Some(risk) => println!("  {}", text.minister_risk(risk * 100.0)),
+        None => (),
+    }
+    let (card, play) = hints.suggestion;
+    println!("  {}", text.suggestion(format_play(text, roster, card, play).as_slice()));
+    println!("");
+}
+
+
+/// What a player decided to do with their turn.
+enum Decision {
+    Play(Card, loveletter::Play),
+    /// Take back the turn before instead.
+    Undo,
 }
 
 
-/// Allow the player to choose a card to play.
+/// Allow the player to choose a card to play, or, if `can_undo`, to ask
+/// for the last turn to be taken back.
+///
+/// `view` must be from the point of view of the player whose turn it is.
 #[cfg(not(test))]
-fn choose(game: &loveletter::Game, turn: &loveletter::Turn) -> (Card, loveletter::Play) {
-    println!("{:?}", turn.player);
+fn choose(prompt: &mut CliPrompt, players: &[PlayerId], roster: &Roster, view: &PlayerView, options: &Options,
+          can_undo: bool) -> Answer<Decision> {
+    let turn = loveletter::Turn {
+        player: view.player,
+        hand: view.hand.expect("Current player not in round"),
+        draw: view.draw.expect("Not the player's turn"),
+    };
+    println!("{}", roster.display(turn.player));
     println!("---------");
-    let chosen = choose_card(turn);
+    let text = options.language.catalogue();
+    if options.hints {
+        show_hints(text, roster, &Hints::new(view, &HeuristicAgent));
+    }
+    let chosen = match try!(choose_card(text, prompt, players, &turn, can_undo)) {
+        CardChoice::Play(card, play) => return Ok(Decision::Play(card, play)),
+        CardChoice::Undo => return Ok(Decision::Undo),
+        CardChoice::Card(card) => card,
+    };
     let action = match chosen {
         Card::Priestess | Card::Minister | Card::Princess => loveletter::Play::NoEffect,
         _ => {
-            let other = choose_target(game);
+            let other = try!(choose_target(text, prompt, players, roster));
             match chosen {
                 Card::Soldier => {
-                    let guess = choose_guess();
+                    let guess = try!(choose_guess(text, prompt));
                     loveletter::Play::Guess(other, guess)
                 },
                 _ => loveletter::Play::Attack(other),
             }
         },
     };
-    (chosen, action)
+    Ok(Decision::Play(chosen, action))
 }
 
 
-fn format_event(event: &loveletter::Event) -> String {
+fn format_event(text: &Catalogue, roster: &Roster, event: &loveletter::Event) -> String {
     match *event {
-        Event::NoChange => "Nothing happened. ".to_string(),
-        Event::Protected(_) => "Now protected until their next turn. ".to_string(),
-        Event::SwappedHands(_, b) => format!("Swapped hands with {:?}. ", b),
-        Event::PlayerEliminated(p) => format!("{:?} eliminated. ", p),
-        Event::ForcedReveal(a, b) => format!("{:?} showed their card to {:?}. ", b, a),
+        Event::NoChange => text.no_change(),
+        Event::Protected(_) => text.protected(),
+        Event::SwappedHands(_, b) => text.swapped_hands(roster.name(b).as_slice()),
+        Event::PlayerEliminated(p) => text.eliminated(roster.name(p).as_slice()),
+        Event::ForcedReveal(a, b) => text.showed_card(roster.name(b).as_slice(), roster.name(a).as_slice()),
         Event::ForcedDiscard(p, card) => {
             // XXX: Worth saying here whether the player was allowed to draw
             // another card?
-            format!("{:?} forced to discard {:?}. ", p, card)
+            text.forced_discard(roster.name(p).as_slice(), card)
         }
     }
 }
 
 
-fn report_outcome(outcome: loveletter::TurnOutcome) -> String {
+fn report_outcome(text: &Catalogue, roster: &Roster, outcome: loveletter::TurnOutcome) -> String {
     match outcome {
         loveletter::TurnOutcome::BustedOut(player, a, b) => {
-            format!("{:?} busted out with {:?} and {:?}!", player, a, b)
+            text.busted_out(roster.name(player).as_slice(), a, b)
         },
         loveletter::TurnOutcome::Played(player, card, play, events) => {
-            let prelude = format!("{:?} played {:?}", player, card);
-            let follow_up = match play {
-                loveletter::Play::NoEffect => ".".to_string(),
-                loveletter::Play::Attack(i) => format!(" on {:?}.", i),
-                loveletter::Play::Guess(i, guess) =>
-                    format!(" on {:?}, guessing {:?}.", i, guess),
-            };
+            let played = text.played(
+                roster.name(player).as_slice(), format_play(text, roster, card, play).as_slice());
             let mut event_str = String::new();
             for event in events.iter() {
-                event_str = event_str + format_event(event).as_slice();
+                event_str = event_str + format_event(text, roster, event).as_slice();
             }
-            format!("{:?}{:?} {:?}", prelude, follow_up, event_str)
+            format!("{} {}", played, event_str)
         },
     }
 }
 
 
 #[cfg(not(test))]
-fn announce_winner(winners: &Vec<(PlayerId, Card)>) {
+fn announce_winner(text: &Catalogue, roster: &Roster, winners: &Vec<(PlayerId, Card)>) {
     // TODO: Probably want to report on all survivors.
     // TODO: Probably want to say *why* the game is over: no more players or
     // no more cards.
     // TODO: Message for last player standing should be different from highest
     // card.
-    print!("ROUND OVER: ");
+    print!("{}", text.round_over());
     match winners.len() {
-        0 => println!("Something went wrong. No winners at all. Is the game over yet?"),
+        0 => println!("{}", text.no_winners()),
         1 => {
             let (i, card) = winners[0];
-            println!("{:?} wins, holding {:?}", i, card);
+            println!("{}", text.round_won(roster.display(i).as_slice(), card));
         },
         n => {
-            println!("Round tied between {:?} players.", n);
+            println!("{}", text.round_tied(n));
             for &(i, card) in winners.iter() {
-                println!("  {:?} holds a {:?}", i, card);
+                println!("  {}", text.tied_holding(roster.display(i).as_slice(), card));
             }
         }
     }
@@ -128,43 +439,685 @@ fn announce_winner(winners: &Vec<(PlayerId, Card)>) {
 }
 
 
-fn announce_current_scores(scores: &[u32]) {
-    println!("Scores");
-    println!("------");
-    for (player_id, &score) in scores.iter().enumerate() {
-        println!("Player {:?}: {:?}", player_id + 1, score);
+fn announce_current_scores(text: &Catalogue, roster: &Roster, scores: &[u32]) {
+    print_heading(text.scores().as_slice());
+    for (&player, &score) in roster.players().iter().zip(scores.iter()) {
+        println!("{}: {:?}", roster.display(player), score);
     }
     println!("");
 }
 
-fn announce_game_winners(scores: &[u32]) {
-    println!("GAME OVER");
+fn announce_game_winners(text: &Catalogue, roster: &Roster, scores: &[u32]) {
+    println!("{}", text.game_over());
+    println!("");
+    announce_current_scores(text, roster, scores);
+}
+
+
+fn handle_reveal(text: &Catalogue, roster: &Roster, player: PlayerId, card: Card) -> () {
+    println!("{}", text.secret(roster.display(player).as_slice(), card));
+}
+
+
+/// Wait for `player` to be the only one looking at the screen, then clear
+/// away whatever was on it.
+#[cfg(not(test))]
+fn pass_to(text: &Catalogue, prompt: &mut CliPrompt, roster: &Roster, player: PlayerId) -> Answer<()> {
+    try!(prompt.wait_for_enter(text.pass_to(roster.display(player).as_slice()).as_slice()));
+    prompt.clear_screen()
+}
+
+
+#[cfg(not(test))]
+fn update_ratings(game: &loveletter::Game) {
+    let path = Path::new(RATINGS_FILE);
+    let names = game.roster().names();
+    let result = Ratings::load(&path)
+        .and_then(|ratings| ratings.record_game(game, names.as_slice()))
+        .and_then(|ratings| ratings.save(&path));
+    match result {
+        Ok(()) => (),
+        Err(e) => println!("Could not update ratings: {:?}", e),
+    }
+}
+
+
+/// Today's date, if we can find out what it is.
+#[cfg(not(test))]
+fn today() -> Option<String> {
+    // XXX: There's no date handling in the standard library, so ask the
+    // system.
+    Command::new("date").arg("+%Y-%m-%d").output().ok()
+        .and_then(|output| String::from_utf8(output.output).ok())
+        .map(|date| date.trim().to_string())
+        .and_then(|date| if date.len() > 0 { Some(date) } else { None })
+}
+
+
+/// Write down how `game` went, if `options` asks for a record.
+#[cfg(not(test))]
+fn save_record(game: &loveletter::Game, options: &Options) {
+    let path = match options.record {
+        Some(ref path) => path,
+        None => return,
+    };
+    match GameRecord::from_game(game, options.seed, today()).save(path) {
+        Ok(()) => {
+            let path = format!("{}", path.display());
+            println!("{}", options.language.catalogue().saved_record(path.as_slice()));
+        },
+        Err(e) => println!("Could not save a record of the game: {:?}", e),
+    }
+}
+
+
+#[cfg(not(test))]
+fn print_leaderboard(options: &Options) {
+    let ratings = match Ratings::load(&Path::new(RATINGS_FILE)) {
+        Ok(r) => r,
+        Err(e) => {
+            println!("Could not load ratings: {:?}", e);
+            os::set_exit_status(1);
+            return;
+        }
+    };
+    let text = options.language.catalogue();
+    print_heading(text.leaderboard().as_slice());
+    for (i, &(ref name, rating)) in ratings.leaderboard().iter().enumerate() {
+        println!("{:>3}. {:<20} {:>7.1} {}", i + 1, name, rating.rating, text.rated_games(rating.games));
+    }
+}
+
+
+#[cfg(not(test))]
+fn export_self_play(args: &[String]) {
+    let (num_games, path, num_players) = match args {
+        [ref n, ref path] => (str::FromStr::from_str(n.as_slice()), path, Some(2us)),
+        [ref n, ref path, ref p] =>
+            (str::FromStr::from_str(n.as_slice()), path, str::FromStr::from_str(p.as_slice())),
+        _ => {
+            println!("Usage: selfplay <num-games> <output-file> [num-players]");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let (num_games, num_players) = match (num_games, num_players) {
+        (Some(g), Some(p)) if 2 <= p && p <= 4 => (g, p),
+        _ => {
+            println!("Number of games must be a number and number of players between 2 and 4");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let agents: Vec<RandomAgent> = range(0, num_players).map(|_| RandomAgent::new()).collect();
+    let agent_refs: Vec<&Agent> = agents.iter().map(|a| a as &Agent).collect();
+    let result = File::create(&Path::new(path.as_slice())).and_then(
+        |mut file| loveletter::selfplay::export(&mut file, num_games, agent_refs.as_slice()));
+    match result {
+        Ok(n) => println!("Wrote {} decisions from {} games to {}", n, num_games, path),
+        Err(e) => {
+            println!("Could not export games: {:?}", e);
+            os::set_exit_status(1);
+        }
+    }
+}
+
+
+#[cfg(not(test))]
+fn send_message(stream: &mut TcpStream, message: &ClientMessage) -> io::IoResult<()> {
+    stream.write_line(protocol::encode_client_message(message).as_slice())
+}
+
+
+/// Play a game on a server, started with `loveletter-server`.
+///
+/// Joins the given table, or the first one with a free seat.
+#[cfg(not(test))]
+fn connect(args: &[String], options: &Options, prompt: &mut CliPrompt) {
+    let (address, table) = match args {
+        [ref address] => (address, None),
+        [ref address, ref table] => match str::FromStr::from_str(table.as_slice()) {
+            Some(table) => (address, Some(table)),
+            None => {
+                println!("Table must be a number");
+                os::set_exit_status(2);
+                return;
+            }
+        },
+        _ => {
+            println!("Usage: connect <host:port> [table]");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let mut stream = match open_connection(address.as_slice()) {
+        Some(s) => s,
+        None => return,
+    };
+    let text = options.language.catalogue();
+    let name = answer!(prompt.repeated_prompt(
+        format!("{}\n>>> ", text.ask_name()).as_slice(),
+        |x| if x.trim().len() > 0 { Ok(x.trim().to_string()) } else { Err(text.need_name()) }));
+    match send_message(&mut stream, &ClientMessage::Join(name, table)) {
+        Ok(()) => play_remote(stream, options, prompt),
+        Err(e) => {
+            println!("Could not join: {}", e);
+            os::set_exit_status(1);
+        }
+    }
+}
+
+
+/// Watch a game on a server without playing. Omniscient spectators see
+/// everyone's hands, once the server thinks it's safe to show them.
+#[cfg(not(test))]
+fn watch(args: &[String], options: &Options, prompt: &mut CliPrompt) {
+    let (address, table, omniscient) = match args {
+        [ref address, ref table] => (address, table, false),
+        [ref address, ref table, ref all] if all.as_slice() == "omniscient" => (address, table, true),
+        _ => {
+            println!("Usage: watch <host:port> <table> [omniscient]");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let table = match str::FromStr::from_str(table.as_slice()) {
+        Some(table) => table,
+        None => {
+            println!("Table must be a number");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let mut stream = match open_connection(address.as_slice()) {
+        Some(s) => s,
+        None => return,
+    };
+    match send_message(&mut stream, &ClientMessage::Spectate(table, omniscient)) {
+        Ok(()) => play_remote(stream, options, prompt),
+        Err(e) => {
+            println!("Could not watch: {}", e);
+            os::set_exit_status(1);
+        }
+    }
+}
+
+
+/// Take back a seat on a server after losing the connection to it.
+#[cfg(not(test))]
+fn rejoin(args: &[String], options: &Options, prompt: &mut CliPrompt) {
+    let (address, token) = match args {
+        [ref address, ref token] => (address, token),
+        _ => {
+            println!("Usage: rejoin <host:port> <token>");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let mut stream = match open_connection(address.as_slice()) {
+        Some(s) => s,
+        None => return,
+    };
+    match send_message(&mut stream, &ClientMessage::Rejoin(token.clone())) {
+        Ok(()) => play_remote(stream, options, prompt),
+        Err(e) => {
+            println!("Could not rejoin: {}", e);
+            os::set_exit_status(1);
+        }
+    }
+}
+
+
+#[cfg(not(test))]
+fn open_connection(address: &str) -> Option<TcpStream> {
+    match TcpStream::connect(address) {
+        Ok(s) => Some(s),
+        Err(e) => {
+            println!("Could not connect to {}: {}", address, e);
+            os::set_exit_status(1);
+            None
+        }
+    }
+}
+
+
+/// Play at whichever table the server has sat us at, until the game is over.
+#[cfg(not(test))]
+fn play_remote(mut stream: TcpStream, options: &Options, prompt: &mut CliPrompt) {
+    // The server numbers the players in the same way a local game would, so
+    // we can make our own player IDs once we know how many there are.
+    let text = options.language.catalogue();
+    let mut players = vec![];
+    let mut roster = Roster::new(&[]);
+    let mut last_turn = None;
+    // The server says when we join the table, before giving us a seat.
+    let mut winning_score = None;
+    let mut reader = io::BufferedReader::new(stream.clone());
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                println!("Lost connection to server: {}", e);
+                os::set_exit_status(1);
+                return;
+            }
+        };
+        let message = match protocol::decode_server_message(players.as_slice(), line.as_slice()) {
+            Ok(m) => m,
+            Err(e) => {
+                println!("Could not understand server: {:?}", e);
+                continue;
+            }
+        };
+        let view = match message {
+            ServerMessage::Joined(table) => {
+                println!("{}", text.joined_table(table.id, table.num_players, table.winning_score));
+                winning_score = Some(table.winning_score);
+                continue;
+            },
+            ServerMessage::Tables(..) | ServerMessage::TableCreated(..) | ServerMessage::Left(..) => continue,
+            ServerMessage::Spectating(table) => {
+                players = loveletter::game::new_game(table.num_players)
+                    .expect("Server has an invalid number of players")
+                    .players();
+                roster = Roster::new(players.as_slice());
+                prompt.set_help(help::reference(text, table.num_players, Some(table.winning_score)));
+                println!("{}", text.watching_table(table.id, table.num_players, table.winning_score));
+                continue;
+            },
+            ServerMessage::SpectatorView(view) => {
+                match view.hands {
+                    Some(ref hands) => {
+                        print_heading(text.all_hands().as_slice());
+                        for (&player, hand) in players.iter().zip(hands.iter()) {
+                            match *hand {
+                                Some(card) => println!("  {}: {}", roster.display(player), text.card(card)),
+                                None => println!("  {}: ({})", roster.display(player), text.status_out()),
+                            }
+                        }
+                        println!("");
+                    },
+                    None => (),
+                }
+                continue;
+            },
+            ServerMessage::Session(token) => {
+                println!("{}", text.rejoin_with());
+                println!("  rejoin <host:port> {}", token);
+                println!("");
+                continue;
+            },
+            ServerMessage::Chat(player, message) => {
+                println!("{}", text.says(roster.display(player).as_slice(), message.as_slice()));
+                continue;
+            },
+            ServerMessage::Emote(player, emote) => {
+                println!("{} *{}*", roster.display(player), emote);
+                continue;
+            },
+            ServerMessage::TurnWarning(seconds) => {
+                println!("{}", text.hurry_up(seconds));
+                continue;
+            },
+            ServerMessage::TimedOut(player) => {
+                println!("{}", text.timed_out(roster.display(player).as_slice()));
+                continue;
+            },
+            ServerMessage::BotTakeover(player) => {
+                println!("{}", text.bot_takeover(roster.display(player).as_slice()));
+                println!("");
+                continue;
+            },
+            ServerMessage::Welcome(seat, num_players) => {
+                players = loveletter::game::new_game(num_players)
+                    .expect("Server has an invalid number of players")
+                    .players();
+                roster = Roster::new(players.as_slice());
+                prompt.set_help(help::reference(text, num_players, winning_score));
+                println!("{}", text.you_are(roster.display(players[seat]).as_slice()));
+                println!("{}", text.help_available());
+                println!("");
+                continue;
+            },
+            ServerMessage::Seated(seated) => {
+                for (&player, name) in players.iter().zip(seated.iter()) {
+                    roster.set_name(player, name.clone());
+                    match *name {
+                        Some(..) => println!("  {}", roster.display(player)),
+                        None => println!("  {}", text.waiting(roster.display(player).as_slice())),
+                    }
+                }
+                println!("");
+                continue;
+            },
+            ServerMessage::RoundStarted(..) => {
+                println!("{}", text.new_round());
+                println!("");
+                continue;
+            },
+            ServerMessage::View(..) => continue,
+            ServerMessage::YourTurn(view) => view,
+            ServerMessage::Turn(outcome) => {
+                io::println(report_outcome(text, &roster, outcome).as_slice());
+                println!("");
+                continue;
+            },
+            ServerMessage::Reveal(player, card) => {
+                handle_reveal(text, &roster, player, card);
+                continue;
+            },
+            ServerMessage::InvalidPlay(e) => {
+                println!("{}: {}\n", text.invalid_move(), describe_play_error(text, &roster, &e));
+                match last_turn.take() {
+                    Some(view) => view,
+                    None => continue,
+                }
+            },
+            ServerMessage::RoundOver(winners, scores) => {
+                announce_winner(text, &roster, &winners);
+                announce_current_scores(text, &roster, scores.as_slice());
+                println!("");
+                continue;
+            },
+            ServerMessage::GameOver(_, scores) => {
+                announce_game_winners(text, &roster, scores.as_slice());
+                return;
+            },
+            ServerMessage::Error(message) => {
+                println!("{}", text.server_says(message.as_slice()));
+                continue;
+            },
+        };
+        print_heading(text.all_discards().as_slice());
+        for player in view.players.iter() {
+            println!("  {}: {}", roster.display(player.id), list_cards(text, player.discards.as_slice()));
+        }
+        println!("");
+        let (card, play) = match answer!(choose(prompt, players.as_slice(), &roster, &view, options, false)) {
+            Decision::Play(card, play) => (card, play),
+            // The server doesn't let turns be taken back.
+            Decision::Undo => unreachable!(),
+        };
+        last_turn = Some(view);
+        let message = ClientMessage::Play(card, SeatPlay::from_play(players.as_slice(), play));
+        match send_message(&mut stream, &message) {
+            Ok(()) => (),
+            Err(e) => {
+                println!("Could not send play: {}", e);
+                os::set_exit_status(1);
+                return;
+            }
+        }
+    }
+    println!("{}", text.server_closed());
+}
+
+
+/// Ways of moving through a recorded game.
+enum ReplayCommand {
+    Forward,
+    Back,
+    Quit,
+}
+
+
+fn parse_replay_command(text: &Catalogue, x: &str) -> Result<ReplayCommand, String> {
+    match x.trim() {
+        "" | "n" => Ok(ReplayCommand::Forward),
+        "p" => Ok(ReplayCommand::Back),
+        "q" => Ok(ReplayCommand::Quit),
+        _ => Err(text.replay_keys()),
+    }
+}
+
+
+/// Show where we've got to in a recorded game. If there's a `viewer`, only
+/// show what they could see.
+#[cfg(not(test))]
+fn show_replay(text: &Catalogue, replay: &Replay, roster: &Roster, viewer: Option<PlayerId>) {
+    let round = match replay.round() {
+        Some(round) => round,
+        None => {
+            println!("{}", text.no_rounds());
+            return;
+        }
+    };
+    print_heading(text.replay_position(replay.round_number() + 1, replay.num_rounds(),
+                                       replay.turn_number(), replay.num_turns()).as_slice());
+    match replay.last_turn() {
+        Some(turn) => io::println(report_outcome(text, roster, turn.clone()).as_slice()),
+        None => println!("{}", text.cards_dealt()),
+    }
     println!("");
-    announce_current_scores(scores);
+    let (players, hands) = match viewer {
+        Some(player) => {
+            let view = round.view(player).ok().expect("Viewer not in round");
+            let hands: Vec<Option<Card>> = view.players.iter()
+                .map(|p| if p.id == player { view.hand } else { None }).collect();
+            (view.players, hands)
+        },
+        None => {
+            let view = round.spectator_view(true);
+            let hands = view.hands.unwrap_or(vec![]);
+            (view.players, hands)
+        },
+    };
+    for (i, player) in players.iter().enumerate() {
+        let status = if !player.active {
+            format!(" ({})", text.status_out())
+        } else if player.protected {
+            format!(" ({})", text.status_protected())
+        } else {
+            "".to_string()
+        };
+        let hand = match hands.get(i) {
+            Some(&Some(card)) => text.holding(card),
+            _ => "".to_string(),
+        };
+        println!("  {}{}{}: {}", roster.display(player.id), status, hand,
+                 list_cards(text, player.discards.as_slice()));
+    }
+    if viewer.is_none() {
+        match round.snapshot() {
+            Some(snapshot) => {
+                // Cards are drawn from the end.
+                let to_draw: Vec<Card> = snapshot.deck.iter().rev().map(|&c| c).collect();
+                println!("  {}", text.still_to_draw(list_cards(text, to_draw.as_slice()).as_slice()));
+            },
+            None => (),
+        }
+    }
+    println!("");
+}
+
+
+/// Step backwards and forwards through a recorded game, seeing everything,
+/// or only what one player could.
+#[cfg(not(test))]
+fn replay(args: &[String], options: &Options, prompt: &mut CliPrompt) {
+    let (path, seat) = match args {
+        [ref path] => (path, None),
+        [ref path, ref seat] => (path, Some(str::FromStr::from_str(seat.as_slice()))),
+        _ => {
+            println!("Usage: replay <file> [player]");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let record = match GameRecord::load(&Path::new(path.as_slice())) {
+        Ok(record) => record,
+        Err(e) => {
+            println!("Could not read game: {:?}", e);
+            os::set_exit_status(1);
+            return;
+        }
+    };
+    let (game, mut replay) = match (record.new_game(), Replay::new(&record)) {
+        (Ok(game), Ok(replay)) => (game, replay),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("Could not replay game: {:?}", e);
+            os::set_exit_status(1);
+            return;
+        }
+    };
+    let text = options.language.catalogue();
+    let players = game.players();
+    let viewer = match seat {
+        None => None,
+        Some(Some(n)) if 1 <= n && n <= players.len() => Some(players[n - 1]),
+        Some(_) => {
+            println!("{}", text.pick_viewer(players.len()));
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    loop {
+        show_replay(text, &replay, game.roster(), viewer);
+        let command = match prompt.repeated_prompt(text.replay_command().as_slice(),
+                                                   |x| parse_replay_command(text, x)) {
+            Ok(command) => command,
+            Err(..) => return,
+        };
+        match command {
+            ReplayCommand::Forward => if !replay.forward() {
+                println!("{}\n", text.end_of_game());
+            },
+            ReplayCommand::Back => if !replay.back() {
+                println!("{}\n", text.start_of_game());
+            },
+            ReplayCommand::Quit => return,
+        }
+    }
+}
+
+
+/// How many rounds to play when estimating exploitability.
+const EXPLOITABILITY_ROUNDS: usize = 200;
+
+/// How many guesses at the hidden cards to make for each challenger decision.
+const EXPLOITABILITY_SAMPLES: usize = 4;
+
+
+#[cfg(not(test))]
+fn solve(args: &[String]) {
+    let (iterations, path) = match args {
+        [ref n, ref path] => (str::FromStr::from_str(n.as_slice()), path),
+        _ => {
+            println!("Usage: solve <iterations> <strategy-file>");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let iterations: usize = match iterations {
+        Some(n) => n,
+        None => {
+            println!("Number of iterations must be a number");
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let mut solver = cfr::Solver::new();
+    let step = std::cmp::max(1, iterations / 10);
+    while solver.iterations() < iterations {
+        let n = std::cmp::min(step, iterations - solver.iterations());
+        solver.iterate(n);
+        println!("{} iterations, {} information sets", solver.iterations(), solver.num_info_sets());
+    }
+    let strategy = solver.strategy();
+    match strategy.save(&Path::new(path.as_slice())) {
+        Ok(()) => println!("Saved strategy to {}", path),
+        Err(e) => {
+            println!("Could not save strategy: {:?}", e);
+            os::set_exit_status(1);
+            return;
+        }
+    }
+    let seed = [1, 2, 3, 4];
+    let random = RandomAgent::new();
+    let equilibrium = cfr::EquilibriumAgent::new(strategy);
+    let agents = [
+        ("random", &random as &Agent),
+        ("heuristic", &HeuristicAgent as &Agent),
+        ("equilibrium", &equilibrium as &Agent),
+        ];
+    println!("Estimated exploitability (0 is unbeatable, 1 always loses):");
+    for &(name, agent) in agents.iter() {
+        let score = cfr::estimate_exploitability(
+            agent, EXPLOITABILITY_ROUNDS, EXPLOITABILITY_SAMPLES, seed);
+        println!("  {:<12} {:.3}", name, score);
+    }
 }
 
 
-fn handle_reveal(player: PlayerId, card: Card) -> () {
-    println!("SECRET: {:?} has a {:?}", player, card);
+#[cfg(not(test))]
+fn usage(program: &str) {
+    println!("Usage: {} [--hints] [--hotseat] [--tui] [--names=<name>[:<colour>],...] \
+              [--script=<file>] [--seed=<n>] [--record=<file>] [--language=<en|fr>] [leaderboard | selfplay <num-games> <output-file> [num-players] \
+              | solve <iterations> <strategy-file> | connect <host:port> [table] \
+              | rejoin <host:port> <token> | watch <host:port> <table> [omniscient] \
+              | replay <file> [player]]", program);
 }
 
 
 #[cfg(not(test))]
 fn main() {
+    let args = os::args();
+    let (options, rest) = match parse_options(args.slice_from(1)) {
+        Ok(x) => x,
+        Err(e) => {
+            println!("{}", e);
+            usage(args[0].as_slice());
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let mut prompt = match open_prompt(&options) {
+        Ok(p) => p,
+        Err(e) => {
+            println!("{}", e);
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    match rest.as_slice().get(0).map(|x| x.as_slice()) {
+        None if options.tui => play_game_tui(&options),
+        None => play_game(&options, prompt),
+        Some("leaderboard") => print_leaderboard(&options),
+        Some("selfplay") => export_self_play(rest.slice_from(1)),
+        Some("solve") => solve(rest.slice_from(1)),
+        Some("connect") => connect(rest.slice_from(1), &options, &mut prompt),
+        Some("rejoin") => rejoin(rest.slice_from(1), &options, &mut prompt),
+        Some("watch") => watch(rest.slice_from(1), &options, &mut prompt),
+        Some("replay") => replay(rest.slice_from(1), &options, &mut prompt),
+        Some(other) => {
+            println!("Unknown command: {}", other);
+            usage(args[0].as_slice());
+            os::set_exit_status(2);
+        }
+    }
+}
+
+
+#[cfg(not(test))]
+fn play_game(options: &Options, mut prompt: CliPrompt) {
     println!("Love Letter");
     println!("===========");
     println!("");
 
-    let num_players = 2us;
-
-    let game = match loveletter::game::new_game(num_players) {
-        Some(g) => g,
-        None => {
-            println!("Invalid number of players: {:?}", num_players);
+    let game = match new_game(options) {
+        Ok(g) => g,
+        Err(e) => {
+            println!("{}", e);
             os::set_exit_status(2);
             return;
         }
     };
+    let roster = game.roster().clone();
+    let text = options.language.catalogue();
+    prompt.set_help(help::game_reference(text, &game));
+    println!("{}", text.help_available());
+    println!("");
+    let mut rng = new_rng(options);
 
     // While the game is not over
     //   Draw a card
@@ -175,41 +1128,268 @@ fn main() {
 
     let mut current_game = game;
     loop {
-        let round = match current_game.next_round() {
+        let deck = Deck::with_rng(&mut rng);
+        let mut record = RoundRecord::new(&deck);
+        let round = match current_game.next_round_from_deck(deck) {
             Some(r) => r,
             None => break,
         };
-        let mut current_round = round;
-        println!("NEW ROUND");
+        let mut history = RoundHistory::new(round);
+        println!("{}", text.new_round());
         println!("");
         loop {
-            println!("All Discards");
-            println!("------------");
-            for (i, discards) in current_round.all_discards().iter().enumerate() {
-                println!("  P{:?}: {:?}", i + 1, discards);
+            print_heading(text.all_discards().as_slice());
+            for (&player, discards) in roster.players().iter().zip(history.current().all_discards().iter()) {
+                println!("  {}: {}", roster.display(player), list_cards(text, *discards));
             }
             println!("");
             // XXX: Maybe Round should have a reference to Game so this capture isn't need
-            let result = current_round.handle_turn(
-                |_, turn| choose(&current_game, turn), handle_reveal);
+            let players = current_game.players();
+            let (ready, turn) = match history.current().peek_turn() {
+                Some(next) => next,
+                None => break,
+            };
+            // Decide before playing the turn, so that the player can ask to
+            // take back the last one instead. Busting out with the Minister
+            // isn't a decision.
+            let decision = if loveletter::minister_bust(turn.hand, turn.draw) {
+                None
+            } else {
+                if options.hotseat {
+                    answer!(pass_to(text, &mut prompt, &roster, turn.player));
+                }
+                let view = ready.view(turn.player).ok().expect("Current player not in round");
+                match answer!(choose(&mut prompt, players.as_slice(), &roster, &view, options, true)) {
+                    Decision::Play(card, play) => Some((card, play)),
+                    Decision::Undo => {
+                        // Taking back a Minister bust would only have it
+                        // happen again, so go back to the last real decision.
+                        if history.last_decision().is_none() {
+                            println!("{}\n", text.nothing_to_undo());
+                        } else if answer!(agree_to_undo(text, &mut prompt, &roster, turn.player,
+                                                        options.hotseat)) {
+                            let outcome = history.undo_decision().expect("No turn to take back");
+                            println!("{}\n", text.took_back(report_outcome(text, &roster, outcome).as_slice()));
+                        } else {
+                            println!("{}\n", text.undo_refused());
+                        }
+                        if options.hotseat {
+                            answer!(prompt.clear_screen());
+                        }
+                        continue;
+                    },
+                }
+            };
+            let revealed = Cell::new(None);
+            let result = history.current().handle_turn(
+                |_, _| decision.expect("Asked for a play after busting out"),
+                |player, card| revealed.set(Some((player, card))));
+            match revealed.get() {
+                Some((player, card)) => {
+                    handle_reveal(text, &roster, player, card);
+                    if options.hotseat {
+                        // Only the player who played the Clown is still at
+                        // the keyboard.
+                        answer!(prompt.wait_for_enter(text.hide_secret().as_slice()));
+                    }
+                },
+                None => (),
+            }
+            if options.hotseat {
+                answer!(prompt.clear_screen());
+            }
             let (new_round, outcome) = match result {
                 Ok(None) => break,
                 Ok(Some(result)) => result,
-                Err(e) => { println!("Invalid move: {:?}\n", e); continue }
+                Err(e) => { println!("{}: {}\n", text.invalid_move(), describe_play_error(text, &roster, &e)); continue }
             };
 
-            io::println(report_outcome(outcome).as_slice());
+            io::println(report_outcome(text, &roster, outcome.clone()).as_slice());
             println!("");
-            current_round = new_round;
+            history.push(new_round, outcome);
         }
-        let winners = current_round.winners();
-        announce_winner(&winners);
+        record.turns = history.turns().to_vec();
+        let winners = history.current().winners();
+        announce_winner(text, &roster, &winners);
         let winner_ids: Vec<PlayerId> = winners.iter().map(|&(i, _)| i).collect();
-        current_game = current_game.players_won(winner_ids.as_slice());
+        current_game = current_game.players_won(winner_ids.as_slice()).record_round(record);
         let scores = current_game.scores();
-        announce_current_scores(scores.as_slice());
+        announce_current_scores(text, &roster, scores.as_slice());
         println!("");
     }
     let scores = current_game.scores();
-    announce_game_winners(scores.as_slice());
+    announce_game_winners(text, &roster, scores.as_slice());
+    save_record(&current_game, options);
+    // Scripted games are for testing, so they shouldn't change anyone's
+    // rating.
+    if options.script.is_none() {
+        update_ratings(&current_game);
+    }
+}
+
+
+/// The full-screen interface, and the view of the round it's showing.
+#[cfg(not(test))]
+struct Screen {
+    terminal: Terminal,
+    ui: Ui,
+    text: &'static (Catalogue + 'static),
+    /// Shown when `?` is pressed.
+    help: String,
+    roster: Roster,
+    view: Option<PlayerView>,
+}
+
+
+#[cfg(not(test))]
+impl Screen {
+    fn draw(&mut self, show_hand: bool, footer: &str) {
+        let (width, height) = self.terminal.size();
+        match self.view {
+            Some(ref view) => {
+                let lines = self.ui.render(self.text, view, &self.roster, show_hand, footer, width, height);
+                self.terminal.draw(lines.as_slice());
+            },
+            None => (),
+        }
+    }
+
+    /// Show the help until Enter is pressed.
+    fn show_help(&mut self) {
+        let (width, height) = self.terminal.size();
+        let lines = render_page(self.help.as_slice(), self.text.back_to_game().as_slice(), width, height);
+        self.terminal.draw(lines.as_slice());
+        while self.terminal.read_key() != Key::Enter {}
+    }
+
+    /// Show `footer` until Enter is pressed. The log can be scrolled, and
+    /// the help shown, while we wait.
+    fn wait(&mut self, show_hand: bool, footer: &str) {
+        loop {
+            self.draw(show_hand, footer);
+            let key = self.terminal.read_key();
+            match key {
+                Key::Enter => return,
+                Key::Char('?') => self.show_help(),
+                _ => { self.ui.scroll(key); },
+            }
+        }
+    }
+
+    /// Let the player whose turn it is pick a play.
+    fn choose(&mut self, view: &PlayerView, options: &Options) -> (Card, loveletter::Play) {
+        self.view = Some(view.clone());
+        if options.hints {
+            let (card, play) = Hints::new(view, &HeuristicAgent).suggestion;
+            let suggestion = format_play(self.text, &self.roster, card, play);
+            self.ui.log(self.text.suggestion(suggestion.as_slice()).as_slice());
+        }
+        let mut chooser = Chooser::new(view);
+        loop {
+            let prompt = chooser.prompt(self.text, &self.roster);
+            self.draw(true, prompt.as_slice());
+            let key = self.terminal.read_key();
+            if key == Key::Char('?') {
+                self.show_help();
+                continue;
+            }
+            if self.ui.scroll(key) {
+                continue;
+            }
+            match chooser.handle(key) {
+                Some(choice) => return choice,
+                None => (),
+            }
+        }
+    }
+}
+
+
+#[cfg(not(test))]
+fn play_game_tui(options: &Options) {
+    let mut current_game = match new_game(options) {
+        Ok(g) => g,
+        Err(e) => {
+            println!("{}", e);
+            os::set_exit_status(2);
+            return;
+        }
+    };
+    let roster = current_game.roster().clone();
+    let text = options.language.catalogue();
+    let mut rng = new_rng(options);
+    let screen = RefCell::new(Screen {
+        terminal: Terminal::new(), ui: Ui::new("Love Letter"), text: text,
+        help: help::game_reference(text, &current_game), roster: roster.clone(), view: None,
+    });
+    screen.borrow_mut().ui.log(text.help_key().as_slice());
+    let players = current_game.players();
+    let mut num_rounds = 0us;
+    loop {
+        let deck = Deck::with_rng(&mut rng);
+        let mut record = RoundRecord::new(&deck);
+        let mut current_round = match current_game.next_round_from_deck(deck) {
+            Some(r) => r,
+            None => break,
+        };
+        num_rounds += 1;
+        {
+            let mut screen = screen.borrow_mut();
+            screen.ui.set_title(text.tui_title(num_rounds).as_slice());
+            screen.ui.log(text.round_dealt(num_rounds).as_slice());
+        }
+        loop {
+            let result = current_round.handle_turn(
+                |round, turn| {
+                    let view = round.view(turn.player).ok().expect("Current player not in round");
+                    let mut screen = screen.borrow_mut();
+                    if options.hotseat {
+                        screen.view = Some(view.clone());
+                        screen.wait(false, text.pass_to(roster.name(turn.player).as_slice()).as_slice());
+                    }
+                    screen.choose(&view, options)
+                },
+                |player, card| {
+                    let secret = text.secret(roster.name(player).as_slice(), card);
+                    let mut screen = screen.borrow_mut();
+                    if options.hotseat {
+                        // Only the player who played the Clown is still at
+                        // the keyboard, so keep it out of the log.
+                        screen.wait(true, format!("{}. {}", secret, text.hide_secret()).as_slice());
+                    } else {
+                        screen.ui.log(secret.as_slice());
+                    }
+                });
+            let (new_round, outcome) = match result {
+                Ok(None) => break,
+                Ok(Some(result)) => result,
+                Err(e) => {
+                    let error = describe_play_error(text, &roster, &e);
+                    screen.borrow_mut().ui.log(format!("{}: {}", text.invalid_move(), error).as_slice());
+                    continue;
+                },
+            };
+            record.turns.push(outcome.clone());
+            screen.borrow_mut().ui.log(report_outcome(text, &roster, outcome).as_slice());
+            current_round = new_round;
+        }
+        let winners = current_round.winners();
+        let winner_ids: Vec<PlayerId> = winners.iter().map(|&(i, _)| i).collect();
+        current_game = current_game.players_won(winner_ids.as_slice()).record_round(record);
+        let mut screen = screen.borrow_mut();
+        for &(player, card) in winners.iter() {
+            screen.ui.log(text.round_won(roster.name(player).as_slice(), card).as_slice());
+        }
+        let scores: Vec<String> = players.iter().zip(current_game.scores().iter())
+            .map(|(&p, s)| format!("{} {}", roster.name(p), s)).collect();
+        screen.ui.log(format!("{}: {}", text.scores(), scores.connect(", ")).as_slice());
+        screen.view = current_round.view(players[0]).ok();
+        let footer = if current_game.is_over() { text.finish_game() } else { text.deal_next_round() };
+        screen.wait(false, footer.as_slice());
+    }
+    drop(screen);
+    let scores = current_game.scores();
+    announce_game_winners(text, &roster, scores.as_slice());
+    save_record(&current_game, options);
+    update_ratings(&current_game);
 }
@@ -0,0 +1,362 @@
+/// A compact, readable way of writing down plays and what they did.
+///
+/// Players are numbered from 1, in order of play, so the notation only
+/// means something alongside the list of players it was written for.
+///
+/// A play can be written out in full, or as a short code:
+///
+/// | Full                              | Short    |
+/// |-----------------------------------|----------|
+/// | `P1: Priestess`                   | `1Pr`    |
+/// | `P1: Knight -> P2`                | `1K2`    |
+/// | `P1: Soldier -> P3 guess Princess`| `1S3Pn`  |
+///
+/// Cards can be given by name or by code: `S` (Soldier), `C` (Clown), `K`
+/// (Knight), `Pr` (Priestess), `W` (Wizard), `G` (General), `M` (Minister)
+/// and `Pn` (Princess).
+///
+/// Events are written as:
+///
+/// * `no change`
+/// * `P1 protected`
+/// * `P1 swapped with P2`
+/// * `P2 out`
+/// * `P2 discarded Knight`
+/// * `P2 showed P1`, meaning P2 had to show their card to P1
+///
+/// A whole turn is the play followed by its events, each after a `;`, e.g.
+/// `P1: Soldier -> P2 guess Knight; P2 out`. A player knocked out for holding
+/// the Minister with too high a card is written
+/// `P1: busted with Minister and Wizard`.
+
+use std::str;
+
+use action::{Event, Play};
+use deck::{Card, CARDS};
+use player_id::PlayerId;
+use round::TurnOutcome;
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+pub enum Error {
+    /// Not the name or code of a card.
+    UnknownCard(String),
+    /// Not a player in the list we were given.
+    UnknownPlayer(String),
+    /// Doesn't look like anything we know how to write.
+    BadSyntax(String),
+}
+
+
+/// The short code for `card`.
+pub fn card_code(card: Card) -> &'static str {
+    match card {
+        Card::Soldier => "S",
+        Card::Clown => "C",
+        Card::Knight => "K",
+        Card::Priestess => "Pr",
+        Card::Wizard => "W",
+        Card::General => "G",
+        Card::Minister => "M",
+        Card::Princess => "Pn",
+    }
+}
+
+
+/// Parse a card, by its name or its short code.
+pub fn parse_card(text: &str) -> Result<Card, Error> {
+    match str::FromStr::from_str(text) {
+        Some(card) => Ok(card),
+        None => CARDS.iter().find(|&&c| card_code(c) == text).map(|&c| c)
+            .ok_or(Error::UnknownCard(text.to_string())),
+    }
+}
+
+
+/// `player`'s number, counting from 1.
+fn number(players: &[PlayerId], player: PlayerId) -> usize {
+    players.iter().position(|&p| p == player).expect("Unknown player") + 1
+}
+
+
+fn parse_number(players: &[PlayerId], text: &str) -> Result<PlayerId, Error> {
+    let n: Option<usize> = str::FromStr::from_str(text);
+    match n {
+        Some(n) if 1 <= n && n <= players.len() => Ok(players[n - 1]),
+        _ => Err(Error::UnknownPlayer(text.to_string())),
+    }
+}
+
+
+fn format_player(players: &[PlayerId], player: PlayerId) -> String {
+    format!("P{}", number(players, player))
+}
+
+
+fn parse_player(players: &[PlayerId], text: &str) -> Result<PlayerId, Error> {
+    if text.starts_with("P") {
+        parse_number(players, text.slice_from(1))
+    } else {
+        Err(Error::UnknownPlayer(text.to_string()))
+    }
+}
+
+
+/// Write out `player` playing `card` in full, e.g. `P1: Knight -> P2`.
+///
+/// Panics if any player involved isn't in `players`.
+pub fn format_play(players: &[PlayerId], player: PlayerId, card: Card, play: Play) -> String {
+    let prelude = format!("{}: {:?}", format_player(players, player), card);
+    match play {
+        Play::NoEffect => prelude,
+        Play::Attack(target) => format!("{} -> {}", prelude, format_player(players, target)),
+        Play::Guess(target, guess) =>
+            format!("{} -> {} guess {:?}", prelude, format_player(players, target), guess),
+    }
+}
+
+
+/// Write out `player` playing `card` as a short code, e.g. `1K2`.
+///
+/// Panics if any player involved isn't in `players`.
+pub fn format_play_short(players: &[PlayerId], player: PlayerId, card: Card, play: Play) -> String {
+    let prelude = format!("{}{}", number(players, player), card_code(card));
+    match play {
+        Play::NoEffect => prelude,
+        Play::Attack(target) => format!("{}{}", prelude, number(players, target)),
+        Play::Guess(target, guess) =>
+            format!("{}{}{}", prelude, number(players, target), card_code(guess)),
+    }
+}
+
+
+/// Parse a play, written either in full or as a short code.
+pub fn parse_play(players: &[PlayerId], text: &str) -> Result<(PlayerId, Card, Play), Error> {
+    let text = text.trim();
+    if text.starts_with("P") && text.contains(":") {
+        parse_full_play(players, text)
+    } else {
+        parse_short_play(players, text)
+    }
+}
+
+
+fn parse_full_play(players: &[PlayerId], text: &str) -> Result<(PlayerId, Card, Play), Error> {
+    let words: Vec<&str> = text.words().collect();
+    let (player, rest) = match words.as_slice() {
+        [first, rest..] if first.ends_with(":") => (first.slice_to(first.len() - 1), rest),
+        _ => return Err(Error::BadSyntax(text.to_string())),
+    };
+    let player = try!(parse_player(players, player));
+    match rest {
+        [card] => Ok((player, try!(parse_card(card)), Play::NoEffect)),
+        [card, "->", target] =>
+            Ok((player, try!(parse_card(card)), Play::Attack(try!(parse_player(players, target))))),
+        [card, "->", target, "guess", guess] =>
+            Ok((player, try!(parse_card(card)),
+                Play::Guess(try!(parse_player(players, target)), try!(parse_card(guess))))),
+        _ => Err(Error::BadSyntax(text.to_string())),
+    }
+}
+
+
+/// Split a short code into runs of digits, and cards: a capital letter,
+/// followed by any lowercase ones.
+fn short_tokens(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = vec![];
+    let mut last: Option<char> = None;
+    for c in text.chars() {
+        let continues = match last {
+            Some(l) => (l.is_digit(10) && c.is_digit(10)) || (l.is_alphabetic() && c.is_lowercase()),
+            None => false,
+        };
+        if continues {
+            let end = tokens.len() - 1;
+            tokens[end].push(c);
+        } else {
+            tokens.push(c.to_string());
+        }
+        last = Some(c);
+    }
+    tokens
+}
+
+
+fn parse_short_play(players: &[PlayerId], text: &str) -> Result<(PlayerId, Card, Play), Error> {
+    let tokens = short_tokens(text);
+    let tokens: Vec<&str> = tokens.iter().map(|t| t.as_slice()).collect();
+    match tokens.as_slice() {
+        [player, card] =>
+            Ok((try!(parse_number(players, player)), try!(parse_card(card)), Play::NoEffect)),
+        [player, card, target] =>
+            Ok((try!(parse_number(players, player)), try!(parse_card(card)),
+                Play::Attack(try!(parse_number(players, target))))),
+        [player, card, target, guess] =>
+            Ok((try!(parse_number(players, player)), try!(parse_card(card)),
+                Play::Guess(try!(parse_number(players, target)), try!(parse_card(guess))))),
+        _ => Err(Error::BadSyntax(text.to_string())),
+    }
+}
+
+
+/// Write out what happened as a result of a play, e.g. `P2 out`.
+///
+/// Panics if any player involved isn't in `players`.
+pub fn format_event(players: &[PlayerId], event: &Event) -> String {
+    match *event {
+        Event::NoChange => "no change".to_string(),
+        Event::Protected(p) => format!("{} protected", format_player(players, p)),
+        Event::SwappedHands(a, b) =>
+            format!("{} swapped with {}", format_player(players, a), format_player(players, b)),
+        Event::PlayerEliminated(p) => format!("{} out", format_player(players, p)),
+        Event::ForcedDiscard(p, card) => format!("{} discarded {:?}", format_player(players, p), card),
+        Event::ForcedReveal(a, b) =>
+            format!("{} showed {}", format_player(players, b), format_player(players, a)),
+    }
+}
+
+
+pub fn parse_event(players: &[PlayerId], text: &str) -> Result<Event, Error> {
+    let words: Vec<&str> = text.words().collect();
+    match words.as_slice() {
+        ["no", "change"] => Ok(Event::NoChange),
+        [p, "protected"] => Ok(Event::Protected(try!(parse_player(players, p)))),
+        [a, "swapped", "with", b] =>
+            Ok(Event::SwappedHands(try!(parse_player(players, a)), try!(parse_player(players, b)))),
+        [p, "out"] => Ok(Event::PlayerEliminated(try!(parse_player(players, p)))),
+        [p, "discarded", card] => Ok(Event::ForcedDiscard(try!(parse_player(players, p)), try!(parse_card(card)))),
+        [b, "showed", a] =>
+            Ok(Event::ForcedReveal(try!(parse_player(players, a)), try!(parse_player(players, b)))),
+        _ => Err(Error::BadSyntax(text.to_string())),
+    }
+}
+
+
+/// Write out a whole turn: the play and everything it did.
+///
+/// Panics if any player involved isn't in `players`.
+pub fn format_outcome(players: &[PlayerId], outcome: &TurnOutcome) -> String {
+    match *outcome {
+        TurnOutcome::BustedOut(player, a, b) =>
+            format!("{}: busted with {:?} and {:?}", format_player(players, player), a, b),
+        TurnOutcome::Played(player, card, play, ref events) => {
+            let mut parts = vec![format_play(players, player, card, play)];
+            parts.extend(events.iter().map(|e| format_event(players, e)));
+            parts.connect("; ")
+        },
+    }
+}
+
+
+pub fn parse_outcome(players: &[PlayerId], text: &str) -> Result<TurnOutcome, Error> {
+    let parts: Vec<&str> = text.split(';').map(|p| p.trim()).collect();
+    let words: Vec<&str> = parts[0].words().collect();
+    match words.as_slice() {
+        [player, "busted", "with", a, "and", b] if parts.len() == 1 && player.ends_with(":") =>
+            return Ok(TurnOutcome::BustedOut(
+                try!(parse_player(players, player.slice_to(player.len() - 1))),
+                try!(parse_card(a)), try!(parse_card(b)))),
+        _ => (),
+    }
+    let (player, card, play) = try!(parse_full_play(players, parts[0]));
+    let mut events = vec![];
+    for part in parts.slice_from(1).iter() {
+        events.push(try!(parse_event(players, *part)));
+    }
+    Ok(TurnOutcome::Played(player, card, play, events))
+}
+
+
+#[cfg(test)]
+mod test {
+    use action::{Event, Play};
+    use deck::{Card, CARDS};
+    use player_id::{player_id_generator, PlayerId};
+    use round::TurnOutcome;
+    use super::{Error, card_code, format_event, format_outcome, format_play, format_play_short,
+                parse_card, parse_event, parse_outcome, parse_play};
+
+    fn make_players() -> Vec<PlayerId> {
+        player_id_generator().take(3).collect()
+    }
+
+    #[test]
+    fn test_cards() {
+        for &card in CARDS.iter() {
+            assert_eq!(Ok(card), parse_card(card_code(card)));
+            assert_eq!(Ok(card), parse_card(format!("{:?}", card).as_slice()));
+        }
+        assert_eq!(Err(Error::UnknownCard("Jester".to_string())), parse_card("Jester"));
+    }
+
+    #[test]
+    fn test_example_plays() {
+        let p = make_players();
+        let (player, card, play) = (p[0], Card::Soldier, Play::Guess(p[2], Card::Priestess));
+        assert_eq!("P1: Soldier -> P3 guess Priestess", format_play(p.as_slice(), player, card, play).as_slice());
+        assert_eq!("1S3Pr", format_play_short(p.as_slice(), player, card, play).as_slice());
+        assert_eq!(Ok((player, card, play)), parse_play(p.as_slice(), "1S3Pr"));
+        assert_eq!(Ok((player, card, play)), parse_play(p.as_slice(), "  P1:  Soldier -> P3 guess Pr "));
+    }
+
+    #[test]
+    fn test_plays_round_trip() {
+        let p = make_players();
+        let plays = [
+            (p[0], Card::Priestess, Play::NoEffect),
+            (p[1], Card::Knight, Play::Attack(p[2])),
+            (p[2], Card::Wizard, Play::Attack(p[2])),
+            (p[2], Card::Soldier, Play::Guess(p[0], Card::Princess)),
+            ];
+        for &(player, card, play) in plays.iter() {
+            let full = format_play(p.as_slice(), player, card, play);
+            let short = format_play_short(p.as_slice(), player, card, play);
+            assert_eq!(Ok((player, card, play)), parse_play(p.as_slice(), full.as_slice()));
+            assert_eq!(Ok((player, card, play)), parse_play(p.as_slice(), short.as_slice()));
+        }
+    }
+
+    #[test]
+    fn test_bad_plays() {
+        let p = make_players();
+        assert_eq!(Err(Error::UnknownPlayer("4".to_string())), parse_play(p.as_slice(), "4S1C"));
+        assert_eq!(Err(Error::UnknownPlayer("P0".to_string())), parse_play(p.as_slice(), "P1: Knight -> P0"));
+        assert_eq!(Err(Error::BadSyntax("P1: Knight at P2".to_string())), parse_play(p.as_slice(), "P1: Knight at P2"));
+        assert_eq!(Err(Error::UnknownCard("X".to_string())), parse_play(p.as_slice(), "1X"));
+        assert!(parse_play(p.as_slice(), "").is_err());
+    }
+
+    #[test]
+    fn test_events_round_trip() {
+        let p = make_players();
+        let events = [
+            Event::NoChange,
+            Event::Protected(p[0]),
+            Event::SwappedHands(p[0], p[2]),
+            Event::PlayerEliminated(p[1]),
+            Event::ForcedDiscard(p[1], Card::Princess),
+            Event::ForcedReveal(p[0], p[1]),
+            ];
+        for event in events.iter() {
+            let text = format_event(p.as_slice(), event);
+            assert_eq!(Ok(*event), parse_event(p.as_slice(), text.as_slice()));
+        }
+        assert_eq!("P2 showed P1", format_event(p.as_slice(), &Event::ForcedReveal(p[0], p[1])).as_slice());
+    }
+
+    #[test]
+    fn test_outcomes_round_trip() {
+        let p = make_players();
+        let outcomes = [
+            TurnOutcome::Played(p[0], Card::Soldier, Play::Guess(p[1], Card::Knight),
+                                vec![Event::PlayerEliminated(p[1])]),
+            TurnOutcome::Played(p[1], Card::Priestess, Play::NoEffect, vec![Event::Protected(p[1])]),
+            TurnOutcome::BustedOut(p[2], Card::Minister, Card::Wizard),
+            ];
+        for outcome in outcomes.iter() {
+            let text = format_outcome(p.as_slice(), outcome);
+            assert_eq!(Ok(outcome.clone()), parse_outcome(p.as_slice(), text.as_slice()));
+        }
+        assert_eq!("P1: Soldier -> P2 guess Knight; P2 out", format_outcome(p.as_slice(), &outcomes[0]).as_slice());
+    }
+}
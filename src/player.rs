@@ -23,6 +23,11 @@ impl Player {
         Player { _hand: hand, _protected: false, _discard: vec![] }
     }
 
+    /// Recreate a player from their hand, protection status and discards.
+    pub fn from_parts(hand: Option<Card>, protected: bool, discards: &[Card]) -> Player {
+        Player { _hand: hand, _protected: protected, _discard: discards.to_vec() }
+    }
+
     /// Is this player still playing?
     pub fn active(&self) -> bool {
         self._hand.is_some()
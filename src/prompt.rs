@@ -1,45 +1,225 @@
+/// Asking players questions, and reading their answers.
+///
+/// A `Prompt` writes its questions to one stream and reads answers from
+/// another. Usually those are the terminal, but a file of answers can stand
+/// in for the players, one per line, so that a whole game can be played
+/// without anyone at the keyboard. A scripted prompt echoes each answer, so
+/// its output reads like a transcript, and gives up on the first answer that
+/// doesn't make sense rather than asking again.
+///
+/// A prompt can also be given some help, which is shown instead of asking
+/// again whenever the answer is `help`.
+
+use std::error::FromError;
 use std::fmt;
 use std::io;
 use std::str;
 
-/// Repeatedly prompt the user until they give us something that parses.
-pub fn repeated_prompt<P, T, E>(prompt: &str, parser: P) -> T
-    where P : Fn(&str) -> Result<T, E>, E : fmt::Show {
-    loop {
-        print!("{}", prompt);
-        let input = io::stdin()
-            .read_line()
-            .ok()
-            .expect("Failed to read line");
-        match parser(input.as_slice()) {
-            Ok(value) => return value,
-            Err(err) => println!("{:?}", err),
+use locale::Catalogue;
+
+
+#[derive(Show, PartialEq)]
+pub enum Error {
+    /// Couldn't ask or answer: usually because there are no answers left.
+    Io(io::IoError),
+    /// A scripted answer, and what was wrong with it.
+    BadAnswer(String, String),
+}
+
+
+impl FromError<io::IoError> for Error {
+    fn from_error(err: io::IoError) -> Error {
+        Error::Io(err)
+    }
+}
+
+
+pub struct Prompt<R, W> {
+    _input: R,
+    _output: W,
+    _scripted: bool,
+    _help: Option<String>,
+}
+
+
+impl<R: Buffer, W: Writer> Prompt<R, W> {
+    /// Ask someone at a terminal, who'll be asked again if they get it
+    /// wrong.
+    pub fn new(input: R, output: W) -> Prompt<R, W> {
+        Prompt { _input: input, _output: output, _scripted: false, _help: None }
+    }
+
+    /// Take answers from a script.
+    pub fn scripted(input: R, output: W) -> Prompt<R, W> {
+        Prompt { _input: input, _output: output, _scripted: true, _help: None }
+    }
+
+    pub fn is_scripted(&self) -> bool {
+        self._scripted
+    }
+
+    /// Show `help` to anyone who answers `help`, from now on.
+    pub fn set_help(&mut self, help: String) {
+        self._help = Some(help);
+    }
+
+    /// Stop asking, and get back whatever the questions were written to.
+    pub fn into_output(self) -> W {
+        self._output
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), Error> {
+        try!(self._output.write_str(text));
+        try!(self._output.flush());
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> Result<String, Error> {
+        let line = try!(self._input.read_line());
+        if self._scripted {
+            try!(self.write(format!("{}\n", line.trim_right()).as_slice()));
         }
+        Ok(line)
+    }
+
+    /// Ask `prompt` until the answer is something other than a request for
+    /// help, showing the help each time it is.
+    fn ask(&mut self, prompt: &str) -> Result<String, Error> {
+        loop {
+            try!(self.write(prompt));
+            let line = try!(self.read_line());
+            let help = match self._help {
+                Some(ref help) if line.trim() == "help" => format!("\n{}\n\n", help),
+                _ => return Ok(line),
+            };
+            try!(self.write(help.as_slice()));
+        }
+    }
+
+    /// Repeatedly prompt the user until they give us something that parses.
+    pub fn repeated_prompt<P, T, E>(&mut self, prompt: &str, parser: P) -> Result<T, Error>
+        where P : Fn(&str) -> Result<T, E>, E : fmt::Show {
+        loop {
+            let input = try!(self.ask(prompt));
+            match parser(input.as_slice()) {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    if self._scripted {
+                        return Err(Error::BadAnswer(input.trim().to_string(), format!("{:?}", err)));
+                    }
+                    try!(self.write(format!("{:?}\n", err).as_slice()));
+                }
+            }
+        }
+    }
+
+    /// Wipe the terminal, so nobody can read what was on it.
+    pub fn clear_screen(&mut self) -> Result<(), Error> {
+        // Clear the screen and its scrollback, then move to the top left.
+        self.write("\x1b[2J\x1b[3J\x1b[H")
+    }
+
+    /// Show `prompt` and wait for the user to press Enter. A script needs a
+    /// line, which can be blank, for each time this is asked.
+    pub fn wait_for_enter(&mut self, prompt: &str) -> Result<(), Error> {
+        try!(self.ask(prompt));
+        Ok(())
+    }
+
+    /// Ask for one of `items` by number. Anything else gets the reply from
+    /// `text`.
+    pub fn choose_from_list<'a, T: fmt::Show>(&mut self, text: &Catalogue, prompt: &str, items: &'a [T])
+                                             -> Result<&'a T, Error> {
+        self.choose_labelled(text, prompt, items, |x| format!("{:?}", x))
+    }
+
+    /// Like `choose_from_list`, but with each item shown as `label` says.
+    pub fn choose_labelled<'a, T, F: Fn(&T) -> String>(&mut self, text: &Catalogue, prompt: &str, items: &'a [T],
+                                                      label: F) -> Result<&'a T, Error> {
+        let mut prompt_vec = vec![prompt.to_string()];
+        prompt_vec.push("\n".to_string());
+        for (i, x) in items.iter().enumerate() {
+            prompt_vec.push(format!("  {}. {}\n", i + 1, label(x)));
+        }
+        prompt_vec.push(">>> ".to_string());
+        let i = try!(self.repeated_prompt(prompt_vec.concat().as_slice(),
+                                          |x| read_int_in_range(text, x, items.len())));
+        Ok(&items[i])
     }
 }
 
 
-pub fn read_int_in_range(x: &str, upper: usize) -> Result<usize, String> {
+/// Read a number from 1 to `upper`, and return it counting from 0. If it
+/// isn't one, say so as `text` would.
+pub fn read_int_in_range(text: &Catalogue, x: &str, upper: usize) -> Result<usize, String> {
     match str::FromStr::from_str(x.trim()) {
-        None => Err(format!(
-            "Please enter a number between 1 and {}", upper)),
+        None => Err(text.pick_number(upper)),
         Some(x) =>
             if 1 <= x && x <= upper {
                 Ok(x - 1)
             } else {
-                Err(format!("Please enter a number between 1 and {}", upper))
+                Err(text.pick_number(upper))
             }
     }
 }
 
 
-pub fn choose_from_list<'a, T: fmt::Show>(prompt: &str, items: &'a [T]) -> &'a T {
-    let mut prompt_vec = vec![prompt.to_string()];
-    prompt_vec.push("\n".to_string());
-    for (i, x) in items.iter().enumerate() {
-        prompt_vec.push(format!("  {}. {:?}\n", i + 1, x));
+#[cfg(test)]
+mod test {
+    use std::io::{BufReader, IoErrorKind, MemWriter};
+    use locale::{Catalogue, Language};
+    use super::{Error, Prompt};
+
+    fn english() -> &'static (Catalogue + 'static) {
+        Language::English.catalogue()
+    }
+
+    #[test]
+    fn test_asks_again() {
+        let mut prompt = Prompt::new(BufReader::new(b"7\n2\n"), MemWriter::new());
+        assert_eq!(Ok(&"b"), prompt.choose_from_list(english(), "Pick one", &["a", "b"]));
+        let output = String::from_utf8(prompt.into_output().into_inner()).unwrap();
+        assert_eq!(2, output.matches_str(">>> ").count());
+        assert!(output.contains("Please enter a number between 1 and 2"));
+    }
+
+    #[test]
+    fn test_asks_again_in_language() {
+        let mut prompt = Prompt::new(BufReader::new(b"7
+2
+"), MemWriter::new());
+        assert_eq!(Ok(&"b"), prompt.choose_from_list(Language::French.catalogue(), "Choisissez", &["a", "b"]));
+        let output = String::from_utf8(prompt.into_output().into_inner()).unwrap();
+        assert!(output.contains("Entrez un nombre entre 1 et 2"));
+    }
+
+    #[test]
+    fn test_script_gives_up() {
+        let mut prompt = Prompt::scripted(BufReader::new(b"1\nseven\n"), MemWriter::new());
+        assert_eq!(Ok(&"a"), prompt.choose_from_list(english(), "Pick one", &["a", "b"]));
+        assert_eq!(Err(Error::BadAnswer("seven".to_string(), "\"Please enter a number between 1 and 2\"".to_string())),
+                   prompt.choose_from_list(english(), "Pick one", &["a", "b"]));
+        let output = String::from_utf8(prompt.into_output().into_inner()).unwrap();
+        assert!(output.starts_with("Pick one\n  1. \"a\"\n  2. \"b\"\n>>> 1\n"));
+    }
+
+    #[test]
+    fn test_help() {
+        let mut prompt = Prompt::scripted(BufReader::new(b"help\n2\n"), MemWriter::new());
+        prompt.set_help("Pick b".to_string());
+        assert_eq!(Ok(&"b"), prompt.choose_from_list(english(), "Pick one", &["a", "b"]));
+        let output = String::from_utf8(prompt.into_output().into_inner()).unwrap();
+        assert_eq!(2, output.matches_str(">>> ").count());
+        assert!(output.contains("\nPick b\n"));
+    }
+
+    #[test]
+    fn test_script_runs_out() {
+        let mut prompt = Prompt::scripted(BufReader::new(b"\n"), MemWriter::new());
+        assert_eq!(Ok(()), prompt.wait_for_enter("Press Enter "));
+        match prompt.wait_for_enter("Press Enter ") {
+            Err(Error::Io(e)) => assert_eq!(IoErrorKind::EndOfFile, e.kind),
+            other => panic!("Expected end of file, got {:?}", other),
+        }
     }
-    prompt_vec.push(">>> ".to_string());
-    let i = repeated_prompt(prompt_vec.concat().as_slice(), |x| read_int_in_range(x, items.len()));
-    &items[i]
 }
@@ -0,0 +1,1125 @@
+/// Messages exchanged between a game server and its clients.
+///
+/// Every message is a single JSON object on a line of its own, with a `type`
+/// field saying what sort of message it is. Players are identified by their
+/// seat, numbered from 0 in order of play.
+///
+/// ### Values
+///
+/// * A card is a string: `"Soldier"`, `"Clown"`, `"Knight"`, `"Priestess"`,
+///   `"Wizard"`, `"General"`, `"Minister"` or `"Princess"`.
+/// * A play is an object with `card`, `effect` (`"none"`, `"attack"` or
+///   `"guess"`), `target` (a seat, or `null` if `effect` is `"none"`) and
+///   `guess` (a card, or `null` if `effect` is not `"guess"`).
+/// * A view is what one player can see of the round: `seat`, `hand` (a card,
+///   or `null` if they're out), `draw` (a card, or `null` if it's not their
+///   turn), `discards` (for each seat, a list of cards, oldest first),
+///   `protected` and `active` (for each seat, a boolean), and `deck_size`.
+/// * A spectator view is what someone watching can see: `discards`,
+///   `protected`, `active` and `deck_size` as in a view, plus `hands` (for
+///   each seat, a card or `null`) and `draw`, which are only filled in for
+///   omniscient spectators, and are `null` otherwise.
+/// * An event is an object with `event` and `player` fields, and sometimes
+///   `target` or `card`:
+///   - `{"event": "no_change"}`
+///   - `{"event": "protected", "player": 0}`
+///   - `{"event": "swapped_hands", "player": 0, "target": 1}`
+///   - `{"event": "eliminated", "player": 1}`
+///   - `{"event": "forced_discard", "player": 1, "card": "Knight"}`
+///   - `{"event": "forced_reveal", "player": 0, "target": 1}`
+///
+/// * An emote is one of `"smile"`, `"laugh"`, `"gasp"`, `"sigh"`, `"think"`
+///   or `"bow"`.
+///
+/// ### From client to server
+///
+/// * `{"type": "list_tables"}`: ask which tables there are.
+/// * `{"type": "create_table", "num_players": 3, "variant": "classic"}`: set
+///   up a new table. `variant` is `"quick"` (first to four tokens) or
+///   `"classic"` (the printed rules).
+/// * `{"type": "add_bot", "table": 0, "agent": "heuristic"}`: give the next
+///   free seat at a table to a bot. `agent` is `"random"`, `"heuristic"` or
+///   `"search"`, from weakest to strongest.
+/// * `{"type": "join", "name": "alice", "table": 0}`: take the next free
+///   seat at a table. If `table` is missing or `null`, join the oldest table
+///   that has a free seat. If every seat is taken but some are played by
+///   bots, you take over from one of them at the start of the next round.
+/// * `{"type": "rejoin", "token": "..."}`: take back your seat after losing
+///   your connection, using the token from the `session` message.
+/// * `{"type": "spectate", "table": 0, "omniscient": false}`: watch a
+///   table without playing. Omniscient spectators also see everyone's hands,
+///   but only after a delay, or at the end of the round.
+/// * `{"type": "leave"}`: give up your seat, or stop watching.
+/// * `{"type": "play", "play": <play>}`: make a play. Only valid when it's
+///   your turn.
+/// * `{"type": "chat", "text": "..."}`: say something to everyone at your
+///   table. Long messages are cut short.
+/// * `{"type": "emote", "emote": <emote>}`: pull a face at everyone at your
+///   table. Chat and emotes share a limit of five every ten seconds.
+///
+/// ### From server to client
+///
+/// * `{"type": "tables", "tables": [<table>, ...]}`: every table that's
+///   still playing, where a table is `{"table": 0, "num_players": 2,
+///   "variant": "quick", "winning_score": 4, "names": ["alice", null],
+///   "started": false, "over": false}`.
+/// * `{"type": "table_created", "table": 1}`: your table is ready to join.
+/// * `{"type": "joined", "table": <table>}`: you've sat down at a table.
+/// * `{"type": "left", "table": 1}`: you've left a table.
+/// * `{"type": "spectating", "table": <table>}`: you're watching a table.
+///   You're sent everything everyone at the table can see, but no views.
+/// * `{"type": "spectator_view", "view": <spectator view>}`: what you can
+///   see now, as a spectator.
+/// * `{"type": "session", "token": "..."}`: keep this to get your seat back
+///   with `rejoin` if your connection drops. When you rejoin, you're sent
+///   everything that's happened so far this round.
+/// * `{"type": "bot_takeover", "player": 1}`: the player in this seat has
+///   left or lost their connection, and a bot is playing for them.
+/// * `{"type": "turn_warning", "seconds_left": 10}`: hurry up, or a bot
+///   will play for you this turn. Only sent to you.
+/// * `{"type": "timed_out", "player": 0}`: this player took too long, so a
+///   bot is making their play. The `turn` comes next.
+/// * `{"type": "chat", "player": 0, "text": "..."}`: someone at your table
+///   said something.
+/// * `{"type": "emote", "player": 0, "emote": <emote>}`: someone at your
+///   table pulled a face.
+/// * `{"type": "welcome", "seat": 0, "num_players": 2}`: you've joined the
+///   table.
+/// * `{"type": "seated", "names": ["alice", null]}`: who is sitting where.
+///   Seats given to bots are named after the kind of bot, e.g.
+///   `"heuristic bot"`.
+/// * `{"type": "round_started", "round": 0}`: a new round has been dealt.
+///   Rounds are numbered from 0.
+/// * `{"type": "view", "view": <view>}`: what you can see now.
+/// * `{"type": "your_turn", "view": <view>, "legal_plays": [<play>, ...]}`:
+///   it's your turn. `view.draw` is the card you've just drawn.
+/// * `{"type": "turn", "player": 0, "outcome": "played", "play": <play>,
+///   "events": [<event>, ...]}`: someone made a play.
+/// * `{"type": "turn", "player": 0, "outcome": "busted", "cards": [<card>,
+///   <card>]}`: someone was holding the Minister with a high card.
+/// * `{"type": "reveal", "player": 1, "card": <card>}`: the player you
+///   played the Clown on showed you their card. Only sent to you.
+/// * `{"type": "invalid_play", "reason": "self_target", "message": "...",
+///   "player": 0, "card": "Knight"}`: the engine rejected your play. It's
+///   still your turn. `reason` is one of `"invalid_player"` (with `player`,
+///   which is `null` if they aren't at the table), `"card_not_found"`
+///   (with `card` and `hand`, a list of two cards), `"inactive_player"`
+///   (with `player`), `"self_target"` (with `player` and `card`),
+///   `"bad_action_for_card"` (with `play` and `card`) or `"bad_guess"`.
+/// * `{"type": "round_over", "winners": [{"player": 0, "card": <card>}],
+///   "scores": [1, 0]}`: the round is over, and these are the scores.
+/// * `{"type": "game_over", "winners": [0], "scores": [4, 2]}`: the game is
+///   over.
+/// * `{"type": "error", "message": "..."}`: the server couldn't make sense
+///   of your message.
+
+use std::collections::BTreeMap;
+use std::str;
+
+use serialize::json;
+use serialize::json::{Json, ToJson};
+
+use action::{Event, Play, PlayError};
+use agent::AgentKind;
+use chat::Emote;
+use deck::Card;
+use player_id::{PlayerId, player_id_generator};
+use game::Variant;
+use lobby::TableSummary;
+use round::TurnOutcome;
+use view::{PlayerView, PublicPlayer, SpectatorView};
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+/// A play, with players identified by seat rather than `PlayerId`.
+pub enum SeatPlay {
+    NoEffect,
+    Attack(usize),
+    Guess(usize, Card),
+}
+
+
+impl SeatPlay {
+    /// Translate a play into seats. `players` lists everyone, by seat.
+    pub fn from_play(players: &[PlayerId], play: Play) -> SeatPlay {
+        match play {
+            Play::NoEffect => SeatPlay::NoEffect,
+            Play::Attack(p) => SeatPlay::Attack(seat(players, p)),
+            Play::Guess(p, c) => SeatPlay::Guess(seat(players, p), c),
+        }
+    }
+
+    /// Translate seats into players. Returns `None` if a seat is empty.
+    pub fn to_play(&self, players: &[PlayerId]) -> Option<Play> {
+        match *self {
+            SeatPlay::NoEffect => Some(Play::NoEffect),
+            SeatPlay::Attack(s) => players.get(s).map(|&p| Play::Attack(p)),
+            SeatPlay::Guess(s, c) => players.get(s).map(|&p| Play::Guess(p, c)),
+        }
+    }
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+/// A message from a client.
+pub enum ClientMessage {
+    ListTables,
+    /// A table for this many players, playing this variant.
+    CreateTable(usize, Variant),
+    /// Give a seat at this table to a bot of this kind.
+    AddBot(usize, AgentKind),
+    /// Join as the given name, at the given table or at any open one.
+    Join(String, Option<usize>),
+    /// Take back a seat, with the token from `ServerMessage::Session`.
+    Rejoin(String),
+    /// Watch the given table, seeing everyone's hands if omniscient.
+    Spectate(usize, bool),
+    Leave,
+    Play(Card, SeatPlay),
+    Chat(String),
+    Emote(Emote),
+}
+
+
+#[derive(Show, PartialEq, Clone)]
+/// A message from the server.
+pub enum ServerMessage {
+    Tables(Vec<TableSummary>),
+    TableCreated(usize),
+    Joined(TableSummary),
+    Left(usize),
+    Spectating(TableSummary),
+    SpectatorView(SpectatorView),
+    /// A token for getting your seat back.
+    Session(String),
+    /// A bot is playing for the given player.
+    BotTakeover(PlayerId),
+    /// How many seconds you have left to make your play.
+    TurnWarning(u64),
+    /// The given player ran out of time, so a bot is playing their turn.
+    TimedOut(PlayerId),
+    /// The given player said something.
+    Chat(PlayerId, String),
+    Emote(PlayerId, Emote),
+    /// Your seat, and the number of seats at the table.
+    Welcome(usize, usize),
+    /// The name of the player in each seat.
+    Seated(Vec<Option<String>>),
+    RoundStarted(usize),
+    View(PlayerView),
+    YourTurn(PlayerView),
+    Turn(TurnOutcome),
+    /// The given player is holding the given card.
+    Reveal(PlayerId, Card),
+    InvalidPlay(PlayError),
+    /// The winners of the round with their cards, and everyone's scores.
+    RoundOver(Vec<(PlayerId, Card)>, Vec<u32>),
+    /// The winners of the game, and everyone's scores.
+    GameOver(Vec<PlayerId>, Vec<u32>),
+    Error(String),
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+/// Why a message couldn't be understood.
+pub enum ProtocolError {
+    /// Not valid JSON.
+    BadJson,
+    /// Valid JSON, but not a message we know about.
+    BadMessage(String),
+}
+
+
+/// Parse a line of JSON from a client.
+pub fn decode_client_message(line: &str) -> Result<ClientMessage, ProtocolError> {
+    let message = try!(json::from_str(line).map_err(|_| ProtocolError::BadJson));
+    match try!(get_string(&message, "type")).as_slice() {
+        "list_tables" => Ok(ClientMessage::ListTables),
+        "create_table" => {
+            let variant = try!(get_string(&message, "variant"));
+            match str::FromStr::from_str(variant.as_slice()) {
+                Some(variant) => Ok(ClientMessage::CreateTable(try!(get_usize(&message, "num_players")), variant)),
+                None => Err(ProtocolError::BadMessage(format!("Unknown variant: {}", variant))),
+            }
+        },
+        "add_bot" => {
+            let agent = try!(get_string(&message, "agent"));
+            match str::FromStr::from_str(agent.as_slice()) {
+                Some(agent) => Ok(ClientMessage::AddBot(try!(get_usize(&message, "table")), agent)),
+                None => Err(ProtocolError::BadMessage(format!("Unknown agent: {}", agent))),
+            }
+        },
+        "join" => {
+            let table = match message.find("table") {
+                None | Some(&Json::Null) => None,
+                Some(..) => Some(try!(get_usize(&message, "table"))),
+            };
+            Ok(ClientMessage::Join(try!(get_string(&message, "name")), table))
+        },
+        "rejoin" => Ok(ClientMessage::Rejoin(try!(get_string(&message, "token")))),
+        "spectate" => {
+            let omniscient = match message.find("omniscient") {
+                None | Some(&Json::Null) => false,
+                Some(omniscient) => try!(as_bool(omniscient)),
+            };
+            Ok(ClientMessage::Spectate(try!(get_usize(&message, "table")), omniscient))
+        },
+        "leave" => Ok(ClientMessage::Leave),
+        "play" => {
+            let play = try!(get(&message, "play"));
+            let (card, seat_play) = try!(seat_play_from_json(play));
+            Ok(ClientMessage::Play(card, seat_play))
+        },
+        "chat" => Ok(ClientMessage::Chat(try!(get_string(&message, "text")))),
+        "emote" => Ok(ClientMessage::Emote(try!(get_emote(&message)))),
+        other => Err(ProtocolError::BadMessage(format!("Unknown message type: {}", other))),
+    }
+}
+
+
+/// Turn a client message into a line of JSON.
+pub fn encode_client_message(message: &ClientMessage) -> String {
+    let mut obj = BTreeMap::new();
+    match *message {
+        ClientMessage::ListTables => {
+            obj.insert("type".to_string(), "list_tables".to_json());
+        },
+        ClientMessage::CreateTable(num_players, variant) => {
+            obj.insert("type".to_string(), "create_table".to_json());
+            obj.insert("num_players".to_string(), num_players.to_json());
+            obj.insert("variant".to_string(), format!("{}", variant).to_json());
+        },
+        ClientMessage::AddBot(table, agent) => {
+            obj.insert("type".to_string(), "add_bot".to_json());
+            obj.insert("table".to_string(), table.to_json());
+            obj.insert("agent".to_string(), format!("{}", agent).to_json());
+        },
+        ClientMessage::Join(ref name, table) => {
+            obj.insert("type".to_string(), "join".to_json());
+            obj.insert("name".to_string(), name.to_json());
+            obj.insert("table".to_string(), table.to_json());
+        },
+        ClientMessage::Rejoin(ref token) => {
+            obj.insert("type".to_string(), "rejoin".to_json());
+            obj.insert("token".to_string(), token.to_json());
+        },
+        ClientMessage::Spectate(table, omniscient) => {
+            obj.insert("type".to_string(), "spectate".to_json());
+            obj.insert("table".to_string(), table.to_json());
+            obj.insert("omniscient".to_string(), omniscient.to_json());
+        },
+        ClientMessage::Leave => {
+            obj.insert("type".to_string(), "leave".to_json());
+        },
+        ClientMessage::Play(card, play) => {
+            obj.insert("type".to_string(), "play".to_json());
+            obj.insert("play".to_string(), seat_play_to_json(card, play));
+        },
+        ClientMessage::Chat(ref text) => {
+            obj.insert("type".to_string(), "chat".to_json());
+            obj.insert("text".to_string(), text.to_json());
+        },
+        ClientMessage::Emote(emote) => {
+            obj.insert("type".to_string(), "emote".to_json());
+            obj.insert("emote".to_string(), format!("{}", emote).to_json());
+        },
+    }
+    Json::Object(obj).to_string()
+}
+
+
+/// Turn a server message into a line of JSON. `players` lists everyone at
+/// the table, by seat.
+pub fn encode_server_message(players: &[PlayerId], message: &ServerMessage) -> String {
+    server_message_to_json(players, message).to_string()
+}
+
+
+/// Parse a line of JSON from the server. `players` lists everyone at the
+/// table, by seat; it can be empty until the server has said how many seats
+/// there are.
+pub fn decode_server_message(players: &[PlayerId], line: &str) -> Result<ServerMessage, ProtocolError> {
+    let message = try!(json::from_str(line).map_err(|_| ProtocolError::BadJson));
+    server_message_from_json(players, &message)
+}
+
+
+/// Read a message from the server that's already been parsed as JSON.
+pub fn server_message_from_json(players: &[PlayerId], m: &Json) -> Result<ServerMessage, ProtocolError> {
+    match try!(get_string(m, "type")).as_slice() {
+        "tables" => {
+            let mut tables = vec![];
+            for table in try!(get_array(m, "tables")).iter() {
+                tables.push(try!(table_from_json(table)));
+            }
+            Ok(ServerMessage::Tables(tables))
+        },
+        "table_created" => Ok(ServerMessage::TableCreated(try!(get_usize(m, "table")))),
+        "joined" => Ok(ServerMessage::Joined(try!(table_from_json(try!(get(m, "table")))))),
+        "left" => Ok(ServerMessage::Left(try!(get_usize(m, "table")))),
+        "spectating" => Ok(ServerMessage::Spectating(try!(table_from_json(try!(get(m, "table")))))),
+        "spectator_view" => Ok(ServerMessage::SpectatorView(
+            try!(spectator_view_from_json(players, try!(get(m, "view")))))),
+        "session" => Ok(ServerMessage::Session(try!(get_string(m, "token")))),
+        "bot_takeover" => Ok(ServerMessage::BotTakeover(try!(player_from_json(players, try!(get(m, "player")))))),
+        "turn_warning" => Ok(ServerMessage::TurnWarning(try!(get_u64(m, "seconds_left")))),
+        "timed_out" => Ok(ServerMessage::TimedOut(try!(player_from_json(players, try!(get(m, "player")))))),
+        "chat" => Ok(ServerMessage::Chat(
+            try!(player_from_json(players, try!(get(m, "player")))), try!(get_string(m, "text")))),
+        "emote" => Ok(ServerMessage::Emote(
+            try!(player_from_json(players, try!(get(m, "player")))), try!(get_emote(m)))),
+        "welcome" => Ok(ServerMessage::Welcome(try!(get_usize(m, "seat")), try!(get_usize(m, "num_players")))),
+        "seated" => {
+            let names = try!(get_array(m, "names")).iter().map(|n| n.as_string().map(|s| s.to_string())).collect();
+            Ok(ServerMessage::Seated(names))
+        },
+        "round_started" => Ok(ServerMessage::RoundStarted(try!(get_usize(m, "round")))),
+        "view" => Ok(ServerMessage::View(try!(view_from_json(players, try!(get(m, "view")))))),
+        "your_turn" => Ok(ServerMessage::YourTurn(try!(view_from_json(players, try!(get(m, "view")))))),
+        "turn" => {
+            let player = try!(player_from_json(players, try!(get(m, "player"))));
+            match try!(get_string(m, "outcome")).as_slice() {
+                "busted" => {
+                    let cards = try!(cards_from_json(try!(get(m, "cards"))));
+                    match cards.as_slice() {
+                        [a, b] => Ok(ServerMessage::Turn(TurnOutcome::BustedOut(player, a, b))),
+                        _ => Err(ProtocolError::BadMessage("cards must have two cards".to_string())),
+                    }
+                },
+                "played" => {
+                    let (card, play) = try!(play_from_json(players, try!(get(m, "play"))));
+                    let mut events = vec![];
+                    for event in try!(get_array(m, "events")).iter() {
+                        events.push(try!(event_from_json(players, event)));
+                    }
+                    Ok(ServerMessage::Turn(TurnOutcome::Played(player, card, play, events)))
+                },
+                other => Err(ProtocolError::BadMessage(format!("Unknown outcome: {}", other))),
+            }
+        },
+        "reveal" => Ok(ServerMessage::Reveal(
+            try!(player_from_json(players, try!(get(m, "player")))),
+            try!(card_from_json(try!(get(m, "card")))))),
+        "invalid_play" => Ok(ServerMessage::InvalidPlay(try!(play_error_from_json(players, m)))),
+        "round_over" => {
+            let mut winners = vec![];
+            for winner in try!(get_array(m, "winners")).iter() {
+                winners.push((try!(player_from_json(players, try!(get(winner, "player")))),
+                              try!(card_from_json(try!(get(winner, "card"))))));
+            }
+            Ok(ServerMessage::RoundOver(winners, try!(get_scores(m))))
+        },
+        "game_over" => {
+            let mut winners = vec![];
+            for winner in try!(get_array(m, "winners")).iter() {
+                winners.push(try!(player_from_json(players, winner)));
+            }
+            Ok(ServerMessage::GameOver(winners, try!(get_scores(m))))
+        },
+        "error" => Ok(ServerMessage::Error(try!(get_string(m, "message")))),
+        other => Err(ProtocolError::BadMessage(format!("Unknown message type: {}", other))),
+    }
+}
+
+
+pub fn server_message_to_json(players: &[PlayerId], message: &ServerMessage) -> Json {
+    let mut obj = BTreeMap::new();
+    let message_type = match *message {
+        ServerMessage::Tables(ref tables) => {
+            obj.insert("tables".to_string(), Json::Array(tables.iter().map(table_to_json).collect()));
+            "tables"
+        },
+        ServerMessage::TableCreated(table) => {
+            obj.insert("table".to_string(), table.to_json());
+            "table_created"
+        },
+        ServerMessage::Joined(ref table) => {
+            obj.insert("table".to_string(), table_to_json(table));
+            "joined"
+        },
+        ServerMessage::Left(table) => {
+            obj.insert("table".to_string(), table.to_json());
+            "left"
+        },
+        ServerMessage::Spectating(ref table) => {
+            obj.insert("table".to_string(), table_to_json(table));
+            "spectating"
+        },
+        ServerMessage::SpectatorView(ref view) => {
+            obj.insert("view".to_string(), spectator_view_to_json(view));
+            "spectator_view"
+        },
+        ServerMessage::Session(ref token) => {
+            obj.insert("token".to_string(), token.to_json());
+            "session"
+        },
+        ServerMessage::BotTakeover(player) => {
+            obj.insert("player".to_string(), seat(players, player).to_json());
+            "bot_takeover"
+        },
+        ServerMessage::TurnWarning(seconds) => {
+            obj.insert("seconds_left".to_string(), seconds.to_json());
+            "turn_warning"
+        },
+        ServerMessage::TimedOut(player) => {
+            obj.insert("player".to_string(), seat(players, player).to_json());
+            "timed_out"
+        },
+        ServerMessage::Chat(player, ref text) => {
+            obj.insert("player".to_string(), seat(players, player).to_json());
+            obj.insert("text".to_string(), text.to_json());
+            "chat"
+        },
+        ServerMessage::Emote(player, emote) => {
+            obj.insert("player".to_string(), seat(players, player).to_json());
+            obj.insert("emote".to_string(), format!("{}", emote).to_json());
+            "emote"
+        },
+        ServerMessage::Welcome(seat, num_players) => {
+            obj.insert("seat".to_string(), seat.to_json());
+            obj.insert("num_players".to_string(), num_players.to_json());
+            "welcome"
+        },
+        ServerMessage::Seated(ref names) => {
+            obj.insert("names".to_string(), names.to_json());
+            "seated"
+        },
+        ServerMessage::RoundStarted(round) => {
+            obj.insert("round".to_string(), round.to_json());
+            "round_started"
+        },
+        ServerMessage::View(ref view) => {
+            obj.insert("view".to_string(), view_to_json(players, view));
+            "view"
+        },
+        ServerMessage::YourTurn(ref view) => {
+            obj.insert("view".to_string(), view_to_json(players, view));
+            obj.insert(
+                "legal_plays".to_string(),
+                Json::Array(view.legal_plays().iter().map(|&(c, p)| play_to_json(players, c, p)).collect()));
+            "your_turn"
+        },
+        ServerMessage::Turn(ref outcome) => {
+            match *outcome {
+                TurnOutcome::BustedOut(player, a, b) => {
+                    obj.insert("player".to_string(), seat(players, player).to_json());
+                    obj.insert("outcome".to_string(), "busted".to_json());
+                    obj.insert("cards".to_string(), Json::Array(vec![card_to_json(a), card_to_json(b)]));
+                },
+                TurnOutcome::Played(player, card, play, ref events) => {
+                    obj.insert("player".to_string(), seat(players, player).to_json());
+                    obj.insert("outcome".to_string(), "played".to_json());
+                    obj.insert("play".to_string(), play_to_json(players, card, play));
+                    obj.insert(
+                        "events".to_string(),
+                        Json::Array(events.iter().map(|e| event_to_json(players, e)).collect()));
+                },
+            }
+            "turn"
+        },
+        ServerMessage::Reveal(player, card) => {
+            obj.insert("player".to_string(), seat(players, player).to_json());
+            obj.insert("card".to_string(), card_to_json(card));
+            "reveal"
+        },
+        ServerMessage::InvalidPlay(ref error) => {
+            obj.insert("reason".to_string(), play_error_reason(error).to_json());
+            obj.insert("message".to_string(), format!("{:?}", error).to_json());
+            match *error {
+                PlayError::InvalidPlayer(p) | PlayError::InactivePlayer(p) => {
+                    let s = players.iter().position(|&q| q == p);
+                    obj.insert("player".to_string(), s.map(|s| s.to_json()).unwrap_or(Json::Null));
+                },
+                PlayError::CardNotFound(card, (a, b)) => {
+                    obj.insert("card".to_string(), card_to_json(card));
+                    obj.insert("hand".to_string(), cards_to_json(&[a, b]));
+                },
+                PlayError::SelfTarget(p, card) => {
+                    obj.insert("player".to_string(), seat(players, p).to_json());
+                    obj.insert("card".to_string(), card_to_json(card));
+                },
+                PlayError::BadActionForCard(play, card) => {
+                    obj.insert("play".to_string(), play_to_json(players, card, play));
+                    obj.insert("card".to_string(), card_to_json(card));
+                },
+                PlayError::BadGuess => (),
+            }
+            "invalid_play"
+        },
+        ServerMessage::RoundOver(ref winners, ref scores) => {
+            let winners: Vec<Json> = winners.iter().map(|&(p, c)| {
+                let mut winner = BTreeMap::new();
+                winner.insert("player".to_string(), seat(players, p).to_json());
+                winner.insert("card".to_string(), card_to_json(c));
+                Json::Object(winner)
+            }).collect();
+            obj.insert("winners".to_string(), Json::Array(winners));
+            obj.insert("scores".to_string(), scores.to_json());
+            "round_over"
+        },
+        ServerMessage::GameOver(ref winners, ref scores) => {
+            obj.insert("winners".to_string(), seats(players, winners.as_slice()).to_json());
+            obj.insert("scores".to_string(), scores.to_json());
+            "game_over"
+        },
+        ServerMessage::Error(ref message) => {
+            obj.insert("message".to_string(), message.to_json());
+            "error"
+        },
+    };
+    obj.insert("type".to_string(), message_type.to_json());
+    Json::Object(obj)
+}
+
+
+/// A short, stable name for each kind of `PlayError`.
+pub fn play_error_reason(error: &PlayError) -> &'static str {
+    match *error {
+        PlayError::InvalidPlayer(..) => "invalid_player",
+        PlayError::CardNotFound(..) => "card_not_found",
+        PlayError::InactivePlayer(..) => "inactive_player",
+        PlayError::SelfTarget(..) => "self_target",
+        PlayError::BadActionForCard(..) => "bad_action_for_card",
+        PlayError::BadGuess => "bad_guess",
+    }
+}
+
+
+fn play_error_from_json(players: &[PlayerId], json: &Json) -> Result<PlayError, ProtocolError> {
+    let player = || player_from_json(players, try!(get(json, "player")));
+    let card = || card_from_json(try!(get(json, "card")));
+    match try!(get_string(json, "reason")).as_slice() {
+        "invalid_player" => if try!(get(json, "player")).is_null() {
+            Ok(PlayError::InvalidPlayer(stranger(players)))
+        } else {
+            Ok(PlayError::InvalidPlayer(try!(player())))
+        },
+        "card_not_found" => {
+            let hand = try!(cards_from_json(try!(get(json, "hand"))));
+            match hand.as_slice() {
+                [a, b] => Ok(PlayError::CardNotFound(try!(card()), (a, b))),
+                _ => Err(ProtocolError::BadMessage("hand must have two cards".to_string())),
+            }
+        },
+        "inactive_player" => Ok(PlayError::InactivePlayer(try!(player()))),
+        "self_target" => Ok(PlayError::SelfTarget(try!(player()), try!(card()))),
+        "bad_action_for_card" => {
+            let (_, play) = try!(play_from_json(players, try!(get(json, "play"))));
+            Ok(PlayError::BadActionForCard(play, try!(card())))
+        },
+        "bad_guess" => Ok(PlayError::BadGuess),
+        other => Err(ProtocolError::BadMessage(format!("Unknown reason: {}", other))),
+    }
+}
+
+
+/// Someone who isn't one of `players`, for errors about players who aren't
+/// at the table.
+fn stranger(players: &[PlayerId]) -> PlayerId {
+    player_id_generator().find(|p| !players.contains(p)).expect("Ran out of player IDs")
+}
+
+
+fn seat(players: &[PlayerId], player: PlayerId) -> usize {
+    players.iter().position(|&p| p == player).expect("Unknown player")
+}
+
+
+fn seats(players: &[PlayerId], ps: &[PlayerId]) -> Vec<usize> {
+    ps.iter().map(|&p| seat(players, p)).collect()
+}
+
+
+pub fn card_to_json(card: Card) -> Json {
+    Json::String(format!("{:?}", card))
+}
+
+
+pub fn card_from_json(json: &Json) -> Result<Card, ProtocolError> {
+    match json.as_string().and_then(|name| str::FromStr::from_str(name)) {
+        Some(card) => Ok(card),
+        None => Err(ProtocolError::BadMessage(format!("Not a card: {}", json))),
+    }
+}
+
+
+pub fn cards_to_json(cards: &[Card]) -> Json {
+    Json::Array(cards.iter().map(|&c| card_to_json(c)).collect())
+}
+
+
+pub fn cards_from_json(json: &Json) -> Result<Vec<Card>, ProtocolError> {
+    let mut cards = vec![];
+    for card in try!(as_array(json)).iter() {
+        cards.push(try!(card_from_json(card)));
+    }
+    Ok(cards)
+}
+
+
+/// The player in the seat given by `json`.
+pub fn player_from_json(players: &[PlayerId], json: &Json) -> Result<PlayerId, ProtocolError> {
+    match json.as_u64().and_then(|s| players.get(s as usize)) {
+        Some(&player) => Ok(player),
+        None => Err(ProtocolError::BadMessage(format!("Not a seat: {}", json))),
+    }
+}
+
+
+/// Read a play, turning seats into players.
+pub fn play_from_json(players: &[PlayerId], json: &Json) -> Result<(Card, Play), ProtocolError> {
+    let (card, seat_play) = try!(seat_play_from_json(json));
+    match seat_play.to_play(players) {
+        Some(play) => Ok((card, play)),
+        None => Err(ProtocolError::BadMessage(format!("No such seat in play: {}", json))),
+    }
+}
+
+
+/// Turn a play into JSON. `players` lists everyone, by seat.
+pub fn play_to_json(players: &[PlayerId], card: Card, play: Play) -> Json {
+    seat_play_to_json(card, SeatPlay::from_play(players, play))
+}
+
+
+pub fn seat_play_to_json(card: Card, play: SeatPlay) -> Json {
+    let (effect, target, guess) = match play {
+        SeatPlay::NoEffect => ("none", Json::Null, Json::Null),
+        SeatPlay::Attack(s) => ("attack", s.to_json(), Json::Null),
+        SeatPlay::Guess(s, c) => ("guess", s.to_json(), card_to_json(c)),
+    };
+    let mut obj = BTreeMap::new();
+    obj.insert("card".to_string(), card_to_json(card));
+    obj.insert("effect".to_string(), effect.to_json());
+    obj.insert("target".to_string(), target);
+    obj.insert("guess".to_string(), guess);
+    Json::Object(obj)
+}
+
+
+pub fn seat_play_from_json(json: &Json) -> Result<(Card, SeatPlay), ProtocolError> {
+    let card = try!(card_from_json(try!(get(json, "card"))));
+    let target = || -> Result<usize, ProtocolError> {
+        match try!(get(json, "target")).as_u64() {
+            Some(s) => Ok(s as usize),
+            None => Err(ProtocolError::BadMessage("target must be a seat number".to_string())),
+        }
+    };
+    let play = match try!(get_string(json, "effect")).as_slice() {
+        "none" => SeatPlay::NoEffect,
+        "attack" => SeatPlay::Attack(try!(target())),
+        "guess" => SeatPlay::Guess(try!(target()), try!(card_from_json(try!(get(json, "guess"))))),
+        other => return Err(ProtocolError::BadMessage(format!("Unknown effect: {}", other))),
+    };
+    Ok((card, play))
+}
+
+
+pub fn table_to_json(table: &TableSummary) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("table".to_string(), table.id.to_json());
+    obj.insert("num_players".to_string(), table.num_players.to_json());
+    obj.insert("variant".to_string(), format!("{}", table.variant).to_json());
+    obj.insert("winning_score".to_string(), table.winning_score.to_json());
+    obj.insert("names".to_string(), table.names.to_json());
+    obj.insert("started".to_string(), table.started.to_json());
+    obj.insert("over".to_string(), table.over.to_json());
+    Json::Object(obj)
+}
+
+
+pub fn table_from_json(json: &Json) -> Result<TableSummary, ProtocolError> {
+    let variant = try!(get_string(json, "variant"));
+    let variant = match str::FromStr::from_str(variant.as_slice()) {
+        Some(variant) => variant,
+        None => return Err(ProtocolError::BadMessage(format!("Unknown variant: {}", variant))),
+    };
+    Ok(TableSummary {
+        id: try!(get_usize(json, "table")),
+        num_players: try!(get_usize(json, "num_players")),
+        variant: variant,
+        winning_score: try!(get_usize(json, "winning_score")) as u32,
+        names: try!(get_array(json, "names")).iter().map(|n| n.as_string().map(|s| s.to_string())).collect(),
+        started: try!(as_bool(try!(get(json, "started")))),
+        over: try!(as_bool(try!(get(json, "over")))),
+    })
+}
+
+
+/// Turn a player's view into JSON. `players` lists everyone, by seat.
+pub fn view_to_json(players: &[PlayerId], view: &PlayerView) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("seat".to_string(), seat(players, view.player).to_json());
+    obj.insert("hand".to_string(), view.hand.map(card_to_json).unwrap_or(Json::Null));
+    obj.insert("draw".to_string(), view.draw.map(card_to_json).unwrap_or(Json::Null));
+    insert_public_players(&mut obj, view.players.as_slice());
+    obj.insert("deck_size".to_string(), view.deck_size.to_json());
+    Json::Object(obj)
+}
+
+
+pub fn spectator_view_to_json(view: &SpectatorView) -> Json {
+    let mut obj = BTreeMap::new();
+    let hands = match view.hands {
+        Some(ref hands) => Json::Array(hands.iter().map(|h| h.map(card_to_json).unwrap_or(Json::Null)).collect()),
+        None => Json::Null,
+    };
+    obj.insert("hands".to_string(), hands);
+    obj.insert("draw".to_string(), view.draw.map(card_to_json).unwrap_or(Json::Null));
+    insert_public_players(&mut obj, view.players.as_slice());
+    obj.insert("deck_size".to_string(), view.deck_size.to_json());
+    Json::Object(obj)
+}
+
+
+/// Add what everyone can see of each player to a view.
+fn insert_public_players(obj: &mut BTreeMap<String, Json>, public: &[PublicPlayer]) {
+    obj.insert(
+        "discards".to_string(),
+        Json::Array(public.iter().map(|p| cards_to_json(p.discards.as_slice())).collect()));
+    obj.insert(
+        "protected".to_string(),
+        public.iter().map(|p| p.protected).collect::<Vec<bool>>().to_json());
+    obj.insert(
+        "active".to_string(),
+        public.iter().map(|p| p.active).collect::<Vec<bool>>().to_json());
+}
+
+
+/// Read what everyone can see of each player from a view.
+fn public_players_from_json(players: &[PlayerId], json: &Json) -> Result<Vec<PublicPlayer>, ProtocolError> {
+    let discards = try!(get_array(json, "discards"));
+    let protected = try!(get_array(json, "protected"));
+    let active = try!(get_array(json, "active"));
+    if discards.len() != players.len() || protected.len() != players.len() || active.len() != players.len() {
+        return Err(ProtocolError::BadMessage("View doesn't have one entry per seat".to_string()));
+    }
+    let mut public = vec![];
+    for (i, &id) in players.iter().enumerate() {
+        public.push(PublicPlayer {
+            id: id,
+            discards: try!(cards_from_json(&discards[i])),
+            protected: try!(as_bool(&protected[i])),
+            active: try!(as_bool(&active[i])),
+        });
+    }
+    Ok(public)
+}
+
+
+/// Read a player's view. `players` lists everyone, by seat.
+pub fn view_from_json(players: &[PlayerId], json: &Json) -> Result<PlayerView, ProtocolError> {
+    let optional_card = |key| -> Result<Option<Card>, ProtocolError> {
+        match try!(get(json, key)) {
+            &Json::Null => Ok(None),
+            card => card_from_json(card).map(|c| Some(c)),
+        }
+    };
+    Ok(PlayerView {
+        player: try!(player_from_json(players, try!(get(json, "seat")))),
+        hand: try!(optional_card("hand")),
+        draw: try!(optional_card("draw")),
+        players: try!(public_players_from_json(players, json)),
+        deck_size: try!(get_usize(json, "deck_size")),
+    })
+}
+
+
+/// Read a spectator's view. `players` lists everyone, by seat.
+pub fn spectator_view_from_json(players: &[PlayerId], json: &Json) -> Result<SpectatorView, ProtocolError> {
+    let optional_card = |card: &Json| -> Result<Option<Card>, ProtocolError> {
+        match *card {
+            Json::Null => Ok(None),
+            _ => card_from_json(card).map(|c| Some(c)),
+        }
+    };
+    let hands = match try!(get(json, "hands")) {
+        &Json::Null => None,
+        hands => {
+            let mut cards = vec![];
+            for hand in try!(as_array(hands)).iter() {
+                cards.push(try!(optional_card(hand)));
+            }
+            if cards.len() != players.len() {
+                return Err(ProtocolError::BadMessage("View doesn't have one hand per seat".to_string()));
+            }
+            Some(cards)
+        },
+    };
+    Ok(SpectatorView {
+        players: try!(public_players_from_json(players, json)),
+        deck_size: try!(get_usize(json, "deck_size")),
+        hands: hands,
+        draw: try!(optional_card(try!(get(json, "draw")))),
+    })
+}
+
+
+pub fn event_to_json(players: &[PlayerId], event: &Event) -> Json {
+    let mut obj = BTreeMap::new();
+    let name = match *event {
+        Event::NoChange => "no_change",
+        Event::Protected(p) => {
+            obj.insert("player".to_string(), seat(players, p).to_json());
+            "protected"
+        },
+        Event::SwappedHands(p, q) => {
+            obj.insert("player".to_string(), seat(players, p).to_json());
+            obj.insert("target".to_string(), seat(players, q).to_json());
+            "swapped_hands"
+        },
+        Event::PlayerEliminated(p) => {
+            obj.insert("player".to_string(), seat(players, p).to_json());
+            "eliminated"
+        },
+        Event::ForcedDiscard(p, c) => {
+            obj.insert("player".to_string(), seat(players, p).to_json());
+            obj.insert("card".to_string(), card_to_json(c));
+            "forced_discard"
+        },
+        Event::ForcedReveal(p, q) => {
+            obj.insert("player".to_string(), seat(players, p).to_json());
+            obj.insert("target".to_string(), seat(players, q).to_json());
+            "forced_reveal"
+        },
+    };
+    obj.insert("event".to_string(), name.to_json());
+    Json::Object(obj)
+}
+
+
+pub fn event_from_json(players: &[PlayerId], json: &Json) -> Result<Event, ProtocolError> {
+    let player = || player_from_json(players, try!(get(json, "player")));
+    let target = || player_from_json(players, try!(get(json, "target")));
+    match try!(get_string(json, "event")).as_slice() {
+        "no_change" => Ok(Event::NoChange),
+        "protected" => Ok(Event::Protected(try!(player()))),
+        "swapped_hands" => Ok(Event::SwappedHands(try!(player()), try!(target()))),
+        "eliminated" => Ok(Event::PlayerEliminated(try!(player()))),
+        "forced_discard" => Ok(Event::ForcedDiscard(try!(player()), try!(card_from_json(try!(get(json, "card")))))),
+        "forced_reveal" => Ok(Event::ForcedReveal(try!(player()), try!(target()))),
+        other => Err(ProtocolError::BadMessage(format!("Unknown event: {}", other))),
+    }
+}
+
+
+fn get<'a>(json: &'a Json, key: &str) -> Result<&'a Json, ProtocolError> {
+    json.find(key).ok_or(ProtocolError::BadMessage(format!("Missing field: {}", key)))
+}
+
+
+fn get_string(json: &Json, key: &str) -> Result<String, ProtocolError> {
+    match try!(get(json, key)).as_string() {
+        Some(s) => Ok(s.to_string()),
+        None => Err(ProtocolError::BadMessage(format!("{} must be a string", key))),
+    }
+}
+
+
+
+fn get_usize(json: &Json, key: &str) -> Result<usize, ProtocolError> {
+    get_u64(json, key).map(|n| n as usize)
+}
+
+
+fn get_u64(json: &Json, key: &str) -> Result<u64, ProtocolError> {
+    match try!(get(json, key)).as_u64() {
+        Some(n) => Ok(n),
+        None => Err(ProtocolError::BadMessage(format!("{} must be a number", key))),
+    }
+}
+
+
+fn get_emote(json: &Json) -> Result<Emote, ProtocolError> {
+    let name = try!(get_string(json, "emote"));
+    match str::FromStr::from_str(name.as_slice()) {
+        Some(emote) => Ok(emote),
+        None => Err(ProtocolError::BadMessage(format!("Unknown emote: {}", name))),
+    }
+}
+
+
+fn get_array<'a>(json: &'a Json, key: &str) -> Result<&'a Vec<Json>, ProtocolError> {
+    as_array(try!(get(json, key)))
+}
+
+
+fn get_scores(json: &Json) -> Result<Vec<u32>, ProtocolError> {
+    let mut scores = vec![];
+    for score in try!(get_array(json, "scores")).iter() {
+        match score.as_u64() {
+            Some(n) => scores.push(n as u32),
+            None => return Err(ProtocolError::BadMessage(format!("Not a score: {}", score))),
+        }
+    }
+    Ok(scores)
+}
+
+
+fn as_array(json: &Json) -> Result<&Vec<Json>, ProtocolError> {
+    json.as_array().ok_or(ProtocolError::BadMessage(format!("Not a list: {}", json)))
+}
+
+
+fn as_bool(json: &Json) -> Result<bool, ProtocolError> {
+    json.as_boolean().ok_or(ProtocolError::BadMessage(format!("Not a boolean: {}", json)))
+}
+
+
+#[cfg(test)]
+mod test {
+    use serialize::json;
+
+    use action::{Event, Play, PlayError};
+    use agent::AgentKind;
+    use chat::Emote;
+    use deck::Card;
+    use game::Variant;
+    use lobby::TableSummary;
+    use player_id::{PlayerId, player_id_generator};
+    use round::{Round, TurnOutcome};
+    use super::{ClientMessage, ProtocolError, SeatPlay, ServerMessage};
+    use super::{decode_client_message, decode_server_message, encode_client_message};
+    use super::encode_server_message;
+
+    fn make_players() -> Vec<PlayerId> {
+        player_id_generator().take(3).collect()
+    }
+
+    #[test]
+    fn test_client_round_trip() {
+        let messages = [
+            ClientMessage::ListTables,
+            ClientMessage::CreateTable(3, Variant::Classic),
+            ClientMessage::AddBot(0, AgentKind::Search),
+            ClientMessage::Join("alice".to_string(), None),
+            ClientMessage::Join("bob".to_string(), Some(2)),
+            ClientMessage::Rejoin("0123abcd".to_string()),
+            ClientMessage::Spectate(1, false),
+            ClientMessage::Spectate(0, true),
+            ClientMessage::Leave,
+            ClientMessage::Play(Card::Priestess, SeatPlay::NoEffect),
+            ClientMessage::Play(Card::Clown, SeatPlay::Attack(2)),
+            ClientMessage::Play(Card::Soldier, SeatPlay::Guess(1, Card::Princess)),
+            ClientMessage::Chat("Nice guess.".to_string()),
+            ClientMessage::Emote(Emote::Gasp),
+            ];
+        for message in messages.iter() {
+            let line = encode_client_message(message);
+            assert_eq!(Ok(message.clone()), decode_client_message(line.as_slice()));
+        }
+    }
+
+    #[test]
+    fn test_decode_bad_json() {
+        assert_eq!(Err(ProtocolError::BadJson), decode_client_message("{"));
+    }
+
+    #[test]
+    fn test_decode_bad_card() {
+        let line = r#"{"type": "play", "play": {"card": "Queen", "effect": "none"}}"#;
+        match decode_client_message(line) {
+            Err(ProtocolError::BadMessage(..)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_seat_play_to_play() {
+        let players = make_players();
+        assert_eq!(Some(Play::Attack(players[2])), SeatPlay::Attack(2).to_play(players.as_slice()));
+        assert_eq!(None, SeatPlay::Attack(3).to_play(players.as_slice()));
+        assert_eq!(SeatPlay::Guess(1, Card::Wizard),
+                   SeatPlay::from_play(players.as_slice(), Play::Guess(players[1], Card::Wizard)));
+    }
+
+    #[test]
+    fn test_encode_turn() {
+        let players = make_players();
+        let outcome = TurnOutcome::Played(
+            players[0], Card::Soldier, Play::Guess(players[1], Card::Wizard),
+            vec![Event::PlayerEliminated(players[1])]);
+        let line = encode_server_message(players.as_slice(), &ServerMessage::Turn(outcome));
+        let expected = json::from_str(
+            r#"{"type": "turn", "player": 0, "outcome": "played",
+                "play": {"card": "Soldier", "effect": "guess", "target": 1, "guess": "Wizard"},
+                "events": [{"event": "eliminated", "player": 1}]}"#).unwrap();
+        assert_eq!(expected, json::from_str(line.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_encode_invalid_play() {
+        let players = make_players();
+        let line = encode_server_message(
+            players.as_slice(), &ServerMessage::InvalidPlay(PlayError::BadGuess));
+        let message = json::from_str(line.as_slice()).unwrap();
+        assert_eq!(Some("bad_guess"), message.find("reason").unwrap().as_string());
+    }
+
+    #[test]
+    fn test_server_round_trip() {
+        let players = make_players();
+        let round = Round::new(players.as_slice());
+        let (ready, turn) = round.peek_turn().unwrap();
+        let table = TableSummary {
+            id: 2, num_players: 3, variant: Variant::Quick, winning_score: 4,
+            names: vec![Some("alice".to_string()), None, None], started: false, over: false,
+        };
+        let messages = vec![
+            ServerMessage::Tables(vec![table.clone()]),
+            ServerMessage::TableCreated(2),
+            ServerMessage::Joined(table.clone()),
+            ServerMessage::Left(2),
+            ServerMessage::Spectating(table),
+            ServerMessage::SpectatorView(round.spectator_view(false)),
+            ServerMessage::SpectatorView(ready.spectator_view(true)),
+            ServerMessage::Session("0123abcd".to_string()),
+            ServerMessage::BotTakeover(players[1]),
+            ServerMessage::TurnWarning(10),
+            ServerMessage::TimedOut(players[2]),
+            ServerMessage::Chat(players[0], "Nice guess.".to_string()),
+            ServerMessage::Emote(players[1], Emote::Sigh),
+            ServerMessage::Welcome(1, 3),
+            ServerMessage::Seated(vec![Some("alice".to_string()), None, Some("carol".to_string())]),
+            ServerMessage::RoundStarted(2),
+            ServerMessage::View(round.view(players[1]).unwrap()),
+            ServerMessage::YourTurn(ready.view(turn.player).unwrap()),
+            ServerMessage::Turn(TurnOutcome::BustedOut(players[2], Card::Minister, Card::Princess)),
+            ServerMessage::Turn(TurnOutcome::Played(
+                players[0], Card::Wizard, Play::Attack(players[1]),
+                vec![Event::ForcedDiscard(players[1], Card::Princess), Event::PlayerEliminated(players[1])])),
+            ServerMessage::Reveal(players[2], Card::General),
+            ServerMessage::InvalidPlay(PlayError::InvalidPlayer(players[1])),
+            ServerMessage::InvalidPlay(PlayError::SelfTarget(players[0], Card::Knight)),
+            ServerMessage::InvalidPlay(PlayError::CardNotFound(Card::Soldier, (Card::Clown, Card::Knight))),
+            ServerMessage::InvalidPlay(PlayError::BadActionForCard(Play::NoEffect, Card::Soldier)),
+            ServerMessage::InvalidPlay(PlayError::BadGuess),
+            ServerMessage::RoundOver(vec![(players[0], Card::Princess)], vec![1, 0, 0]),
+            ServerMessage::GameOver(vec![players[2]], vec![1, 2, 4]),
+            ServerMessage::Error("oops".to_string()),
+            ];
+        for message in messages.iter() {
+            let line = encode_server_message(players.as_slice(), message);
+            assert_eq!(Ok(message.clone()), decode_server_message(players.as_slice(), line.as_slice()));
+        }
+    }
+
+    #[test]
+    fn test_decode_player_not_at_table() {
+        let players = make_players();
+        let line = r#"{"type": "invalid_play", "reason": "invalid_player", "message": "", "player": null}"#;
+        match decode_server_message(players.as_slice(), line) {
+            Ok(ServerMessage::InvalidPlay(PlayError::InvalidPlayer(p))) => assert!(!players.contains(&p)),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_unknown_seat() {
+        let line = r#"{"type": "reveal", "player": 5, "card": "Soldier"}"#;
+        match decode_server_message(make_players().as_slice(), line) {
+            Err(ProtocolError::BadMessage(..)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
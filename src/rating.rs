@@ -0,0 +1,281 @@
+/// Persistent skill ratings for players of Love Letter.
+///
+/// Ratings use a multi-player generalisation of Elo. A finished game is
+/// treated as a set of pairwise matches between every pair of players: the
+/// player with more tokens of affection wins the match, and players with the
+/// same number of tokens draw. Because round wins can be shared (see
+/// `RoundResult::winners`), ties are common and are scored as half a win
+/// each.
+///
+/// Ratings are kept in a plain text file with one player per line:
+///
+/// ```text
+/// <name>\t<rating>\t<games played>
+/// ```
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::io::{BufferedReader, File, IoError, IoErrorKind};
+use std::num::Float;
+use std::str;
+
+use game::Game;
+
+
+/// The rating given to a player we've never seen before.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How much a single game can move a player's rating.
+///
+/// Each pairwise match is worth `K_FACTOR / (n - 1)`, so a player's rating
+/// moves by roughly the same amount however many people were at the table.
+const K_FACTOR: f64 = 32.0;
+
+
+#[derive(PartialEq, Show, Clone, Copy)]
+/// A single player's rating.
+pub struct Rating {
+    /// The player's skill estimate. Higher is better.
+    pub rating: f64,
+    /// The number of games that have contributed to this rating.
+    pub games: u32,
+}
+
+
+impl Rating {
+    fn new() -> Rating {
+        Rating { rating: INITIAL_RATING, games: 0 }
+    }
+}
+
+
+#[derive(Show)]
+/// Errors that can occur while reading, writing or updating ratings.
+pub enum Error {
+    /// Could not read or write the ratings file.
+    Io(IoError),
+    /// The ratings file has a line we don't understand. Line numbers start
+    /// at 1.
+    Malformed(usize),
+    /// Tried to record a game that hasn't finished yet.
+    GameNotOver,
+    /// Tried to record a game without giving a name for every player.
+    WrongNumberOfNames(usize, usize),
+}
+
+
+#[derive(PartialEq, Show, Clone)]
+/// The ratings of every player we know about, indexed by name.
+pub struct Ratings {
+    _players: BTreeMap<String, Rating>,
+}
+
+
+impl Ratings {
+    /// Create an empty set of ratings.
+    pub fn new() -> Ratings {
+        Ratings { _players: BTreeMap::new() }
+    }
+
+    /// Get the rating for `name`, or the initial rating if we haven't seen
+    /// them play yet.
+    pub fn get(&self, name: &str) -> Rating {
+        match self._players.get(name) {
+            Some(rating) => *rating,
+            None => Rating::new(),
+        }
+    }
+
+    /// Return every rated player, best first.
+    pub fn leaderboard(&self) -> Vec<(String, Rating)> {
+        let mut players: Vec<(String, Rating)> = self._players
+            .iter()
+            .map(|(name, rating)| (name.clone(), *rating))
+            .collect();
+        players.sort_by(
+            |&(_, a), &(_, b)| b.rating.partial_cmp(&a.rating).expect("Rating is NaN"));
+        players
+    }
+
+    /// Update ratings given the final number of tokens each player earned
+    /// in a game.
+    pub fn record(&self, results: &[(String, u32)]) -> Ratings {
+        let num_players = results.len();
+        if num_players < 2 {
+            return self.clone();
+        }
+        let k = K_FACTOR / ((num_players - 1) as f64);
+        let before: Vec<Rating> = results.iter().map(|&(ref name, _)| self.get(name.as_slice())).collect();
+        let mut new_ratings = self.clone();
+        for (i, &(ref name, score)) in results.iter().enumerate() {
+            let mut delta = 0.0;
+            for (j, &(_, other_score)) in results.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let actual = match score.cmp(&other_score) {
+                    Ordering::Greater => 1.0,
+                    Ordering::Equal => 0.5,
+                    Ordering::Less => 0.0,
+                };
+                delta += k * (actual - expected_score(before[i].rating, before[j].rating));
+            }
+            let rating = Rating { rating: before[i].rating + delta, games: before[i].games + 1 };
+            new_ratings._players.insert(name.clone(), rating);
+        }
+        new_ratings
+    }
+
+    /// Update ratings with the results of a finished game.
+    ///
+    /// `names` gives the name to rate each player under, in the same order
+    /// as `game.players()`.
+    pub fn record_game(&self, game: &Game, names: &[String]) -> Result<Ratings, Error> {
+        if !game.is_over() {
+            return Err(Error::GameNotOver);
+        }
+        let scores = game.scores();
+        if names.len() != scores.len() {
+            return Err(Error::WrongNumberOfNames(scores.len(), names.len()));
+        }
+        let results: Vec<(String, u32)> = names
+            .iter()
+            .zip(scores.iter())
+            .map(|(name, &score)| (name.clone(), score))
+            .collect();
+        Ok(self.record(results.as_slice()))
+    }
+
+    /// Load ratings from `path`. A missing file is the same as having no
+    /// ratings at all.
+    pub fn load(path: &Path) -> Result<Ratings, Error> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind == IoErrorKind::FileNotFound => return Ok(Ratings::new()),
+            Err(e) => return Err(Error::Io(e)),
+        };
+        let mut reader = BufferedReader::new(file);
+        let mut lines = vec![];
+        for line in reader.lines() {
+            lines.push(try!(line.map_err(Error::Io)));
+        }
+        Ratings::parse(lines.iter().map(|x| x.as_slice()))
+    }
+
+    /// Write ratings to `path`, replacing whatever was there.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut file = try!(File::create(path).map_err(Error::Io));
+        for line in self.format().iter() {
+            try!(file.write_line(line.as_slice()).map_err(Error::Io));
+        }
+        Ok(())
+    }
+
+    fn parse<'a, I>(lines: I) -> Result<Ratings, Error> where I: Iterator<Item=&'a str> {
+        let mut ratings = Ratings::new();
+        for (i, line) in lines.enumerate() {
+            let line = line.trim_right_matches('\n');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 3 {
+                return Err(Error::Malformed(i + 1));
+            }
+            let rating: Option<f64> = str::FromStr::from_str(fields[1]);
+            let games: Option<u32> = str::FromStr::from_str(fields[2]);
+            match (rating, games) {
+                (Some(rating), Some(games)) => {
+                    ratings._players.insert(
+                        fields[0].to_string(), Rating { rating: rating, games: games });
+                },
+                _ => return Err(Error::Malformed(i + 1)),
+            }
+        }
+        Ok(ratings)
+    }
+
+    fn format(&self) -> Vec<String> {
+        self._players
+            .iter()
+            .map(|(name, rating)| format!("{}\t{}\t{}", name, rating.rating, rating.games))
+            .collect()
+    }
+}
+
+
+/// The score that a player rated `a` is expected to get against a player
+/// rated `b`, where a win is 1 and a loss is 0.
+fn expected_score(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10.0f64.powf((b - a) / 400.0))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::{Ratings, Rating, INITIAL_RATING, expected_score};
+
+    fn results(scores: &[(&str, u32)]) -> Vec<(String, u32)> {
+        scores.iter().map(|&(name, score)| (name.to_string(), score)).collect()
+    }
+
+    #[test]
+    fn test_unknown_player_has_initial_rating() {
+        let ratings = Ratings::new();
+        assert_eq!(Rating { rating: INITIAL_RATING, games: 0 }, ratings.get("alice"));
+    }
+
+    #[test]
+    fn test_expected_score_even() {
+        assert_eq!(0.5, expected_score(1500.0, 1500.0));
+    }
+
+    #[test]
+    fn test_winner_goes_up_loser_goes_down() {
+        let ratings = Ratings::new().record(results(&[("alice", 4), ("bob", 2)]).as_slice());
+        assert!(ratings.get("alice").rating > INITIAL_RATING);
+        assert!(ratings.get("bob").rating < INITIAL_RATING);
+        assert_eq!(1, ratings.get("alice").games);
+        assert_eq!(1, ratings.get("bob").games);
+    }
+
+    #[test]
+    fn test_shared_wins_draw() {
+        let ratings = Ratings::new().record(results(&[("alice", 4), ("bob", 4)]).as_slice());
+        assert_eq!(INITIAL_RATING, ratings.get("alice").rating);
+        assert_eq!(INITIAL_RATING, ratings.get("bob").rating);
+    }
+
+    #[test]
+    fn test_multiplayer_ratings_conserved() {
+        let ratings = Ratings::new().record(
+            results(&[("alice", 4), ("bob", 2), ("carol", 2), ("dave", 0)]).as_slice());
+        let total = ratings.leaderboard().iter().fold(0.0, |acc, &(_, r)| acc + r.rating);
+        assert!((total - 4.0 * INITIAL_RATING).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_leaderboard_sorted() {
+        let ratings = Ratings::new().record(
+            results(&[("alice", 1), ("bob", 4), ("carol", 2)]).as_slice());
+        let names: Vec<String> = ratings.leaderboard().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(vec!["bob".to_string(), "carol".to_string(), "alice".to_string()], names);
+    }
+
+    #[test]
+    fn test_format_parse_round_trip() {
+        let ratings = Ratings::new().record(results(&[("alice", 4), ("bob", 3)]).as_slice());
+        let lines = ratings.format();
+        let parsed = Ratings::parse(lines.iter().map(|x| x.as_slice())).unwrap();
+        assert_eq!(ratings, parsed);
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        let lines = ["alice\t1500\t1", "bob\tlots\t2"];
+        match Ratings::parse(lines.iter().map(|x| *x)) {
+            Err(super::Error::Malformed(2)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
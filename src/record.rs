@@ -0,0 +1,526 @@
+/// Written records of whole games, for archiving, sharing and replaying.
+///
+/// A record is plain text, a little like PGN for chess:
+///
+/// ```text
+/// [Player "alice"]
+/// [Player "bob"]
+/// [Variant "quick"]
+/// [Seed "42"]
+/// [Date "2015-01-20"]
+///
+/// Round 1
+/// Deck: K S Pr W S C G S M S K Pn W S C Pr
+/// P1: Soldier -> P2 guess Priestess; P2 out
+///
+/// Round 2
+/// ...
+/// ```
+///
+/// Headers come first, one per line. There's a `Player` header for each
+/// player, in order of play, and a `Variant`. `Seed` and `Date` are
+/// optional. Values are quoted, with `\` before any `"` or `\` in them.
+///
+/// Each round starts with its number, then the deck in the order cards come
+/// off it: the first is burned, the next is dealt to each player in order of
+/// play, and the rest are drawn. Cards are written as short codes. Then comes
+/// every turn, with what happened, in the notation from `notation`.
+///
+/// A record has everything needed to play the game again, turn by turn. A
+/// `Replay` steps backwards and forwards through it.
+
+use std::io::{BufferedReader, File, IoError};
+use std::str;
+
+use action::Play;
+use deck::{Card, Deck};
+use game;
+use game::{Game, Variant};
+use notation;
+use player_id::PlayerId;
+use round::{Round, TurnOutcome};
+
+
+#[derive(Show, PartialEq, Eq)]
+pub enum Error {
+    Io(IoError),
+    /// A line, counting from 1, that doesn't make sense, and why.
+    BadLine(usize, String),
+    /// Love Letter can't be played by this many players.
+    WrongNumberOfPlayers(usize),
+    /// A round, counting from 1, that wasn't dealt from a proper deck.
+    BadDeck(usize),
+    /// A turn that can't have gone the way it's recorded, as a round and a
+    /// turn, both counting from 1. If a round stops too soon, it's the turn
+    /// after the last one.
+    BadTurn(usize, usize),
+}
+
+
+/// How one round was dealt and played.
+#[derive(Show, PartialEq, Eq, Clone)]
+pub struct RoundRecord {
+    /// The deck, in the order cards came off it.
+    pub deck: Vec<Card>,
+    /// Every turn, in order.
+    pub turns: Vec<TurnOutcome>,
+}
+
+
+impl RoundRecord {
+    /// A round about to be dealt from `deck`, with no turns played yet.
+    pub fn new(deck: &Deck) -> RoundRecord {
+        // Cards are dealt from the end of a `Deck`.
+        let mut cards: Vec<Card> = deck.as_slice().iter().map(|&c| c).collect();
+        cards.reverse();
+        RoundRecord { deck: cards, turns: vec![] }
+    }
+
+    /// The deck this round was dealt from, or `None` if it's not a proper
+    /// Love Letter deck.
+    pub fn make_deck(&self) -> Option<Deck> {
+        let mut cards = self.deck.clone();
+        cards.reverse();
+        Deck::from_slice(cards.as_slice()).ok()
+    }
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+pub struct GameRecord {
+    /// Everyone's name, in order of play.
+    pub names: Vec<String>,
+    pub variant: Variant,
+    /// The seed the decks were shuffled with, if there was one.
+    pub seed: Option<u32>,
+    /// When the game was played, written however the recorder liked.
+    pub date: Option<String>,
+    pub rounds: Vec<RoundRecord>,
+}
+
+
+impl GameRecord {
+    /// A record of everything in `game`'s history.
+    pub fn from_game(game: &Game, seed: Option<u32>, date: Option<String>) -> GameRecord {
+        GameRecord {
+            names: game.roster().names(),
+            variant: game.variant(),
+            seed: seed,
+            date: date,
+            rounds: game.history().iter().map(|r| r.clone()).collect(),
+        }
+    }
+
+    /// A new game for the players in this record, with no rounds played.
+    pub fn new_game(&self) -> Result<Game, Error> {
+        game::new_game_with_names(self.names.as_slice(), self.variant)
+            .ok_or(Error::WrongNumberOfPlayers(self.names.len()))
+    }
+
+    /// The players, in order of play. These are what the turns refer to.
+    pub fn players(&self) -> Result<Vec<PlayerId>, Error> {
+        self.new_game().map(|game| game.players())
+    }
+
+    /// The `i`th round, counting from 0, as dealt and then after each turn.
+    pub fn round_states(&self, i: usize) -> Result<Vec<Round>, Error> {
+        let players = try!(self.players());
+        let record = &self.rounds[i];
+        let deck = try!(record.make_deck().ok_or(Error::BadDeck(i + 1)));
+        let mut states = vec![Round::from_deck(players.as_slice(), deck)];
+        for (j, turn) in record.turns.iter().enumerate() {
+            let play = match *turn {
+                TurnOutcome::Played(_, card, play, _) => (card, play),
+                // Nobody gets asked what to play when they bust out, so it
+                // doesn't matter what we say.
+                TurnOutcome::BustedOut(_, card, _) => (card, Play::NoEffect),
+            };
+            let result = states[j].handle_turn(|_, _| play, |_, _| ());
+            match result {
+                Ok(Some((round, outcome))) => {
+                    if outcome != *turn {
+                        return Err(Error::BadTurn(i + 1, j + 1));
+                    }
+                    states.push(round);
+                },
+                _ => return Err(Error::BadTurn(i + 1, j + 1)),
+            }
+        }
+        let over = states[states.len() - 1].peek_turn().is_none();
+        if !over {
+            return Err(Error::BadTurn(i + 1, record.turns.len() + 1));
+        }
+        Ok(states)
+    }
+
+    /// Play the whole game again, checking every turn went the way the
+    /// record says it did. Returns the game as it was at the end.
+    pub fn replay(&self) -> Result<Game, Error> {
+        let mut game = try!(self.new_game());
+        for (i, record) in self.rounds.iter().enumerate() {
+            let states = try!(self.round_states(i));
+            let winners: Vec<PlayerId> = states[states.len() - 1].winners().iter().map(|&(p, _)| p).collect();
+            game = game.players_won(winners.as_slice()).record_round(record.clone());
+        }
+        Ok(game)
+    }
+
+    /// Load a record from `path`.
+    pub fn load(path: &Path) -> Result<GameRecord, Error> {
+        let file = try!(File::open(path).map_err(Error::Io));
+        let mut reader = BufferedReader::new(file);
+        let mut lines = vec![];
+        for line in reader.lines() {
+            lines.push(try!(line.map_err(Error::Io)));
+        }
+        GameRecord::parse(lines.iter().map(|x| x.as_slice()))
+    }
+
+    /// Write this record to `path`, replacing whatever was there.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut file = try!(File::create(path).map_err(Error::Io));
+        for line in self.format().iter() {
+            try!(file.write_line(line.as_slice()).map_err(Error::Io));
+        }
+        Ok(())
+    }
+
+    /// Read a record, one line at a time.
+    pub fn parse<'a, I>(lines: I) -> Result<GameRecord, Error> where I: Iterator<Item=&'a str> {
+        let mut names = vec![];
+        let mut variant = None;
+        let mut seed = None;
+        let mut date = None;
+        let mut players = None;
+        let mut rounds: Vec<RoundRecord> = vec![];
+        for (i, line) in lines.enumerate() {
+            let line = line.trim();
+            let bad = |&: why: &str| Error::BadLine(i + 1, why.to_string());
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("[") {
+                if rounds.len() > 0 {
+                    return Err(bad("Headers must come before the first round"));
+                }
+                let (tag, value) = try!(parse_header(line).ok_or(bad("Not a header")));
+                match tag {
+                    "Player" => names.push(value),
+                    "Variant" => variant = Some(try!(str::FromStr::from_str(value.as_slice()).ok_or(bad("Unknown variant")))),
+                    "Seed" => seed = Some(try!(str::FromStr::from_str(value.as_slice()).ok_or(bad("Seed must be a number")))),
+                    "Date" => date = Some(value),
+                    _ => return Err(bad("Unknown header")),
+                }
+                continue;
+            }
+            if line.starts_with("Round ") {
+                let number: Option<usize> = str::FromStr::from_str(line.slice_from("Round ".len()));
+                if number != Some(rounds.len() + 1) {
+                    return Err(bad("Rounds must be numbered in order, from 1"));
+                }
+                rounds.push(RoundRecord { deck: vec![], turns: vec![] });
+                continue;
+            }
+            let current = match rounds.last_mut() {
+                Some(round) => round,
+                None => return Err(bad("Turns must be in a round")),
+            };
+            if line.starts_with("Deck:") {
+                if current.deck.len() > 0 || current.turns.len() > 0 {
+                    return Err(bad("Deck must come once, at the start of a round"));
+                }
+                for code in line.slice_from("Deck:".len()).words() {
+                    current.deck.push(try!(notation::parse_card(code).map_err(|e| bad(format!("{:?}", e).as_slice()))));
+                }
+                continue;
+            }
+            // We only know who the players are once all the headers are in.
+            if players.is_none() {
+                let record = GameRecord {
+                    names: names.clone(), variant: Variant::Quick, seed: None, date: None, rounds: vec![] };
+                players = Some(try!(record.players()));
+            }
+            let players = players.as_ref().unwrap();
+            let turn = try!(notation::parse_outcome(players.as_slice(), line)
+                            .map_err(|e| bad(format!("{:?}", e).as_slice())));
+            current.turns.push(turn);
+        }
+        let variant = try!(variant.ok_or(Error::BadLine(1, "No variant".to_string())));
+        Ok(GameRecord { names: names, variant: variant, seed: seed, date: date, rounds: rounds })
+    }
+
+    /// Write this record out, one line at a time.
+    pub fn format(&self) -> Vec<String> {
+        let mut lines = vec![];
+        for name in self.names.iter() {
+            lines.push(format_header("Player", name.as_slice()));
+        }
+        lines.push(format_header("Variant", format!("{}", self.variant).as_slice()));
+        match self.seed {
+            Some(seed) => lines.push(format_header("Seed", seed.to_string().as_slice())),
+            None => (),
+        }
+        match self.date {
+            Some(ref date) => lines.push(format_header("Date", date.as_slice())),
+            None => (),
+        }
+        let players = self.players().ok().expect("Record has the wrong number of players");
+        for (i, round) in self.rounds.iter().enumerate() {
+            lines.push("".to_string());
+            lines.push(format!("Round {}", i + 1));
+            let codes: Vec<&str> = round.deck.iter().map(|&c| notation::card_code(c)).collect();
+            lines.push(format!("Deck: {}", codes.connect(" ")));
+            for turn in round.turns.iter() {
+                lines.push(notation::format_outcome(players.as_slice(), turn));
+            }
+        }
+        lines
+    }
+}
+
+
+/// A place in a recorded game, which can be moved a turn at a time.
+pub struct Replay {
+    /// Each round, as dealt and then after each turn.
+    _rounds: Vec<Vec<Round>>,
+    _turns: Vec<Vec<TurnOutcome>>,
+    _round: usize,
+    _turn: usize,
+}
+
+
+impl Replay {
+    /// Start at the beginning of `record`, with the first round dealt.
+    /// Returns an error if the record couldn't have been played.
+    pub fn new(record: &GameRecord) -> Result<Replay, Error> {
+        let mut rounds = vec![];
+        for i in range(0, record.rounds.len()) {
+            rounds.push(try!(record.round_states(i)));
+        }
+        Ok(Replay {
+            _rounds: rounds,
+            _turns: record.rounds.iter().map(|r| r.turns.clone()).collect(),
+            _round: 0,
+            _turn: 0,
+        })
+    }
+
+    pub fn num_rounds(&self) -> usize {
+        self._rounds.len()
+    }
+
+    /// The round we're in, counting from 0.
+    pub fn round_number(&self) -> usize {
+        self._round
+    }
+
+    /// How many turns have been played this round.
+    pub fn turn_number(&self) -> usize {
+        self._turn
+    }
+
+    /// How many turns there are in this round.
+    pub fn num_turns(&self) -> usize {
+        self._turns.get(self._round).map(|t| t.len()).unwrap_or(0)
+    }
+
+    /// The round as it is now, or `None` if no rounds were recorded.
+    pub fn round(&self) -> Option<&Round> {
+        self._rounds.get(self._round).map(|states| &states[self._turn])
+    }
+
+    /// The turn that got us here, or `None` at the start of a round.
+    pub fn last_turn(&self) -> Option<&TurnOutcome> {
+        if self._turn == 0 {
+            None
+        } else {
+            Some(&self._turns[self._round][self._turn - 1])
+        }
+    }
+
+    /// Play the next turn, or deal the next round if this one's over.
+    /// Returns `false` if the game's already over.
+    pub fn forward(&mut self) -> bool {
+        if self._turn < self.num_turns() {
+            self._turn += 1;
+        } else if self._round + 1 < self.num_rounds() {
+            self._round += 1;
+            self._turn = 0;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Take back the last turn, or go back to the end of the last round if
+    /// this one's only just been dealt. Returns `false` if we're already at
+    /// the start.
+    pub fn back(&mut self) -> bool {
+        if self._turn > 0 {
+            self._turn -= 1;
+        } else if self._round > 0 {
+            self._round -= 1;
+            self._turn = self.num_turns();
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+
+fn format_header(tag: &str, value: &str) -> String {
+    let escaped = value.replace("\\", "\\\\").replace("\"", "\\\"");
+    format!("[{} \"{}\"]", tag, escaped)
+}
+
+
+/// Split a header into its tag and value, or `None` if it's not a header.
+fn parse_header(line: &str) -> Option<(&str, String)> {
+    if !line.starts_with("[") || !line.ends_with("]") {
+        return None;
+    }
+    let inside = line.slice(1, line.len() - 1);
+    let space = match inside.find(' ') {
+        Some(i) => i,
+        None => return None,
+    };
+    let (tag, quoted) = (inside.slice_to(space), inside.slice_from(space + 1).trim());
+    if quoted.len() < 2 || !quoted.starts_with("\"") || !quoted.ends_with("\"") {
+        return None;
+    }
+    let mut value = String::new();
+    let mut escaped = false;
+    for c in quoted.slice(1, quoted.len() - 1).chars() {
+        match (escaped, c) {
+            (false, '\\') => escaped = true,
+            (false, '"') => return None,
+            _ => {
+                value.push(c);
+                escaped = false;
+            },
+        }
+    }
+    if escaped { None } else { Some((tag, value)) }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::rand::{SeedableRng, XorShiftRng};
+
+    use agent::{Agent, RandomAgent};
+    use deck::Deck;
+    use game;
+    use game::{Game, Variant};
+    use player_id::PlayerId;
+    use super::{Error, GameRecord, Replay, RoundRecord};
+
+    /// Play a whole game between random agents, keeping a record.
+    fn play_game(names: &[String]) -> Game {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([1, 2, 3, 4]);
+        let agent = RandomAgent::from_seed([5, 6, 7, 8]);
+        let mut game = game::new_game_with_names(names, Variant::Quick).unwrap();
+        loop {
+            let deck = Deck::with_rng(&mut rng);
+            let mut record = RoundRecord::new(&deck);
+            let mut round = match game.next_round_from_deck(deck) {
+                Some(round) => round,
+                None => break,
+            };
+            loop {
+                let result = round.handle_turn(
+                    |round, turn| agent.choose(&round.view(turn.player).ok().unwrap()),
+                    |_, _| ());
+                match result.unwrap() {
+                    Some((new_round, outcome)) => {
+                        record.turns.push(outcome);
+                        round = new_round;
+                    },
+                    None => break,
+                }
+            }
+            let winners: Vec<PlayerId> = round.winners().iter().map(|&(p, _)| p).collect();
+            game = game.players_won(winners.as_slice()).record_round(record);
+        }
+        game
+    }
+
+    fn names() -> Vec<String> {
+        vec!["alice".to_string(), "bob \"the knight\"".to_string(), "carol".to_string()]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let game = play_game(names().as_slice());
+        assert!(game.history().len() >= 4);
+        let record = GameRecord::from_game(&game, Some(42), Some("2015-01-20".to_string()));
+        let lines = record.format();
+        assert_eq!("[Player \"bob \\\"the knight\\\"\"]", lines[1].as_slice());
+        let parsed = GameRecord::parse(lines.iter().map(|x| x.as_slice()));
+        assert_eq!(Ok(record.clone()), parsed);
+        let replayed = record.replay().unwrap();
+        assert_eq!(game.scores(), replayed.scores());
+        assert_eq!(game.roster().names(), replayed.roster().names());
+        assert_eq!(game.history(), replayed.history());
+    }
+
+    #[test]
+    fn test_round_states() {
+        let game = play_game(names().as_slice());
+        let record = GameRecord::from_game(&game, None, None);
+        let states = record.round_states(0).unwrap();
+        assert_eq!(record.rounds[0].turns.len() + 1, states.len());
+        // One card is burned, and one dealt to each player.
+        assert_eq!(12, states[0].num_cards_remaining());
+    }
+
+    #[test]
+    fn test_replay_steps() {
+        let game = play_game(names().as_slice());
+        let record = GameRecord::from_game(&game, None, None);
+        let mut replay = Replay::new(&record).unwrap();
+        assert!(!replay.back());
+        assert_eq!(None, replay.last_turn());
+        let first_turns = record.rounds[0].turns.len();
+        for _ in range(0, first_turns) {
+            assert!(replay.forward());
+        }
+        assert_eq!(record.rounds[0].turns.last(), replay.last_turn());
+        // Past the end of the round is the start of the next one.
+        assert!(replay.forward());
+        assert_eq!((1, 0), (replay.round_number(), replay.turn_number()));
+        assert_eq!(12, replay.round().unwrap().num_cards_remaining());
+        assert!(replay.back());
+        assert_eq!((0, first_turns), (replay.round_number(), replay.turn_number()));
+        let mut steps = 0;
+        while replay.forward() {
+            steps += 1;
+        }
+        let total: usize = record.rounds.iter().fold(0, |n, r| n + r.turns.len() + 1);
+        assert_eq!(total - 1 - first_turns, steps);
+    }
+
+    #[test]
+    fn test_tampered_record() {
+        let game = play_game(names().as_slice());
+        let mut record = GameRecord::from_game(&game, None, None);
+        record.rounds[1].turns.pop();
+        let turns = record.rounds[1].turns.len();
+        assert_eq!(Err(Error::BadTurn(2, turns + 1)), record.replay());
+        record.rounds[1].deck.pop();
+        assert_eq!(Err(Error::BadDeck(2)), record.replay());
+    }
+
+    #[test]
+    fn test_bad_lines() {
+        let parse = |&: text: &str| GameRecord::parse(text.lines());
+        assert_eq!(Err(Error::BadLine(3, "Turns must be in a round".to_string())),
+                   parse("[Player \"a\"]\n[Player \"b\"]\nP1: Priestess\n"));
+        assert_eq!(Err(Error::BadLine(1, "Unknown header".to_string())), parse("[Winner \"a\"]"));
+        assert_eq!(Err(Error::BadLine(2, "Rounds must be numbered in order, from 1".to_string())),
+                   parse("[Variant \"quick\"]\nRound 2"));
+        assert_eq!(Err(Error::WrongNumberOfPlayers(1)),
+                   parse("[Player \"a\"]\n[Variant \"quick\"]\nRound 1\nP1: Priestess"));
+    }
+}
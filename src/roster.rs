@@ -0,0 +1,193 @@
+/// What to call the players in a game.
+///
+/// `PlayerId`s are opaque, and can only show themselves as "Player #1",
+/// "Player #2" and so on. A `Roster` gives each player a name to be shown
+/// instead, and, optionally, a colour to show it in. Players without a
+/// name are shown as their `PlayerId`.
+
+use std::fmt;
+use std::str;
+
+use player_id::PlayerId;
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+pub enum Colour {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+
+/// Every colour, in the order they're given out.
+pub const COLOURS: [Colour; 6] = [
+    Colour::Red, Colour::Green, Colour::Yellow, Colour::Blue, Colour::Magenta, Colour::Cyan];
+
+
+impl Colour {
+    /// The ANSI escape sequence that switches a terminal to this colour.
+    pub fn escape(&self) -> &'static str {
+        match *self {
+            Colour::Red => "\x1b[31m",
+            Colour::Green => "\x1b[32m",
+            Colour::Yellow => "\x1b[33m",
+            Colour::Blue => "\x1b[34m",
+            Colour::Magenta => "\x1b[35m",
+            Colour::Cyan => "\x1b[36m",
+        }
+    }
+}
+
+
+impl fmt::String for Colour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Colour::Red => "red",
+            Colour::Green => "green",
+            Colour::Yellow => "yellow",
+            Colour::Blue => "blue",
+            Colour::Magenta => "magenta",
+            Colour::Cyan => "cyan",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+
+impl str::FromStr for Colour {
+    fn from_str(name: &str) -> Option<Colour> {
+        match name {
+            "red" => Some(Colour::Red),
+            "green" => Some(Colour::Green),
+            "yellow" => Some(Colour::Yellow),
+            "blue" => Some(Colour::Blue),
+            "magenta" => Some(Colour::Magenta),
+            "cyan" => Some(Colour::Cyan),
+            _ => None,
+        }
+    }
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+struct Entry {
+    id: PlayerId,
+    name: Option<String>,
+    colour: Option<Colour>,
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+pub struct Roster {
+    _entries: Vec<Entry>,
+}
+
+
+impl Roster {
+    /// A roster for `players`, none of whom have names yet.
+    pub fn new(players: &[PlayerId]) -> Roster {
+        Roster { _entries: players.iter().map(|&id| Entry { id: id, name: None, colour: None }).collect() }
+    }
+
+    /// A roster giving each of `players` the name at the same position in
+    /// `names`. Returns `None` unless there's one name per player.
+    pub fn with_names(players: &[PlayerId], names: &[String]) -> Option<Roster> {
+        if players.len() != names.len() {
+            return None;
+        }
+        let mut roster = Roster::new(players);
+        for (entry, name) in roster._entries.iter_mut().zip(names.iter()) {
+            entry.name = Some(name.clone());
+        }
+        Some(roster)
+    }
+
+    /// Everyone on the roster, in order.
+    pub fn players(&self) -> Vec<PlayerId> {
+        self._entries.iter().map(|e| e.id).collect()
+    }
+
+    /// Call `player` `name`, or go back to their `PlayerId` if `None`.
+    pub fn set_name(&mut self, player: PlayerId, name: Option<String>) {
+        match self._entries.iter_mut().find(|e| e.id == player) {
+            Some(entry) => entry.name = name,
+            None => (),
+        }
+    }
+
+    /// Show `player`'s name in `colour`, or in the terminal's usual colour
+    /// if `None`.
+    pub fn set_colour(&mut self, player: PlayerId, colour: Option<Colour>) {
+        match self._entries.iter_mut().find(|e| e.id == player) {
+            Some(entry) => entry.colour = colour,
+            None => (),
+        }
+    }
+
+    /// What to call `player`.
+    pub fn name(&self, player: PlayerId) -> String {
+        match self._entries.iter().find(|e| e.id == player).and_then(|e| e.name.clone()) {
+            Some(name) => name,
+            None => format!("{:?}", player),
+        }
+    }
+
+    /// What to call everyone, in order.
+    pub fn names(&self) -> Vec<String> {
+        self._entries.iter().map(|e| self.name(e.id)).collect()
+    }
+
+    pub fn colour(&self, player: PlayerId) -> Option<Colour> {
+        self._entries.iter().find(|e| e.id == player).and_then(|e| e.colour)
+    }
+
+    /// `player`'s name, in their colour if they have one, ready to print to
+    /// a terminal.
+    pub fn display(&self, player: PlayerId) -> String {
+        match self.colour(player) {
+            Some(colour) => format!("{}{}\x1b[0m", colour.escape(), self.name(player)),
+            None => self.name(player),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::str;
+
+    use player_id::player_id_generator;
+    use super::{Colour, COLOURS, Roster};
+
+    #[test]
+    fn test_unnamed_players() {
+        let players: Vec<_> = player_id_generator().take(2).collect();
+        let roster = Roster::new(players.as_slice());
+        assert_eq!(vec!["Player #1".to_string(), "Player #2".to_string()], roster.names());
+        assert_eq!("Player #2", roster.display(players[1]).as_slice());
+    }
+
+    #[test]
+    fn test_names_and_colours() {
+        let players: Vec<_> = player_id_generator().take(2).collect();
+        assert!(Roster::with_names(players.as_slice(), &["alice".to_string()]).is_none());
+        let mut roster = Roster::with_names(
+            players.as_slice(), &["alice".to_string(), "bob".to_string()]).unwrap();
+        assert_eq!("bob", roster.name(players[1]).as_slice());
+        roster.set_colour(players[0], Some(Colour::Red));
+        assert_eq!("\x1b[31malice\x1b[0m", roster.display(players[0]).as_slice());
+        roster.set_name(players[1], None);
+        assert_eq!("Player #2", roster.name(players[1]).as_slice());
+    }
+
+    #[test]
+    fn test_colour_names() {
+        for &colour in COLOURS.iter() {
+            assert_eq!(Some(colour), str::FromStr::from_str(format!("{}", colour).as_slice()));
+        }
+        assert_eq!(None, str::FromStr::from_str("mauve").map(|c: Colour| c));
+    }
+}
@@ -15,6 +15,7 @@ use deck::Card;
 use player;
 use player_id;
 use util;
+use view;
 
 
 #[derive(Show, PartialEq, Eq, Copy)]
@@ -28,6 +29,12 @@ impl Turn {
     fn new(player: player_id::PlayerId, hand: Card, draw: Card) -> Turn {
         Turn { player: player, hand: hand, draw: draw }
     }
+
+    /// Does the player lose straight away, because they're holding the
+    /// Minister with a high card?
+    pub fn busts(&self) -> bool {
+        minister_bust(self.draw, self.hand)
+    }
 }
 
 
@@ -51,6 +58,8 @@ impl Turn {
 enum State {
     NotStarted,
     PlayerReady(player_id::PlayerId, Card),
+    /// The given player has finished their turn.
+    TurnOver(player_id::PlayerId),
     RoundOver(RoundResult),
 }
 
@@ -65,10 +74,16 @@ pub enum Error {
     InvalidPlayers(usize),
     /// The given cards do not form a valid deck.
     BadDeck,
+    /// Tried to reconstruct a round from the view of a player whose turn it
+    /// isn't.
+    NotPlayersTurn(player_id::PlayerId),
+    /// Tried to reconstruct a round without saying what an active player is
+    /// holding.
+    MissingHand(player_id::PlayerId),
 }
 
 
-#[derive(Show)]
+#[derive(Show, PartialEq, Eq, Clone)]
 pub enum TurnOutcome {
     // XXX: Not sure we should include originating player id in this
     // structure, but Round currently doesn't expose whose turn that just was.
@@ -81,6 +96,21 @@ pub enum TurnOutcome {
 }
 
 
+#[derive(Show, PartialEq, Eq, Clone)]
+/// Everything there is to know about a round between turns, laid out so it
+/// can be stored somewhere and turned back into a `Round` later.
+pub struct RoundSnapshot {
+    /// The cards left to draw, drawn from the end.
+    pub deck: Vec<Card>,
+    /// Each player, in order of play, with their hand (`None` if they're
+    /// out), whether they're protected, and their discards, oldest first.
+    pub players: Vec<(player_id::PlayerId, Option<Card>, bool, Vec<Card>)>,
+    /// The player who has just finished their turn, or `None` if nobody has
+    /// played yet.
+    pub last_player: Option<player_id::PlayerId>,
+}
+
+
 #[derive(Show, PartialEq, Eq, Clone)]
 /// Represents a single round of Love Letter.
 pub struct Round {
@@ -171,13 +201,119 @@ impl Round {
         })
     }
 
+    /// Reconstruct a round from what a player can see, plus a guess at what
+    /// they can't.
+    ///
+    /// `view` must be the view of the player whose turn it is. `hands` gives
+    /// the hands of every other player still in the round, and `deck` is the
+    /// stack of cards left to draw, drawn from the end. The new round is
+    /// positioned just before the viewer's turn, so the next call to
+    /// `handle_turn` will deal them `view.draw`.
+    pub fn from_view(view: &view::PlayerView, hands: &[(player_id::PlayerId, Card)],
+                     deck: &[Card]) -> Result<Round, Error> {
+        let (own_hand, draw) = match (view.hand, view.draw) {
+            (Some(hand), Some(draw)) => (hand, draw),
+            _ => return Err(Error::NotPlayersTurn(view.player)),
+        };
+        let mut all_cards: Vec<Card> = deck.iter().map(|&x| x).collect();
+        all_cards.push(draw);
+        let mut players = vec![];
+        for public in view.players.iter() {
+            let hand = if public.id == view.player {
+                Some(own_hand)
+            } else if public.active {
+                match hands.iter().find(|&&(id, _)| id == public.id) {
+                    Some(&(_, card)) => Some(card),
+                    None => return Err(Error::MissingHand(public.id)),
+                }
+            } else {
+                None
+            };
+            for &card in hand.iter().chain(public.discards.iter()) {
+                all_cards.push(card);
+            }
+            players.push(
+                (public.id, player::Player::from_parts(hand, public.protected, public.discards.as_slice())));
+        }
+        if !valid_player_count(players.len()) {
+            return Err(Error::InvalidPlayers(players.len()));
+        }
+        if !deck::is_valid_subdeck(all_cards.as_slice()) {
+            return Err(Error::BadDeck);
+        }
+
+        // Rewind to the end of the previous active player's turn, and put
+        // the viewer's draw back on the stack.
+        let seat = view.seat(view.player).expect("Viewer not in view");
+        let num_players = players.len();
+        let previous = range(1, num_players)
+            .map(|i| &players[(seat + num_players - i) % num_players])
+            .find(|&&(_, ref p)| p.active())
+            .map(|&(id, _)| id)
+            .unwrap_or(view.player);
+        let mut stack: Vec<Card> = deck.iter().map(|&x| x).collect();
+        stack.push(draw);
+        Ok(Round {
+            _stack: stack,
+            _current: State::TurnOver(previous),
+            _players: players,
+        })
+    }
+
+    /// Recreate a round from a snapshot taken with `snapshot`.
+    pub fn from_snapshot(snapshot: &RoundSnapshot) -> Result<Round, Error> {
+        if !valid_player_count(snapshot.players.len()) {
+            return Err(Error::InvalidPlayers(snapshot.players.len()));
+        }
+        let mut all_cards = snapshot.deck.clone();
+        for &(_, hand, _, ref discards) in snapshot.players.iter() {
+            all_cards.extend(hand.into_iter());
+            all_cards.push_all(discards.as_slice());
+        }
+        if !deck::is_valid_subdeck(all_cards.as_slice()) {
+            return Err(Error::BadDeck);
+        }
+        let current = match snapshot.last_player {
+            None => State::NotStarted,
+            Some(player) => State::TurnOver(player),
+        };
+        Ok(Round {
+            _stack: snapshot.deck.clone(),
+            _current: current,
+            _players: snapshot.players
+                .iter()
+                .map(|&(id, hand, protected, ref discards)|
+                     (id, player::Player::from_parts(hand, protected, discards.as_slice())))
+                .collect(),
+        })
+    }
+
+    /// Everything about this round, so it can be stored and recreated with
+    /// `from_snapshot`. Returns `None` in the middle of a turn, or once the
+    /// round is over.
+    pub fn snapshot(&self) -> Option<RoundSnapshot> {
+        let last_player = match self._current {
+            State::NotStarted => None,
+            State::TurnOver(player) => Some(player),
+            State::PlayerReady(..) | State::RoundOver(..) => return None,
+        };
+        Some(RoundSnapshot {
+            deck: self._stack.clone(),
+            players: self._players
+                .iter()
+                .map(|&(id, ref p)| (id, p.get_hand(), p.protected(), p.discards().to_vec()))
+                .collect(),
+            last_player: last_player,
+        })
+    }
+
     /// Number of players in this game.
     pub fn num_players(&self) -> usize {
         self._players.len()
     }
 
     /// Return the player IDs in the order of play.
-    fn player_ids(&self) -> Vec<player_id::PlayerId> {
+    pub fn player_ids(&self) -> Vec<player_id::PlayerId> {
         let mut ids = vec![];
         for &(id, _) in self._players.iter() {
             ids.push(id)
@@ -194,8 +330,8 @@ impl Round {
         discards
     }
 
-    #[cfg(test)]
-    fn num_cards_remaining(&self) -> usize {
+    /// Number of cards left to draw.
+    pub fn num_cards_remaining(&self) -> usize {
         self._stack.len()
     }
 
@@ -208,6 +344,7 @@ impl Round {
         match self._current {
             State::NotStarted => None,
             State::PlayerReady(i, _) => Some(i),
+            State::TurnOver(i) => Some(i),
             State::RoundOver(..) => None,
         }
     }
@@ -222,6 +359,61 @@ impl Round {
         }
     }
 
+    /// What `viewer` can see of this round.
+    ///
+    /// If it's `viewer`'s turn, this includes the card they just drew.
+    pub fn view(&self, viewer: player_id::PlayerId) -> Result<view::PlayerView, action::PlayError> {
+        let hand = match self._players.iter().find(|&&(id, _)| id == viewer) {
+            None => return Err(action::PlayError::InvalidPlayer(viewer)),
+            Some(&(_, ref player)) => player.get_hand(),
+        };
+        let draw = match self._current {
+            State::PlayerReady(i, card) if i == viewer => Some(card),
+            _ => None,
+        };
+        Ok(view::PlayerView {
+            player: viewer,
+            hand: hand,
+            draw: draw,
+            players: self.public_players(),
+            deck_size: self._stack.len(),
+        })
+    }
+
+    /// What someone watching this round can see. If `omniscient`, this
+    /// includes everyone's hands, and the card the current player just drew.
+    pub fn spectator_view(&self, omniscient: bool) -> view::SpectatorView {
+        let (hands, draw) = if omniscient {
+            let hands = self._players.iter().map(|&(_, ref p)| p.get_hand()).collect();
+            let draw = match self._current {
+                State::PlayerReady(_, card) => Some(card),
+                _ => None,
+            };
+            (Some(hands), draw)
+        } else {
+            (None, None)
+        };
+        view::SpectatorView {
+            players: self.public_players(),
+            deck_size: self._stack.len(),
+            hands: hands,
+            draw: draw,
+        }
+    }
+
+    /// What everyone can see of each player.
+    fn public_players(&self) -> Vec<view::PublicPlayer> {
+        self._players
+            .iter()
+            .map(|&(id, ref p)| view::PublicPlayer {
+                id: id,
+                discards: p.discards().to_vec(),
+                protected: p.protected(),
+                active: p.active(),
+            })
+            .collect()
+    }
+
     fn _game_result(&self) -> RoundResult {
         // XXX: probably doesn't need to be a clone
         RoundResult::new(self._players.clone())
@@ -328,6 +520,17 @@ impl Round {
         }
     }
 
+    /// Look at the next turn without playing it.
+    ///
+    /// Returns the round as the next player will see it, having drawn their
+    /// card, along with their turn. Returns `None` if the round is over.
+    pub fn peek_turn(&self) -> Option<(Round, Turn)> {
+        match self.next_player() {
+            (round, Some(turn)) => Some((round, turn)),
+            (_, None) => None,
+        }
+    }
+
     fn next_player(&self) -> (Round, Option<Turn>) {
         match (self._next_player(), self.draw()) {
             (Some(new_player_id), (game, Some(c))) => {
@@ -471,8 +674,9 @@ impl Round {
         if minister_bust(turn.draw, turn.hand) {
             // XXX: Add tests to verify that the discard pile includes both
             // picked up card & held card.
-            let new_game = try!(new_game.update_player_by(
+            let mut new_game = try!(new_game.update_player_by(
                 turn.player, |p| p.play_card(turn.draw, turn.draw).and_then(|p| p.eliminate())));
+            new_game._current = State::TurnOver(turn.player);
             Ok(Some((new_game, TurnOutcome::BustedOut(turn.player, turn.draw, turn.hand))))
         } else {
             // Find out what they'd like to play.
@@ -490,18 +694,20 @@ impl Round {
                 _ => (),
             }
             let mut events = vec![event];
-            let (new_game, follow_up) = try!(new_game.apply_event(event));
+            let (mut new_game, follow_up) = try!(new_game.apply_event(event));
             match follow_up {
                 Some(event) => events.push(event),
                 None => (),
             };
+            new_game._current = State::TurnOver(turn.player);
             Ok(Some((new_game, TurnOutcome::Played(turn.player, card, play, events))))
         }
     }
 }
 
 
-fn minister_bust(a: Card, b: Card) -> bool {
+/// Does holding `a` and `b` together bust you out with the Minister?
+pub fn minister_bust(a: Card, b: Card) -> bool {
     match util::other((a, b), Card::Minister) {
         Some(Card::Wizard) | Some(Card::General) | Some(Card::Princess) => true,
         Some(Card::Minister) => panic!("Called with 2 ministers!"),
@@ -852,6 +1058,155 @@ mod test {
         assert_eq!(players[2], t.unwrap().player);
     }
 
+    #[test]
+    fn test_view_own_turn() {
+        let g = make_round(3);
+        let players = g.player_ids();
+        let (g, turn) = g.next_player();
+        let turn = turn.unwrap();
+        let view = g.view(players[0]).unwrap();
+        assert_eq!(Some(turn.hand), view.hand);
+        assert_eq!(Some(turn.draw), view.draw);
+        assert_eq!(g.num_cards_remaining(), view.deck_size);
+        assert_eq!(players, view.active_players());
+    }
+
+    #[test]
+    fn test_view_other_turn() {
+        let g = make_round(3);
+        let players = g.player_ids();
+        let (g, _) = g.next_player();
+        let view = g.view(players[1]).unwrap();
+        assert_eq!(Some(g.get_hand(players[1]).unwrap()), view.hand);
+        assert_eq!(None, view.draw);
+        assert_eq!(vec![], view.legal_plays());
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let players = make_player_ids(2);
+        let g = Round::from_manual(
+            &[(players[0], Some(Card::Priestess)), (players[1], Some(Card::Soldier))],
+            &[Card::Clown, Card::Wizard], None).unwrap();
+        let snapshot = g.snapshot().unwrap();
+        assert_eq!(None, snapshot.last_player);
+        assert_eq!(Ok(g.clone()), Round::from_snapshot(&snapshot));
+        let (g, _) = g.handle_turn(|_, _| (Card::Priestess, action::Play::NoEffect), |_, _| ())
+            .unwrap().unwrap();
+        let snapshot = g.snapshot().unwrap();
+        assert_eq!(Some(players[0]), snapshot.last_player);
+        assert_eq!((players[0], Some(Card::Wizard), true, vec![Card::Priestess]), snapshot.players[0]);
+        assert_eq!(Ok(g.clone()), Round::from_snapshot(&snapshot));
+        let (g, _) = g.next_player();
+        assert_eq!(None, g.snapshot());
+    }
+
+    #[test]
+    fn test_snapshot_rejects_extra_cards() {
+        let players = make_player_ids(2);
+        let snapshot = super::RoundSnapshot {
+            deck: vec![Card::Princess],
+            players: vec![(players[0], Some(Card::Princess), false, vec![]),
+                          (players[1], Some(Card::Soldier), false, vec![])],
+            last_player: None,
+        };
+        assert_eq!(Err(super::Error::BadDeck), Round::from_snapshot(&snapshot));
+    }
+
+    #[test]
+    fn test_spectator_view() {
+        let g = make_round(3);
+        let players = g.player_ids();
+        let (g, turn) = g.next_player();
+        let turn = turn.unwrap();
+        let public = g.spectator_view(false);
+        assert_eq!(g.view(players[0]).unwrap().players, public.players);
+        assert_eq!(g.num_cards_remaining(), public.deck_size);
+        assert_eq!(None, public.hands);
+        assert_eq!(None, public.draw);
+        let full = g.spectator_view(true);
+        let hands: Vec<Option<Card>> = players.iter().map(|&p| g.get_hand(p).ok()).collect();
+        assert_eq!(Some(hands), full.hands);
+        assert_eq!(Some(turn.draw), full.draw);
+    }
+
+    #[test]
+    fn test_view_after_turn_has_no_draw() {
+        let players = make_player_ids(2);
+        let g = Round::from_manual(
+            &[(players[0], Some(Card::Priestess)), (players[1], Some(Card::Soldier))],
+            &[Card::Clown, Card::Wizard], None).unwrap();
+        let (g, _) = g.handle_turn(|_, _| (Card::Priestess, action::Play::NoEffect), |_, _| ())
+            .unwrap().unwrap();
+        let view = g.view(players[0]).unwrap();
+        assert_eq!(Some(Card::Wizard), view.hand);
+        assert_eq!(None, view.draw);
+    }
+
+    #[test]
+    fn test_peek_turn_matches_handle_turn() {
+        let players = make_player_ids(2);
+        let g = Round::from_manual(
+            &[(players[0], Some(Card::Priestess)), (players[1], Some(Card::Soldier))],
+            &[Card::Clown, Card::Wizard], None).unwrap();
+        let (_, turn) = g.peek_turn().unwrap();
+        assert_eq!(Turn::new(players[0], Card::Priestess, Card::Wizard), turn);
+        let (_, outcome) = g.handle_turn(|_, t| { assert_eq!(turn, *t); (t.hand, action::Play::NoEffect) },
+                                         |_, _| ()).unwrap().unwrap();
+        match outcome {
+            super::TurnOutcome::Played(p, Card::Priestess, _, _) => assert_eq!(players[0], p),
+            other => panic!("Unexpected outcome: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_from_view_resumes_turn() {
+        let players = make_player_ids(3);
+        let g = Round::from_manual(
+            &[(players[0], Some(Card::Priestess)),
+              (players[1], Some(Card::Soldier)),
+              (players[2], Some(Card::Knight))],
+            &[Card::Clown, Card::Soldier, Card::Wizard, Card::General], None).unwrap();
+        let (g, _) = g.handle_turn(|_, _| (Card::Priestess, action::Play::NoEffect), |_, _| ())
+            .unwrap().unwrap();
+        let (ready, turn) = g.peek_turn().unwrap();
+        let view = ready.view(turn.player).unwrap();
+        let rebuilt = Round::from_view(
+            &view, &[(players[0], Card::General), (players[2], Card::Knight)],
+            &[Card::Clown, Card::Soldier]).unwrap();
+        let (rebuilt_ready, rebuilt_turn) = rebuilt.peek_turn().unwrap();
+        assert_eq!(turn, rebuilt_turn);
+        assert_eq!(view, rebuilt_ready.view(turn.player).unwrap());
+    }
+
+    #[test]
+    fn test_from_view_needs_turn() {
+        let g = make_round(2);
+        let players = g.player_ids();
+        let view = g.view(players[0]).unwrap();
+        assert_eq!(
+            Err(super::Error::NotPlayersTurn(players[0])),
+            Round::from_view(&view, &[], &[]));
+    }
+
+    #[test]
+    fn test_from_view_missing_hand() {
+        let g = make_round(2);
+        let players = g.player_ids();
+        let (ready, _) = g.peek_turn().unwrap();
+        let view = ready.view(players[0]).unwrap();
+        assert_eq!(
+            Err(super::Error::MissingHand(players[1])),
+            Round::from_view(&view, &[], &[]));
+    }
+
+    #[test]
+    fn test_view_nonexistent_player() {
+        let players: Vec<PlayerId> = make_player_ids(5);
+        let round = Round::new(players.slice_to(4));
+        assert_eq!(Err(PlayError::InvalidPlayer(players[4])), round.view(players[4]));
+    }
+
     #[test]
     fn test_round_result_survivors() {
         let player_ids = make_player_ids(2);
@@ -0,0 +1,241 @@
+/// Export self-play games as training data.
+///
+/// Agents play whole games against each other, and every decision they make
+/// is written out as a single line of JSON. Turns where a player busted out
+/// with the Minister aren't decisions, so they aren't recorded.
+///
+/// ### Schema
+///
+/// Each line is an object with these fields:
+///
+/// * `game`: index of the game within this export, starting at 0
+/// * `round`: index of the round within the game, starting at 0
+/// * `turn`: index of the turn within the round, starting at 0. Busted turns
+///   are counted.
+/// * `num_players`: number of players in the game
+/// * `player`: seat of the acting player, where seats are numbered from 0 in
+///   order of play
+/// * `hand`: the card the acting player was holding
+/// * `draw`: the card the acting player just drew
+/// * `discards`: for each seat, a list of the cards they have discarded,
+///   oldest first
+/// * `protected`: for each seat, whether they are protected by the Priestess
+/// * `active`: for each seat, whether they are still in the round
+/// * `deck_size`: number of cards left to draw
+/// * `legal_plays`: every play the acting player could have made
+/// * `chosen`: the play they made
+/// * `round_winners`: seats of the players who won this round
+/// * `game_winners`: seats of the players who won this game
+///
+/// Cards are strings: `"Soldier"`, `"Clown"`, `"Knight"`, `"Priestess"`,
+/// `"Wizard"`, `"General"`, `"Minister"` or `"Princess"`.
+///
+/// A play is an object with these fields:
+///
+/// * `card`: the card played
+/// * `effect`: `"none"`, `"attack"` or `"guess"`
+/// * `target`: seat of the targeted player, or `null` if `effect` is `"none"`
+/// * `guess`: the guessed card, or `null` if `effect` is not `"guess"`
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::IoResult;
+
+use serialize::json::{Json, ToJson};
+
+use action::Play;
+use agent;
+use agent::Agent;
+use deck::Card;
+use game;
+use player_id::PlayerId;
+use protocol::{card_to_json, cards_to_json, play_to_json};
+use view::PlayerView;
+
+
+/// A single decision made by an agent.
+#[derive(Show, Clone)]
+pub struct Decision {
+    pub round: usize,
+    pub turn: usize,
+    pub view: PlayerView,
+    pub chosen: (Card, Play),
+}
+
+
+/// Everything that happened in a single self-play game.
+#[derive(Show, Clone)]
+pub struct GameRecord {
+    /// The players, in order of play.
+    pub players: Vec<PlayerId>,
+    pub decisions: Vec<Decision>,
+    /// The winners of each round.
+    pub round_winners: Vec<Vec<PlayerId>>,
+    pub game_winners: Vec<PlayerId>,
+}
+
+
+/// Play a whole game between `agents`, recording every decision.
+///
+/// `agents` has one agent per player, in order of play.
+pub fn self_play(agents: &[&Agent]) -> GameRecord {
+    let mut current_game = game::new_game(agents.len()).expect("Invalid number of agents");
+    let players = current_game.players();
+    let mut decisions = vec![];
+    let mut round_winners = vec![];
+    loop {
+        let round = match current_game.next_round() {
+            Some(r) => r,
+            None => break,
+        };
+        let round_num = round_winners.len();
+        let mut current_round = round;
+        let mut turn_num = 0;
+        loop {
+            let decision = RefCell::new(None);
+            let result = current_round.handle_turn(
+                |r, turn| {
+                    let view = r.view(turn.player).ok().expect("Current player not in round");
+                    let chosen = agent::decide(r, players.as_slice(), agents, turn.player);
+                    *decision.borrow_mut() = Some(Decision {
+                        round: round_num, turn: turn_num, view: view, chosen: chosen });
+                    chosen
+                },
+                |_, _| ());
+            match result {
+                Ok(None) => break,
+                Ok(Some((new_round, _))) => current_round = new_round,
+                Err(e) => panic!("Agent made an invalid play: {:?}", e),
+            }
+            match decision.into_inner() {
+                Some(d) => decisions.push(d),
+                None => (),
+            }
+            turn_num += 1;
+        }
+        let winners: Vec<PlayerId> = current_round.winners().iter().map(|&(i, _)| i).collect();
+        current_game = current_game.players_won(winners.as_slice());
+        round_winners.push(winners);
+    }
+    GameRecord {
+        players: players,
+        decisions: decisions,
+        round_winners: round_winners,
+        game_winners: current_game.winners(),
+    }
+}
+
+
+/// Play `num_games` games between `agents` and write every decision to
+/// `writer`, one JSON object per line.
+///
+/// Returns the number of decisions written.
+pub fn export<W: Writer>(writer: &mut W, num_games: usize, agents: &[&Agent]) -> IoResult<usize> {
+    let mut count = 0;
+    for game_num in range(0, num_games) {
+        let record = self_play(agents);
+        for json in record_to_json(game_num, &record).iter() {
+            try!(writer.write_line(json.to_string().as_slice()));
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+
+/// Turn a game record into one JSON object per decision.
+pub fn record_to_json(game_num: usize, record: &GameRecord) -> Vec<Json> {
+    let seats = |players: &[PlayerId]| -> Json {
+        players
+            .iter()
+            .map(|p| seat(record.players.as_slice(), *p))
+            .collect::<Vec<usize>>()
+            .to_json()
+    };
+    let game_winners = seats(record.game_winners.as_slice());
+    record.decisions.iter().map(|decision| {
+        let view = &decision.view;
+        let mut obj = BTreeMap::new();
+        obj.insert("game".to_string(), game_num.to_json());
+        obj.insert("round".to_string(), decision.round.to_json());
+        obj.insert("turn".to_string(), decision.turn.to_json());
+        obj.insert("num_players".to_string(), record.players.len().to_json());
+        obj.insert("player".to_string(), seat(record.players.as_slice(), view.player).to_json());
+        obj.insert("hand".to_string(), card_to_json(view.hand.expect("Decision by inactive player")));
+        obj.insert("draw".to_string(), card_to_json(view.draw.expect("Decision without a draw")));
+        obj.insert(
+            "discards".to_string(),
+            Json::Array(view.players.iter().map(|p| cards_to_json(p.discards.as_slice())).collect()));
+        obj.insert(
+            "protected".to_string(),
+            view.players.iter().map(|p| p.protected).collect::<Vec<bool>>().to_json());
+        obj.insert(
+            "active".to_string(),
+            view.players.iter().map(|p| p.active).collect::<Vec<bool>>().to_json());
+        obj.insert("deck_size".to_string(), view.deck_size.to_json());
+        obj.insert(
+            "legal_plays".to_string(),
+            Json::Array(view.legal_plays()
+                        .iter()
+                        .map(|&(card, play)| play_to_json(record.players.as_slice(), card, play))
+                        .collect()));
+        let (card, play) = decision.chosen;
+        obj.insert("chosen".to_string(), play_to_json(record.players.as_slice(), card, play));
+        obj.insert(
+            "round_winners".to_string(),
+            seats(record.round_winners[decision.round].as_slice()));
+        obj.insert("game_winners".to_string(), game_winners.clone());
+        Json::Object(obj)
+    }).collect()
+}
+
+
+fn seat(players: &[PlayerId], player: PlayerId) -> usize {
+    players.iter().position(|&p| p == player).expect("Unknown player")
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::MemWriter;
+    use std::str;
+
+    use serialize::json;
+
+    use agent::{Agent, RandomAgent};
+    use super::{export, self_play};
+
+    fn make_agents(n: usize) -> Vec<RandomAgent> {
+        range(0, n).map(|i| RandomAgent::from_seed([1, 2, 3, i as u32 + 4])).collect()
+    }
+
+    #[test]
+    fn test_self_play_finishes_game() {
+        let agents = make_agents(3);
+        let agent_refs: Vec<&Agent> = agents.iter().map(|a| a as &Agent).collect();
+        let record = self_play(agent_refs.as_slice());
+        assert!(record.game_winners.len() > 0);
+        assert!(record.decisions.len() > 0);
+        for decision in record.decisions.iter() {
+            assert!(decision.view.legal_plays().contains(&decision.chosen));
+        }
+    }
+
+    #[test]
+    fn test_export_writes_one_line_per_decision() {
+        let agents = make_agents(2);
+        let agent_refs: Vec<&Agent> = agents.iter().map(|a| a as &Agent).collect();
+        let mut writer = MemWriter::new();
+        let count = export(&mut writer, 2, agent_refs.as_slice()).unwrap();
+        let output = writer.into_inner();
+        let text = str::from_utf8(output.as_slice()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(count, lines.len());
+        for line in lines.iter() {
+            let record = json::from_str(*line).unwrap();
+            let legal = record.find("legal_plays").unwrap().as_array().unwrap();
+            let chosen = record.find("chosen").unwrap();
+            assert!(legal.contains(chosen));
+        }
+    }
+}
@@ -0,0 +1,839 @@
+/// Host games of Love Letter over TCP.
+///
+/// Clients connect, send one JSON message per line, and get JSON messages
+/// back, one per line. Browsers can connect with WebSocket instead, and send
+/// and receive the same messages, one per text frame. See `protocol` for the
+/// messages, and `table` for how the game itself is run.
+///
+/// Losing the connection doesn't lose the seat. Players get a session token
+/// when they join, and can use it to rejoin from a new connection. If they
+/// don't come back within the grace period, a bot plays on for them. There
+/// can also be a time limit on each turn.
+///
+/// Seats can be given to bots, which people can take over by joining.
+///
+/// Connections can also watch a table instead of playing at it.
+///
+/// If given somewhere to keep them, the server saves every table whenever
+/// anything happens at it, and picks up where it left off when restarted.
+
+use std::collections::HashMap;
+use std::io::{BufferedReader, IoError, IoErrorKind, IoResult, TcpListener, TcpStream};
+use std::io::stdio::stderr;
+use std::io::{Acceptor, Listener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::io::timer::sleep;
+use std::thread::Thread;
+use std::time::Duration;
+
+use agent::AgentKind;
+use game::Variant;
+use lobby::Lobby;
+use player_id::PlayerId;
+use protocol;
+use protocol::{ClientMessage, ServerMessage};
+use storage;
+use storage::Storage;
+use table;
+use table::Outgoing;
+use websocket;
+
+
+/// Somewhere to send lines of text to a client.
+///
+/// Only the server's own thread writes to an outbox, so whatever it sends
+/// can't be interleaved with anything else.
+pub trait Outbox: Send {
+    fn send_line(&mut self, line: &str) -> IoResult<()>;
+
+    /// Answer a ping. Only WebSocket clients send them.
+    fn send_pong(&mut self, _payload: &[u8]) -> IoResult<()> {
+        Ok(())
+    }
+
+    /// Say goodbye, if the transport has a way to, before the connection
+    /// is dropped.
+    fn close(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+
+impl Outbox for TcpStream {
+    fn send_line(&mut self, line: &str) -> IoResult<()> {
+        self.write_line(line)
+    }
+}
+
+
+/// A client connected with WebSocket.
+pub struct WebSocketOutbox {
+    stream: TcpStream,
+}
+
+
+impl Outbox for WebSocketOutbox {
+    fn send_line(&mut self, line: &str) -> IoResult<()> {
+        websocket::write_text(&mut self.stream, line)
+    }
+
+    fn send_pong(&mut self, payload: &[u8]) -> IoResult<()> {
+        websocket::write_pong(&mut self.stream, payload)
+    }
+
+    fn close(&mut self) -> IoResult<()> {
+        websocket::write_close(&mut self.stream)
+    }
+}
+
+
+/// Something that happened to one of the server's connections.
+pub enum ServerEvent {
+    /// A new client has connected. Messages for them go to the outbox.
+    Connected(usize, Box<Outbox + Send>),
+    /// A client sent us a line.
+    Received(usize, String),
+    /// A WebSocket client pinged us, and wants the payload back.
+    Pinged(usize, Vec<u8>),
+    /// A client has gone away.
+    Disconnected(usize),
+    /// Another second has passed.
+    Tick,
+}
+
+
+/// Everyone connected to the server, and the tables they're playing at.
+pub struct Server {
+    _lobby: Lobby,
+    _outboxes: HashMap<usize, Box<Outbox + Send>>,
+    /// Which connection is sitting at which table, in which seat.
+    _seats: HashMap<usize, (usize, usize)>,
+    /// Which connection is watching which table, and whether they can see
+    /// everyone's hands.
+    _spectators: HashMap<usize, (usize, bool)>,
+    /// Seconds since the server started.
+    _now: u64,
+    /// Where to save tables, if anywhere.
+    _storage: Option<Storage>,
+}
+
+
+impl Server {
+    pub fn new(lobby: Lobby) -> Server {
+        Server {
+            _lobby: lobby,
+            _outboxes: HashMap::new(),
+            _seats: HashMap::new(),
+            _spectators: HashMap::new(),
+            _now: 0,
+            _storage: None,
+        }
+    }
+
+    /// Save every table to `storage` whenever anything happens at it.
+    pub fn set_storage(&mut self, storage: Storage) {
+        self._storage = Some(storage);
+    }
+
+    /// Have all the games been won?
+    pub fn is_over(&self) -> bool {
+        self._lobby.is_over()
+    }
+
+    /// Deal with something that happened to a connection.
+    pub fn handle_event(&mut self, event: ServerEvent) {
+        match event {
+            ServerEvent::Connected(id, outbox) => {
+                self._outboxes.insert(id, outbox);
+            },
+            ServerEvent::Received(id, line) => {
+                match protocol::decode_client_message(line.as_slice()) {
+                    Ok(message) => self.handle_message(id, message),
+                    Err(e) => self.send_error(id, format!("{:?}", e)),
+                }
+            },
+            ServerEvent::Pinged(id, payload) => {
+                let failed = match self._outboxes.get_mut(&id) {
+                    Some(outbox) => outbox.send_pong(payload.as_slice()).is_err(),
+                    None => false,
+                };
+                if failed {
+                    self._outboxes.remove(&id);
+                }
+            },
+            ServerEvent::Disconnected(id) => {
+                match self._outboxes.remove(&id) {
+                    Some(mut outbox) => { let _ = outbox.close(); },
+                    None => (),
+                }
+                self._spectators.remove(&id);
+                self.disconnect(id);
+            },
+            ServerEvent::Tick => {
+                self._now += 1;
+                for (table, messages) in self._lobby.tick(self._now).into_iter() {
+                    self.dispatch(table, messages.as_slice());
+                }
+            },
+        }
+    }
+
+    fn handle_message(&mut self, id: usize, message: ClientMessage) {
+        match (message, self._seats.get(&id).map(|&s| s)) {
+            (ClientMessage::ListTables, _) => {
+                let tables = self._lobby.list();
+                self.send_to_connection(id, &[], &ServerMessage::Tables(tables));
+            },
+            (ClientMessage::CreateTable(num_players, variant), _) => {
+                match self._lobby.create(num_players, variant) {
+                    Ok(table) => self.send_to_connection(id, &[], &ServerMessage::TableCreated(table)),
+                    Err(e) => self.send_error(id, format!("{:?}", e)),
+                }
+            },
+            (ClientMessage::AddBot(table, kind), _) => {
+                match self._lobby.add_bot(table, kind) {
+                    Ok((_, messages)) => self.dispatch(table, messages.as_slice()),
+                    Err(e) => self.send_error(id, format!("{:?}", e)),
+                }
+            },
+            (ClientMessage::Join(..), Some(_)) => self.send_error(id, "Already seated".to_string()),
+            (ClientMessage::Join(name, table), None) => {
+                self._spectators.remove(&id);
+                match self._lobby.join(table, name.as_slice()) {
+                    Ok((table, seat, token, messages)) => {
+                        self._seats.insert(id, (table, seat));
+                        let summary = self._lobby.summary(table).expect("Joined a missing table");
+                        self.send_to_connection(id, &[], &ServerMessage::Joined(summary));
+                        self.send_to_connection(id, &[], &ServerMessage::Session(token));
+                        self.dispatch(table, messages.as_slice());
+                    },
+                    Err(e) => self.send_error(id, format!("{:?}", e)),
+                }
+            },
+            (ClientMessage::Rejoin(..), Some(_)) => self.send_error(id, "Already seated".to_string()),
+            (ClientMessage::Rejoin(token), None) => {
+                self._spectators.remove(&id);
+                match self._lobby.rejoin(token.as_slice()) {
+                    Ok((table, seat, messages)) => {
+                        // Whoever had the seat before is presumably a dead
+                        // connection we haven't noticed yet.
+                        let stale: Vec<usize> = self._seats
+                            .iter()
+                            .filter(|&(_, &place)| place == (table, seat))
+                            .map(|(&other, _)| other)
+                            .collect();
+                        for other in stale.iter() {
+                            self._seats.remove(other);
+                        }
+                        self._seats.insert(id, (table, seat));
+                        let summary = self._lobby.summary(table).expect("Rejoined a missing table");
+                        self.send_to_connection(id, &[], &ServerMessage::Joined(summary));
+                        self.dispatch(table, messages.as_slice());
+                    },
+                    Err(e) => self.send_error(id, format!("{:?}", e)),
+                }
+            },
+            (ClientMessage::Spectate(..), Some(_)) => self.send_error(id, "Already seated".to_string()),
+            (ClientMessage::Spectate(table, omniscient), None) => {
+                match self._lobby.spectate(table) {
+                    Ok(messages) => {
+                        self._spectators.insert(id, (table, omniscient));
+                        let summary = self._lobby.summary(table).expect("Watching a missing table");
+                        self.send_to_connection(id, &[], &ServerMessage::Spectating(summary));
+                        let players = self._lobby.get(table).expect("Watching a missing table").players();
+                        for message in messages.iter() {
+                            self.send_to_connection(id, players.as_slice(), message);
+                        }
+                    },
+                    Err(e) => self.send_error(id, format!("{:?}", e)),
+                }
+            },
+            (ClientMessage::Leave, None) => match self._spectators.remove(&id) {
+                Some((table, _)) => self.send_to_connection(id, &[], &ServerMessage::Left(table)),
+                None => self.send_error(id, "Not at a table".to_string()),
+            },
+            (ClientMessage::Leave, Some((table, _))) => {
+                self.leave(id);
+                self.send_to_connection(id, &[], &ServerMessage::Left(table));
+            },
+            (ClientMessage::Play(..), None) => self.send_error(id, "Join a table first".to_string()),
+            (ClientMessage::Play(card, play), Some((table, seat))) => {
+                let messages = self._lobby.submit(table, seat, card, play).ok().expect("Seated at a missing table");
+                self.dispatch(table, messages.as_slice());
+            },
+            (ClientMessage::Chat(..), None) | (ClientMessage::Emote(..), None) =>
+                self.send_error(id, "Join a table first".to_string()),
+            (ClientMessage::Chat(text), Some((table, seat))) => {
+                let messages = self._lobby.chat(table, seat, text.as_slice()).ok().expect("Seated at a missing table");
+                self.dispatch(table, messages.as_slice());
+            },
+            (ClientMessage::Emote(emote), Some((table, seat))) => {
+                let messages = self._lobby.emote(table, seat, emote).ok().expect("Seated at a missing table");
+                self.dispatch(table, messages.as_slice());
+            },
+        }
+    }
+
+    /// Take connection `id` away from its table, if it's at one.
+    fn leave(&mut self, id: usize) {
+        match self._seats.remove(&id) {
+            Some((table, seat)) => {
+                let messages = self._lobby.leave(table, seat).ok().expect("Seated player not at table");
+                self.dispatch(table, messages.as_slice());
+            },
+            None => (),
+        }
+    }
+
+    /// Connection `id` has gone away. Its seat is kept for a while, in case
+    /// they come back.
+    fn disconnect(&mut self, id: usize) {
+        match self._seats.remove(&id) {
+            Some((table, seat)) => {
+                let now = self._now;
+                self._lobby.disconnect(table, seat, now).ok().expect("Seated player not at table");
+            },
+            None => (),
+        }
+    }
+
+    /// Send messages from a table to the connections sitting there, and
+    /// those watching.
+    fn dispatch(&mut self, table: usize, messages: &[Outgoing]) {
+        let players = self._lobby.get(table).expect("Messages from a missing table").players();
+        for message in messages.iter() {
+            let (ids, message) = match *message {
+                Outgoing::To(seat, ref message) => (self.seated(table, Some(seat)), message),
+                Outgoing::All(ref message) => {
+                    let mut ids = self.seated(table, None);
+                    ids.push_all(self.watching(table, false).as_slice());
+                    (ids, message)
+                },
+                Outgoing::Spectators(ref message) => (self.watching(table, false), message),
+                Outgoing::Omniscient(ref message) => (self.watching(table, true), message),
+            };
+            for &id in ids.iter() {
+                self.send_to_connection(id, players.as_slice(), message);
+            }
+        }
+        if messages.len() > 0 {
+            self.save(table);
+        }
+    }
+
+    /// Write `table` to storage, or take it out if the game is over.
+    fn save(&self, id: usize) {
+        let storage = match self._storage {
+            Some(ref storage) => storage,
+            None => return,
+        };
+        let table = self._lobby.get(id).expect("Saving a missing table");
+        let result = if table.is_over() {
+            storage.remove(id)
+        } else {
+            storage.save(id, table, self._lobby.sessions(id).as_slice())
+        };
+        match result {
+            Ok(()) => (),
+            // Carry on: the game can still be played, even if it won't
+            // survive a restart.
+            Err(e) => { let _ = writeln!(&mut stderr(), "Could not save table {}: {:?}", id, e); },
+        }
+    }
+
+    /// The connections sitting at `table`, in `seat` if given.
+    fn seated(&self, table: usize, seat: Option<usize>) -> Vec<usize> {
+        self._seats
+            .iter()
+            .filter(|&(_, &(t, s))| t == table && seat.map_or(true, |seat| s == seat))
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// The connections watching `table`. Only those who can see everyone's
+    /// hands if `omniscient`.
+    fn watching(&self, table: usize, omniscient: bool) -> Vec<usize> {
+        self._spectators
+            .iter()
+            .filter(|&(_, &(t, o))| t == table && (o || !omniscient))
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    fn send_error(&mut self, id: usize, message: String) {
+        self.send_to_connection(id, &[], &ServerMessage::Error(message));
+    }
+
+    /// Send `message` to connection `id`. `players` lists everyone at the
+    /// table the message is about, by seat.
+    fn send_to_connection(&mut self, id: usize, players: &[PlayerId], message: &ServerMessage) {
+        let line = protocol::encode_server_message(players, message);
+        let failed = match self._outboxes.get_mut(&id) {
+            Some(outbox) => outbox.send_line(line.as_slice()).is_err(),
+            None => false,
+        };
+        if failed {
+            // The reader thread will notice too, and tell us it's gone.
+            self._outboxes.remove(&id);
+        }
+    }
+}
+
+
+/// Read lines from a client until they go away, passing them on as events.
+fn read_lines(id: usize, stream: TcpStream, events: Sender<ServerEvent>) {
+    let mut reader = BufferedReader::new(stream);
+    for line in reader.lines() {
+        match line {
+            Ok(line) => {
+                let line = line.as_slice().trim();
+                if line.len() > 0 {
+                    let _ = events.send(ServerEvent::Received(id, line.to_string()));
+                }
+            },
+            Err(..) => break,
+        }
+    }
+    let _ = events.send(ServerEvent::Disconnected(id));
+}
+
+
+/// Complete the WebSocket handshake, then pass on each message as an event
+/// until the client goes away. Once the handshake is done, everything sent
+/// to the client goes through its outbox, pongs included.
+fn read_websocket(id: usize, stream: TcpStream, events: Sender<ServerEvent>) {
+    let mut writer = stream.clone();
+    let mut reader = BufferedReader::new(stream);
+    match websocket::server_handshake(&mut reader, &mut writer) {
+        Ok(()) => (),
+        Err(..) => return,
+    }
+    let outbox = WebSocketOutbox { stream: writer };
+    let _ = events.send(ServerEvent::Connected(id, Box::new(outbox) as Box<Outbox + Send>));
+    loop {
+        match websocket::read_client_message(&mut reader) {
+            Ok(websocket::Message::Text(text)) => {
+                let _ = events.send(ServerEvent::Received(id, text.as_slice().trim().to_string()));
+            },
+            Ok(websocket::Message::Ping(payload)) => {
+                let _ = events.send(ServerEvent::Pinged(id, payload));
+            },
+            Ok(websocket::Message::Binary(..)) | Ok(websocket::Message::Pong(..)) => (),
+            Ok(websocket::Message::Close) | Err(..) => break,
+        }
+    }
+    let _ = events.send(ServerEvent::Disconnected(id));
+}
+
+
+/// Tell the server every time a second passes, until it stops listening.
+fn tick(events: Sender<ServerEvent>) {
+    loop {
+        sleep(Duration::seconds(1));
+        if events.send(ServerEvent::Tick).is_err() {
+            break;
+        }
+    }
+}
+
+
+#[derive(Copy)]
+/// How clients talk to us.
+enum Transport {
+    Lines,
+    WebSocket,
+}
+
+
+/// Accept connections forever, starting a reader for each one. Connections
+/// from every listener share `next_id`, so their ids don't clash.
+fn accept_connections(listener: TcpListener, transport: Transport, next_id: Arc<AtomicUsize>,
+                      events: Sender<ServerEvent>) {
+    let mut acceptor = match listener.listen() {
+        Ok(acceptor) => acceptor,
+        Err(e) => panic!("Could not listen: {}", e),
+    };
+    for stream in acceptor.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(..) => continue,
+        };
+        let id = next_id.fetch_add(1, Ordering::SeqCst);
+        let sender = events.clone();
+        match transport {
+            Transport::Lines => {
+                let outbox = Box::new(stream.clone()) as Box<Outbox + Send>;
+                let _ = events.send(ServerEvent::Connected(id, outbox));
+                Thread::spawn(move || read_lines(id, stream, sender));
+            },
+            // The handshake can take a while, so it's done on the reader's
+            // thread, which says when the client has connected.
+            Transport::WebSocket => { Thread::spawn(move || read_websocket(id, stream, sender)); },
+        }
+    }
+}
+
+
+/// How to run a server.
+pub struct Config {
+    /// Where to accept line-delimited connections.
+    pub address: String,
+    /// Where to accept WebSocket connections, if anywhere.
+    pub websocket_address: Option<String>,
+    /// The number of players at the table the server starts with.
+    pub num_players: usize,
+    /// The rules for the table the server starts with.
+    pub variant: Variant,
+    /// Keep running once every game is over, so people can set up new
+    /// tables. Otherwise, stop once there's a winner at every table.
+    pub lobby: bool,
+    /// How many seconds to wait for someone who's lost their connection
+    /// before a bot takes their seat.
+    pub grace_period: u64,
+    /// How many seconds players get to make each play, if there's a limit.
+    pub turn_limit: Option<u64>,
+    /// How many seconds omniscient spectators wait to see hands. If `None`,
+    /// they wait until the end of the round.
+    pub omniscient_delay: Option<u64>,
+    /// A directory to save tables in, so they survive restarts.
+    pub storage: Option<String>,
+    /// Bots to sit at the table the server starts with, leaving the rest of
+    /// the seats for people.
+    pub bots: Vec<AgentKind>,
+    /// How many seconds bots spend thinking about each play.
+    pub think_delay: u64,
+}
+
+
+impl Config {
+    /// Run a single quick game for two players on `address`.
+    pub fn new(address: &str) -> Config {
+        Config {
+            address: address.to_string(),
+            websocket_address: None,
+            num_players: 2,
+            variant: Variant::Quick,
+            lobby: false,
+            grace_period: table::DEFAULT_GRACE_PERIOD,
+            turn_limit: None,
+            omniscient_delay: None,
+            storage: None,
+            bots: vec![],
+            think_delay: 0,
+        }
+    }
+}
+
+
+/// Start a table as described by `config`, and run the server.
+///
+/// If there are tables saved from last time, carry on with those instead.
+pub fn serve(config: &Config) -> IoResult<()> {
+    let mut lobby = Lobby::with_grace_period(config.grace_period);
+    lobby.set_turn_limit(config.turn_limit);
+    lobby.set_omniscient_delay(config.omniscient_delay);
+    lobby.set_think_delay(config.think_delay);
+    let storage = match config.storage {
+        Some(ref dir) => Some(try!(open_storage(dir.as_slice(), &mut lobby))),
+        None => None,
+    };
+    if lobby.list().len() == 0 {
+        let table = match lobby.create(config.num_players, config.variant) {
+            Ok(table) => table,
+            Err(e) => panic!("Could not create table: {:?}", e),
+        };
+        for &kind in config.bots.iter() {
+            match lobby.add_bot(table, kind) {
+                Ok(..) => (),
+                Err(e) => panic!("Could not add bot: {:?}", e),
+            }
+        }
+    }
+    let (sender, receiver): (Sender<ServerEvent>, Receiver<ServerEvent>) = channel();
+    let next_id = Arc::new(AtomicUsize::new(0));
+    let listener = try!(TcpListener::bind(config.address.as_slice()));
+    let (ids, events) = (next_id.clone(), sender.clone());
+    Thread::spawn(move || accept_connections(listener, Transport::Lines, ids, events));
+    match config.websocket_address {
+        Some(ref websocket_address) => {
+            let listener = try!(TcpListener::bind(websocket_address.as_slice()));
+            let (ids, events) = (next_id.clone(), sender.clone());
+            Thread::spawn(move || accept_connections(listener, Transport::WebSocket, ids, events));
+        },
+        None => (),
+    }
+    let events = sender.clone();
+    Thread::spawn(move || tick(events));
+    let mut server = Server::new(lobby);
+    match storage {
+        Some(storage) => server.set_storage(storage),
+        None => (),
+    }
+    while config.lobby || !server.is_over() {
+        match receiver.recv() {
+            Ok(event) => server.handle_event(event),
+            Err(..) => break,
+        }
+    }
+    Ok(())
+}
+
+
+/// Keep tables in `dir`, restoring any that are already there to `lobby`.
+fn open_storage(dir: &str, lobby: &mut Lobby) -> IoResult<Storage> {
+    let result = Storage::new(Path::new(dir)).and_then(|storage| storage.load().map(|saved| (storage, saved)));
+    match result {
+        Ok((storage, saved)) => {
+            for table in saved.into_iter() {
+                lobby.restore(table.id, table.table, table.sessions.as_slice());
+            }
+            Ok(storage)
+        },
+        Err(storage::Error::Io(e)) => Err(e),
+        Err(storage::Error::Malformed(path, e)) => Err(IoError {
+            kind: IoErrorKind::InvalidInput,
+            desc: "Could not read saved table",
+            detail: Some(format!("{}: {:?}", path.display(), e)),
+        }),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::IoResult;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+
+    use serialize::json;
+
+    use game::Variant;
+    use lobby::Lobby;
+    use super::{Outbox, Server, ServerEvent};
+
+    struct ChannelOutbox {
+        sender: Sender<String>,
+    }
+
+    impl Outbox for ChannelOutbox {
+        fn send_line(&mut self, line: &str) -> IoResult<()> {
+            let _ = self.sender.send(line.to_string());
+            Ok(())
+        }
+    }
+
+    fn connect(server: &mut Server, id: usize) -> Receiver<String> {
+        let (sender, receiver) = channel();
+        server.handle_event(ServerEvent::Connected(id, Box::new(ChannelOutbox { sender: sender })));
+        receiver
+    }
+
+    fn message_types(receiver: &Receiver<String>) -> Vec<String> {
+        let mut types = vec![];
+        loop {
+            let line = match receiver.try_recv() {
+                Ok(line) => line,
+                Err(..) => return types,
+            };
+            let message = json::from_str(line.as_slice()).unwrap();
+            types.push(message.find("type").unwrap().as_string().unwrap().to_string());
+        }
+    }
+
+    fn make_server(num_tables: usize) -> Server {
+        let mut lobby = Lobby::new();
+        for _ in range(0, num_tables) {
+            lobby.create(2, Variant::Quick).unwrap();
+        }
+        Server::new(lobby)
+    }
+
+    fn receive(server: &mut Server, id: usize, line: &str) {
+        server.handle_event(ServerEvent::Received(id, line.to_string()));
+    }
+
+    #[test]
+    fn test_join_and_start() {
+        let mut server = make_server(1);
+        let alice = connect(&mut server, 0);
+        let bob = connect(&mut server, 1);
+        receive(&mut server, 0, r#"{"type": "join", "name": "alice"}"#);
+        assert_eq!(vec!["joined", "session", "welcome", "seated"], message_types(&alice));
+        receive(&mut server, 1, r#"{"type": "join", "name": "bob"}"#);
+        let alice_types = message_types(&alice);
+        let bob_types = message_types(&bob);
+        assert!(alice_types.contains(&"round_started".to_string()));
+        assert!(bob_types.contains(&"welcome".to_string()));
+        assert!(alice_types.contains(&"view".to_string()));
+        assert!(bob_types.contains(&"view".to_string()));
+        let turns = alice_types.iter().chain(bob_types.iter()).filter(|t| t.as_slice() == "your_turn").count();
+        assert_eq!(1, turns);
+    }
+
+    #[test]
+    fn test_garbage_gets_error() {
+        let mut server = make_server(1);
+        let alice = connect(&mut server, 0);
+        receive(&mut server, 0, "hello");
+        assert_eq!(vec!["error"], message_types(&alice));
+    }
+
+    #[test]
+    fn test_create_and_join_table() {
+        let mut server = make_server(0);
+        let alice = connect(&mut server, 0);
+        let bob = connect(&mut server, 1);
+        let carol = connect(&mut server, 2);
+        receive(&mut server, 0, r#"{"type": "create_table", "num_players": 2, "variant": "classic"}"#);
+        receive(&mut server, 1, r#"{"type": "create_table", "num_players": 3, "variant": "quick"}"#);
+        assert_eq!(vec!["table_created"], message_types(&alice));
+        assert_eq!(vec!["table_created"], message_types(&bob));
+        receive(&mut server, 2, r#"{"type": "list_tables"}"#);
+        assert_eq!(vec!["tables"], message_types(&carol));
+        receive(&mut server, 0, r#"{"type": "join", "name": "alice", "table": 1}"#);
+        receive(&mut server, 1, r#"{"type": "join", "name": "bob", "table": 0}"#);
+        message_types(&alice);
+        message_types(&bob);
+        // Carol joins Bob's table, and the game starts there, but not at
+        // Alice's.
+        receive(&mut server, 2, r#"{"type": "join", "name": "carol", "table": 0}"#);
+        assert!(message_types(&bob).contains(&"round_started".to_string()));
+        assert!(message_types(&carol).contains(&"round_started".to_string()));
+        assert_eq!(Vec::<String>::new(), message_types(&alice));
+        receive(&mut server, 0, r#"{"type": "leave"}"#);
+        assert_eq!(vec!["left"], message_types(&alice));
+    }
+
+    fn session_token(receiver: &Receiver<String>) -> String {
+        let mut lines = vec![];
+        loop {
+            match receiver.try_recv() {
+                Ok(line) => lines.push(line),
+                Err(..) => break,
+            }
+        }
+        lines
+            .iter()
+            .map(|line| json::from_str(line.as_slice()).unwrap())
+            .filter(|m| m.find("type").unwrap().as_string() == Some("session"))
+            .filter_map(|m| m.find("token").and_then(|t| t.as_string()).map(|t| t.to_string()))
+            .next()
+            .expect("No session token")
+    }
+
+    #[test]
+    fn test_rejoin_after_disconnect() {
+        let mut lobby = Lobby::with_grace_period(5);
+        lobby.create(2, Variant::Quick).unwrap();
+        let mut server = Server::new(lobby);
+        let alice = connect(&mut server, 0);
+        let bob = connect(&mut server, 1);
+        receive(&mut server, 0, r#"{"type": "join", "name": "alice"}"#);
+        receive(&mut server, 1, r#"{"type": "join", "name": "bob"}"#);
+        let token = session_token(&alice);
+        message_types(&bob);
+        server.handle_event(ServerEvent::Disconnected(0));
+        for _ in range(0, 3) {
+            server.handle_event(ServerEvent::Tick);
+        }
+        assert_eq!(Vec::<String>::new(), message_types(&bob));
+        let alice = connect(&mut server, 2);
+        receive(&mut server, 2, format!(r#"{{"type": "rejoin", "token": "{}"}}"#, token).as_slice());
+        let types = message_types(&alice);
+        assert_eq!(vec!["joined", "welcome", "seated", "round_started"], types[..4].to_vec());
+        assert!(types.contains(&"view".to_string()) || types.contains(&"your_turn".to_string()));
+        receive(&mut server, 1, r#"{"type": "rejoin", "token": "nonsense"}"#);
+        assert_eq!(vec!["error"], message_types(&bob));
+    }
+
+    #[test]
+    fn test_bot_takes_over_after_grace_period() {
+        let mut lobby = Lobby::with_grace_period(2);
+        lobby.create(2, Variant::Quick).unwrap();
+        let mut server = Server::new(lobby);
+        connect(&mut server, 0);
+        let bob = connect(&mut server, 1);
+        receive(&mut server, 0, r#"{"type": "join", "name": "alice"}"#);
+        receive(&mut server, 1, r#"{"type": "join", "name": "bob"}"#);
+        message_types(&bob);
+        server.handle_event(ServerEvent::Disconnected(0));
+        server.handle_event(ServerEvent::Tick);
+        assert_eq!(Vec::<String>::new(), message_types(&bob));
+        server.handle_event(ServerEvent::Tick);
+        server.handle_event(ServerEvent::Tick);
+        assert!(message_types(&bob).contains(&"bot_takeover".to_string()));
+    }
+
+    #[test]
+    fn test_bot_seat_thinks() {
+        let mut lobby = Lobby::new();
+        lobby.set_think_delay(2);
+        lobby.create(2, Variant::Quick).unwrap();
+        let mut server = Server::new(lobby);
+        let alice = connect(&mut server, 0);
+        receive(&mut server, 0, r#"{"type": "add_bot", "table": 0, "agent": "random"}"#);
+        assert_eq!(Vec::<String>::new(), message_types(&alice));
+        receive(&mut server, 0, r#"{"type": "add_bot", "table": 3, "agent": "random"}"#);
+        assert_eq!(vec!["error"], message_types(&alice));
+        receive(&mut server, 0, r#"{"type": "join", "name": "alice"}"#);
+        let types = message_types(&alice);
+        assert!(types.contains(&"round_started".to_string()));
+        if !types.contains(&"your_turn".to_string()) {
+            server.handle_event(ServerEvent::Tick);
+            assert_eq!(Vec::<String>::new(), message_types(&alice));
+            server.handle_event(ServerEvent::Tick);
+            assert!(message_types(&alice).contains(&"turn".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_spectate() {
+        let mut server = make_server(1);
+        let alice = connect(&mut server, 0);
+        let bob = connect(&mut server, 1);
+        let carol = connect(&mut server, 2);
+        let dave = connect(&mut server, 3);
+        receive(&mut server, 2, r#"{"type": "spectate", "table": 0}"#);
+        receive(&mut server, 3, r#"{"type": "spectate", "table": 0, "omniscient": true}"#);
+        assert_eq!(vec!["spectating", "seated"], message_types(&carol));
+        assert_eq!(vec!["spectating", "seated"], message_types(&dave));
+        receive(&mut server, 0, r#"{"type": "join", "name": "alice"}"#);
+        receive(&mut server, 1, r#"{"type": "join", "name": "bob"}"#);
+        message_types(&alice);
+        message_types(&bob);
+        let carol_types = message_types(&carol);
+        assert!(carol_types.contains(&"round_started".to_string()));
+        assert!(carol_types.contains(&"spectator_view".to_string()));
+        assert!(!carol_types.contains(&"view".to_string()));
+        assert!(!carol_types.contains(&"your_turn".to_string()));
+        assert!(message_types(&dave).contains(&"round_started".to_string()));
+        receive(&mut server, 2, r#"{"type": "leave"}"#);
+        assert_eq!(vec!["left"], message_types(&carol));
+        receive(&mut server, 2, r#"{"type": "spectate", "table": 7}"#);
+        assert_eq!(vec!["error"], message_types(&carol));
+    }
+
+    #[test]
+    fn test_chat_reaches_table() {
+        let mut server = make_server(2);
+        let alice = connect(&mut server, 0);
+        let bob = connect(&mut server, 1);
+        let carol = connect(&mut server, 2);
+        let dave = connect(&mut server, 3);
+        receive(&mut server, 0, r#"{"type": "join", "name": "alice", "table": 0}"#);
+        receive(&mut server, 1, r#"{"type": "join", "name": "bob", "table": 0}"#);
+        receive(&mut server, 2, r#"{"type": "spectate", "table": 0}"#);
+        receive(&mut server, 3, r#"{"type": "join", "name": "dave", "table": 1}"#);
+        for receiver in [&alice, &bob, &carol, &dave].iter() {
+            message_types(*receiver);
+        }
+        receive(&mut server, 0, r#"{"type": "chat", "text": "good luck"}"#);
+        receive(&mut server, 1, r#"{"type": "emote", "emote": "bow"}"#);
+        assert_eq!(vec!["chat", "emote"], message_types(&alice));
+        assert_eq!(vec!["chat", "emote"], message_types(&carol));
+        assert_eq!(Vec::<String>::new(), message_types(&dave));
+        receive(&mut server, 2, r#"{"type": "chat", "text": "hello"}"#);
+        assert_eq!(vec!["error"], message_types(&carol));
+    }
+}
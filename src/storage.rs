@@ -0,0 +1,410 @@
+/// Keep tables on disk, so games survive the server restarting.
+///
+/// Each table is kept in its own file, `table-<id>.json`, in a directory of
+/// its own. A file holds a single JSON object with a snapshot of the table
+/// and the session tokens of the players sitting at it:
+///
+/// ```text
+/// {"table": 0, "variant": "quick", "names": ["alice", "bob"],
+///  "bots": [false, false], "agents": [null, null], "scores": [1, 0],
+///  "num_rounds": 2,
+///  "round": {"deck": [<card>, ...], "hands": [<card or null>, ...],
+///            "protected": [false, true], "discards": [[<card>, ...], ...],
+///            "last_player": 0},
+///  "log": [{"seat": null, "message": <server message>}, ...],
+///  "sessions": {"<token>": 0, "<token>": 1}}
+/// ```
+///
+/// `agents` says which seats were given to bots, and what kind (see
+/// `protocol`); files without it have none. `round` is `null` between
+/// rounds, and `last_player` is `null` if nobody has played yet this round.
+/// Messages in the log are written as they are sent to clients (see
+/// `protocol`), along with the seat they were sent to, or `null` if they
+/// were sent to everyone. Turns are the exception: they're written out in
+/// full in the notation from `notation`, with what they did, so the log can
+/// be read at a glance:
+///
+/// ```text
+/// {"seat": null, "turn": "P1: Knight -> P2; P2 out"}
+/// ```
+
+use std::collections::BTreeMap;
+use std::io::{File, IoError, USER_RWX};
+use std::io::fs;
+use std::io::fs::PathExtensions;
+use std::str;
+
+use serialize::json;
+use serialize::json::{Json, ToJson};
+
+use game;
+use notation;
+use player_id::PlayerId;
+use protocol;
+use protocol::{ProtocolError, ServerMessage};
+use round::RoundSnapshot;
+use table::{Outgoing, Table, TableSnapshot};
+
+
+#[derive(Show)]
+pub enum Error {
+    /// Could not read or write a file.
+    Io(IoError),
+    /// A file has something in it we don't understand.
+    Malformed(Path, ProtocolError),
+}
+
+
+/// A table as it was last saved, with the session tokens for each seat.
+pub struct SavedTable {
+    pub id: usize,
+    pub table: Table,
+    pub sessions: Vec<(String, usize)>,
+}
+
+
+/// A directory full of tables.
+pub struct Storage {
+    _dir: Path,
+}
+
+
+impl Storage {
+    /// Keep tables in `dir`, creating it if need be.
+    pub fn new(dir: Path) -> Result<Storage, Error> {
+        if !dir.is_dir() {
+            try!(fs::mkdir_recursive(&dir, USER_RWX).map_err(Error::Io));
+        }
+        Ok(Storage { _dir: dir })
+    }
+
+    fn path(&self, id: usize) -> Path {
+        self._dir.join(format!("table-{}.json", id))
+    }
+
+    /// Write table `id` to disk, replacing what was there before.
+    ///
+    /// The table is written to a temporary file first, so a crash half way
+    /// through doesn't lose the last good copy.
+    pub fn save(&self, id: usize, table: &Table, sessions: &[(String, usize)]) -> Result<(), Error> {
+        let path = self.path(id);
+        let temp = path.with_extension("tmp");
+        let json = encode(id, &table.snapshot(), sessions);
+        {
+            let mut file = try!(File::create(&temp).map_err(Error::Io));
+            try!(file.write_str(json.to_string().as_slice()).map_err(Error::Io));
+        }
+        fs::rename(&temp, &path).map_err(Error::Io)
+    }
+
+    /// Forget about table `id`.
+    pub fn remove(&self, id: usize) -> Result<(), Error> {
+        let path = self.path(id);
+        if path.exists() {
+            try!(fs::unlink(&path).map_err(Error::Io));
+        }
+        Ok(())
+    }
+
+    /// Every table that's been saved, in order.
+    pub fn load(&self) -> Result<Vec<SavedTable>, Error> {
+        let mut paths = try!(fs::readdir(&self._dir).map_err(Error::Io));
+        paths.sort();
+        let mut tables = vec![];
+        for path in paths.iter() {
+            let is_table = path.filename_str().map_or(
+                false, |name| name.starts_with("table-") && name.ends_with(".json"));
+            if !is_table {
+                continue;
+            }
+            let text = try!(File::open(path).read_to_string().map_err(Error::Io));
+            let saved = json::from_str(text.as_slice())
+                .map_err(|_| ProtocolError::BadJson)
+                .and_then(|json| decode(&json));
+            match saved {
+                Ok(saved) => tables.push(saved),
+                Err(e) => return Err(Error::Malformed(path.clone(), e)),
+            }
+        }
+        tables.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(tables)
+    }
+}
+
+
+/// Turn a table into JSON, as it's kept on disk.
+pub fn encode(id: usize, snapshot: &TableSnapshot, sessions: &[(String, usize)]) -> Json {
+    let players = seat_players(snapshot.names.len());
+    let mut obj = BTreeMap::new();
+    obj.insert("table".to_string(), id.to_json());
+    obj.insert("variant".to_string(), format!("{}", snapshot.variant).to_json());
+    obj.insert("names".to_string(), snapshot.names.to_json());
+    obj.insert("bots".to_string(), snapshot.bots.to_json());
+    let agents = snapshot.agents.iter().map(|agent| match *agent {
+        Some(kind) => format!("{}", kind).to_json(),
+        None => Json::Null,
+    }).collect();
+    obj.insert("agents".to_string(), Json::Array(agents));
+    obj.insert("scores".to_string(), snapshot.scores.to_json());
+    obj.insert("num_rounds".to_string(), snapshot.num_rounds.to_json());
+    let round = match snapshot.round {
+        Some(ref round) => round_to_json(players.as_slice(), round),
+        None => Json::Null,
+    };
+    obj.insert("round".to_string(), round);
+    let log = snapshot.log.iter().filter_map(|entry| {
+        let (seat, message) = match *entry {
+            Outgoing::To(seat, ref message) => (Some(seat), message),
+            Outgoing::All(ref message) => (None, message),
+            // Spectators' messages are never logged.
+            _ => return None,
+        };
+        let mut obj = BTreeMap::new();
+        obj.insert("seat".to_string(), seat.to_json());
+        match *message {
+            ServerMessage::Turn(ref outcome) =>
+                obj.insert("turn".to_string(), notation::format_outcome(players.as_slice(), outcome).to_json()),
+            _ => obj.insert("message".to_string(), protocol::server_message_to_json(players.as_slice(), message)),
+        };
+        Some(Json::Object(obj))
+    }).collect();
+    obj.insert("log".to_string(), Json::Array(log));
+    let mut tokens = BTreeMap::new();
+    for &(ref token, seat) in sessions.iter() {
+        tokens.insert(token.clone(), seat.to_json());
+    }
+    obj.insert("sessions".to_string(), Json::Object(tokens));
+    Json::Object(obj)
+}
+
+
+/// Read a table from JSON written by `encode`.
+pub fn decode(json: &Json) -> Result<SavedTable, ProtocolError> {
+    let id = try!(get_usize(json, "table"));
+    let variant = try!(get_string(json, "variant"));
+    let variant = match str::FromStr::from_str(variant.as_slice()) {
+        Some(variant) => variant,
+        None => return Err(ProtocolError::BadMessage(format!("Unknown variant: {}", variant))),
+    };
+    let names: Vec<Option<String>> = try!(get_array(json, "names"))
+        .iter()
+        .map(|n| n.as_string().map(|s| s.to_string()))
+        .collect();
+    let players = seat_players(names.len());
+    let mut bots = vec![];
+    for bot in try!(get_array(json, "bots")).iter() {
+        bots.push(try!(bot.as_boolean().ok_or(bad("bots must be booleans"))));
+    }
+    let mut agents = vec![];
+    match json.find("agents") {
+        None => agents.extend(names.iter().map(|_| None)),
+        Some(..) => for agent in try!(get_array(json, "agents")).iter() {
+            agents.push(match *agent {
+                Json::Null => None,
+                Json::String(ref name) => match str::FromStr::from_str(name.as_slice()) {
+                    Some(kind) => Some(kind),
+                    None => return Err(ProtocolError::BadMessage(format!("Unknown agent: {}", name))),
+                },
+                _ => return Err(bad("agents must be strings or null")),
+            });
+        },
+    }
+    let mut scores = vec![];
+    for score in try!(get_array(json, "scores")).iter() {
+        scores.push(try!(score.as_u64().ok_or(bad("scores must be numbers"))) as u32);
+    }
+    let round = match try!(get(json, "round")) {
+        &Json::Null => None,
+        round => Some(try!(round_from_json(players.as_slice(), round))),
+    };
+    let mut log = vec![];
+    for entry in try!(get_array(json, "log")).iter() {
+        let message = match entry.find("turn") {
+            Some(..) => ServerMessage::Turn(try!(
+                notation::parse_outcome(players.as_slice(), try!(get_string(entry, "turn")).as_slice())
+                    .map_err(|e| ProtocolError::BadMessage(format!("Bad turn: {:?}", e))))),
+            None => try!(protocol::server_message_from_json(players.as_slice(), try!(get(entry, "message")))),
+        };
+        match try!(get(entry, "seat")) {
+            &Json::Null => log.push(Outgoing::All(message)),
+            seat => log.push(Outgoing::To(try!(seat.as_u64().ok_or(bad("seat must be a number"))) as usize,
+                                          message)),
+        }
+    }
+    let mut sessions = vec![];
+    match try!(get(json, "sessions")).as_object() {
+        Some(tokens) => for (token, seat) in tokens.iter() {
+            sessions.push((token.clone(), try!(seat.as_u64().ok_or(bad("seat must be a number"))) as usize));
+        },
+        None => return Err(bad("sessions must be an object")),
+    }
+    let snapshot = TableSnapshot {
+        variant: variant,
+        names: names,
+        bots: bots,
+        agents: agents,
+        scores: scores,
+        num_rounds: try!(get_usize(json, "num_rounds")),
+        round: round,
+        log: log,
+    };
+    match Table::from_snapshot(&snapshot) {
+        Some(table) => Ok(SavedTable { id: id, table: table, sessions: sessions }),
+        None => Err(bad("Not a table that can be played")),
+    }
+}
+
+
+/// The players at a table with `num_players` seats, by seat. These are the
+/// same for every table of that size.
+fn seat_players(num_players: usize) -> Vec<PlayerId> {
+    game::new_game(num_players).map(|game| game.players()).unwrap_or(vec![])
+}
+
+
+fn round_to_json(players: &[PlayerId], round: &RoundSnapshot) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("deck".to_string(), protocol::cards_to_json(round.deck.as_slice()));
+    obj.insert(
+        "hands".to_string(),
+        Json::Array(round.players.iter().map(|&(_, hand, _, _)| match hand {
+            Some(card) => protocol::card_to_json(card),
+            None => Json::Null,
+        }).collect()));
+    obj.insert(
+        "protected".to_string(),
+        round.players.iter().map(|&(_, _, protected, _)| protected).collect::<Vec<bool>>().to_json());
+    obj.insert(
+        "discards".to_string(),
+        Json::Array(round.players.iter().map(|&(_, _, _, ref d)| protocol::cards_to_json(d.as_slice())).collect()));
+    let last_player = round.last_player.and_then(|p| players.iter().position(|&q| q == p));
+    obj.insert("last_player".to_string(), last_player.to_json());
+    Json::Object(obj)
+}
+
+
+fn round_from_json(players: &[PlayerId], json: &Json) -> Result<RoundSnapshot, ProtocolError> {
+    let hands = try!(get_array(json, "hands"));
+    let protected = try!(get_array(json, "protected"));
+    let discards = try!(get_array(json, "discards"));
+    if hands.len() != players.len() || protected.len() != players.len() || discards.len() != players.len() {
+        return Err(bad("Round doesn't have one entry per seat"));
+    }
+    let mut round_players = vec![];
+    for (i, &id) in players.iter().enumerate() {
+        let hand = match hands[i] {
+            Json::Null => None,
+            ref card => Some(try!(protocol::card_from_json(card))),
+        };
+        round_players.push((
+            id,
+            hand,
+            try!(protected[i].as_boolean().ok_or(bad("protected must be booleans"))),
+            try!(protocol::cards_from_json(&discards[i]))));
+    }
+    let last_player = match try!(get(json, "last_player")) {
+        &Json::Null => None,
+        seat => Some(try!(protocol::player_from_json(players, seat))),
+    };
+    Ok(RoundSnapshot {
+        deck: try!(protocol::cards_from_json(try!(get(json, "deck")))),
+        players: round_players,
+        last_player: last_player,
+    })
+}
+
+
+fn bad(message: &str) -> ProtocolError {
+    ProtocolError::BadMessage(message.to_string())
+}
+
+
+fn get<'a>(json: &'a Json, key: &str) -> Result<&'a Json, ProtocolError> {
+    json.find(key).ok_or(ProtocolError::BadMessage(format!("Missing field: {}", key)))
+}
+
+
+fn get_string(json: &Json, key: &str) -> Result<String, ProtocolError> {
+    match try!(get(json, key)).as_string() {
+        Some(s) => Ok(s.to_string()),
+        None => Err(ProtocolError::BadMessage(format!("{} must be a string", key))),
+    }
+}
+
+
+fn get_usize(json: &Json, key: &str) -> Result<usize, ProtocolError> {
+    match try!(get(json, key)).as_u64() {
+        Some(n) => Ok(n as usize),
+        None => Err(ProtocolError::BadMessage(format!("{} must be a number", key))),
+    }
+}
+
+
+fn get_array<'a>(json: &'a Json, key: &str) -> Result<&'a Vec<Json>, ProtocolError> {
+    match try!(get(json, key)).as_array() {
+        Some(array) => Ok(array),
+        None => Err(ProtocolError::BadMessage(format!("{} must be a list", key))),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use serialize::json;
+
+    use action::{Event, Play};
+    use agent::AgentKind;
+    use deck::Card;
+    use notation;
+    use protocol::ServerMessage;
+    use round::TurnOutcome;
+    use table::{Outgoing, Table};
+    use super::{decode, encode};
+
+    #[test]
+    fn test_round_trip() {
+        let mut table = Table::new(3).unwrap();
+        table.join("alice").unwrap();
+        table.add_bot(AgentKind::Random).unwrap();
+        table.join("carol").unwrap();
+        table.chat(2, "hmm");
+        let sessions = vec![("abc".to_string(), 0), ("def".to_string(), 2)];
+        let json = encode(4, &table.snapshot(), sessions.as_slice());
+        // It survives being written out as text, too.
+        let json = json::from_str(json.to_string().as_slice()).unwrap();
+        let saved = decode(&json).unwrap();
+        assert_eq!(4, saved.id);
+        assert_eq!(sessions, saved.sessions);
+        assert_eq!(table.snapshot(), saved.table.snapshot());
+    }
+
+    #[test]
+    fn test_turns_logged_in_notation() {
+        let mut table = Table::new(2).unwrap();
+        table.join("alice").unwrap();
+        table.join("bob").unwrap();
+        let players = table.players();
+        let outcome = TurnOutcome::Played(
+            players[0], Card::Knight, Play::Attack(players[1]), vec![Event::PlayerEliminated(players[1])]);
+        let mut snapshot = table.snapshot();
+        snapshot.log.push(Outgoing::All(ServerMessage::Turn(outcome.clone())));
+        let json = encode(0, &snapshot, &[]);
+        let log = json.find("log").unwrap().as_array().unwrap();
+        let turn = log[log.len() - 1].find("turn").and_then(|t| t.as_string());
+        assert_eq!(Some(notation::format_outcome(players.as_slice(), &outcome).as_slice()), turn);
+        assert_eq!(snapshot.log, decode(&json).unwrap().table.snapshot().log);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_round() {
+        let table = Table::new(2).unwrap();
+        let mut json = encode(0, &table.snapshot(), &[]);
+        match json {
+            json::Json::Object(ref mut obj) => {
+                obj.insert("round".to_string(), json::from_str(r#"{"deck": []}"#).unwrap());
+            },
+            _ => panic!("Not an object"),
+        }
+        assert!(decode(&json).is_err());
+    }
+}
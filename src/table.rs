@@ -0,0 +1,1168 @@
+/// A game of Love Letter played by remote players.
+///
+/// A `Table` knows nothing about networks. It takes joins and plays from
+/// players, identified by seat, and says which messages should go to whom.
+/// Players only ever get sent their own view of the round.
+///
+/// If a player's connection drops, their seat is kept for them for a grace
+/// period. If they're not back by then, a bot plays for them until they
+/// return. Time is measured in seconds, by whoever is running the table.
+///
+/// Tables can also have a time limit for each turn. Players are warned when
+/// it's nearly up, and if it runs out, a bot makes their play for them.
+///
+/// Seats can also be given to bots from the start, so a game doesn't have to
+/// wait for enough people to turn up. Bots can take their time over each
+/// play, so the game doesn't rush past. Anyone who joins later takes over
+/// from a bot, but only once the round is over, so the bot gets to finish
+/// what it started.
+///
+/// Anyone can watch. Spectators see what everyone at the table sees, and
+/// nothing more, as it happens. Omniscient spectators also see everyone's
+/// hands, but only after a delay, or once the round is over, so they can't
+/// pass on what they see to the players.
+///
+/// Players can also chat, or send emotes, to everyone at the table. What
+/// they say goes in the round's log with everything else.
+///
+/// A table can be taken apart into a `TableSnapshot`, and put back together
+/// again, so that games can outlive the server that's running them.
+
+use std::cell::RefCell;
+use std::mem;
+
+use action::{Play, PlayError};
+use agent::AgentKind;
+use chat;
+use chat::{Emote, RateLimit};
+use deck::Card;
+use game;
+use game::{Game, Variant};
+use player_id::PlayerId;
+use protocol::{SeatPlay, ServerMessage};
+use roster::Roster;
+use round::{Round, RoundSnapshot};
+use view::SpectatorView;
+
+
+/// How long to wait for a disconnected player before a bot takes over.
+pub const DEFAULT_GRACE_PERIOD: u64 = 60;
+
+/// How many seconds before the end of a turn to warn the player.
+pub const TURN_WARNING: u64 = 10;
+
+
+#[derive(Show, PartialEq, Clone)]
+/// A message to send, and who to send it to.
+pub enum Outgoing {
+    /// Send to the player in the given seat.
+    To(usize, ServerMessage),
+    /// Send to everyone at the table, including spectators.
+    All(ServerMessage),
+    /// Send to everyone watching the table.
+    Spectators(ServerMessage),
+    /// Send to spectators who can see everyone's hands.
+    Omniscient(ServerMessage),
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+pub enum Error {
+    /// Every seat is taken.
+    TableFull,
+    /// There's no such seat, or nobody is sitting in it.
+    EmptySeat(usize),
+}
+
+
+#[derive(Show, PartialEq, Clone)]
+/// What's worth keeping about a table between turns.
+pub struct TableSnapshot {
+    pub variant: Variant,
+    /// The name of the player in each seat, or `None` if it's free.
+    pub names: Vec<Option<String>>,
+    /// Whether a bot is playing for each seat.
+    pub bots: Vec<bool>,
+    /// The kind of bot each seat was given to, if it was given to one.
+    pub agents: Vec<Option<AgentKind>>,
+    /// Everyone's tokens, by seat.
+    pub scores: Vec<u32>,
+    /// How many rounds have been started.
+    pub num_rounds: usize,
+    /// The round being played, if there is one.
+    pub round: Option<RoundSnapshot>,
+    /// Everything that's happened this round.
+    pub log: Vec<Outgoing>,
+}
+
+
+#[derive(Show, Clone)]
+struct Seat {
+    name: Option<String>,
+    /// When the player lost their connection, if they have.
+    disconnected_at: Option<u64>,
+    /// A bot is playing for them.
+    bot: bool,
+    /// The seat was given to a bot of this kind, which plays until the end
+    /// of the round in which someone takes it over.
+    agent: Option<AgentKind>,
+}
+
+
+impl Seat {
+    fn empty() -> Seat {
+        Seat { name: None, disconnected_at: None, bot: false, agent: None }
+    }
+}
+
+
+#[derive(Clone)]
+pub struct Table {
+    _seats: Vec<Seat>,
+    _variant: Variant,
+    _game: Game,
+    /// The round being played, as of the end of the last turn.
+    _round: Option<Round>,
+    /// How many rounds have been started.
+    _num_rounds: usize,
+    /// Everything that's happened this round, apart from views, so players
+    /// who come back can catch up.
+    _log: Vec<Outgoing>,
+    _grace_period: u64,
+    /// How long each player has to make their play, if there's a limit.
+    _turn_limit: Option<u64>,
+    /// The last time we were told.
+    _now: u64,
+    /// When the current turn runs out.
+    _deadline: Option<u64>,
+    /// The player whose turn it is has been told time's nearly up.
+    _warned: bool,
+    /// How long omniscient spectators wait to see hands, or `None` to wait
+    /// until the end of the round.
+    _omniscient_delay: Option<u64>,
+    /// Messages for omniscient spectators, and when to send them. Those
+    /// without a time are sent at the end of the round.
+    _delayed: Vec<(Option<u64>, ServerMessage)>,
+    /// How much each seat has said recently.
+    _chat_limits: Vec<RateLimit>,
+    /// How long bots take to make each play.
+    _think_delay: u64,
+    /// When the bot whose turn it is will make its play.
+    _bot_due: Option<u64>,
+}
+
+
+impl Table {
+    /// An empty table with seats for `num_players`, playing a quick game.
+    /// Returns `None` if Love Letter can't be played by that many.
+    pub fn new(num_players: usize) -> Option<Table> {
+        Table::with_variant(num_players, Variant::Quick)
+    }
+
+    /// An empty table with seats for `num_players`, playing to the rules of
+    /// `variant`.
+    pub fn with_variant(num_players: usize, variant: Variant) -> Option<Table> {
+        game::new_game_with_variant(num_players, variant).map(|game| Table {
+            _seats: range(0, num_players).map(|_| Seat::empty()).collect(),
+            _variant: variant,
+            _game: game,
+            _round: None,
+            _num_rounds: 0,
+            _log: vec![],
+            _grace_period: DEFAULT_GRACE_PERIOD,
+            _turn_limit: None,
+            _now: 0,
+            _deadline: None,
+            _warned: false,
+            _omniscient_delay: None,
+            _delayed: vec![],
+            _chat_limits: range(0, num_players).map(|_| RateLimit::new()).collect(),
+            _think_delay: 0,
+            _bot_due: None,
+        })
+    }
+
+    /// Put a table back together from a snapshot. Everyone who was sitting
+    /// at it is treated as having just lost their connection. Returns `None`
+    /// if the snapshot doesn't make sense.
+    pub fn from_snapshot(snapshot: &TableSnapshot) -> Option<Table> {
+        let num_players = snapshot.names.len();
+        let mut table = match Table::with_variant(num_players, snapshot.variant) {
+            Some(table) => table,
+            None => return None,
+        };
+        if snapshot.bots.len() != num_players || snapshot.agents.len() != num_players {
+            return None;
+        }
+        table._game = match table._game.with_scores(snapshot.scores.as_slice()) {
+            Some(game) => game,
+            None => return None,
+        };
+        table._round = match snapshot.round {
+            Some(ref round) => match Round::from_snapshot(round) {
+                Ok(ref round) if round.player_ids() == table.players() => Some(round.clone()),
+                _ => return None,
+            },
+            None => None,
+        };
+        for seat in range(0, num_players) {
+            let name = snapshot.names[seat].clone();
+            let disconnected_at = if name.is_some() { Some(0) } else { None };
+            table._seats[seat] = Seat {
+                name: name,
+                disconnected_at: disconnected_at,
+                bot: snapshot.bots[seat],
+                agent: snapshot.agents[seat],
+            };
+        }
+        table._num_rounds = snapshot.num_rounds;
+        table._log = snapshot.log.clone();
+        table.update_roster();
+        table.start_turn_clock();
+        Some(table)
+    }
+
+    /// Everything needed to put this table back together with
+    /// `from_snapshot`.
+    pub fn snapshot(&self) -> TableSnapshot {
+        TableSnapshot {
+            variant: self._variant,
+            names: self._seats.iter().map(|s| s.name.clone()).collect(),
+            bots: self._seats.iter().map(|s| s.bot).collect(),
+            agents: self._seats.iter().map(|s| s.agent).collect(),
+            scores: self._game.scores(),
+            num_rounds: self._num_rounds,
+            round: self._round.as_ref().map(|round| round.snapshot().expect("Stopped in the middle of a turn")),
+            log: self._log.clone(),
+        }
+    }
+
+    /// How many seconds to wait for a disconnected player before a bot
+    /// takes over.
+    pub fn set_grace_period(&mut self, seconds: u64) {
+        self._grace_period = seconds;
+    }
+
+    /// How many seconds each player gets to make their play, or `None` to
+    /// let them take as long as they like. Applies from the next turn, or
+    /// from now if the turn in progress has no limit yet, as when a table
+    /// has just been restored.
+    pub fn set_turn_limit(&mut self, seconds: Option<u64>) {
+        self._turn_limit = seconds;
+        if self._deadline.is_none() {
+            self.start_turn_clock();
+        }
+    }
+
+    /// How many seconds omniscient spectators wait to see what's happened,
+    /// or `None` to make them wait until the end of the round.
+    pub fn set_omniscient_delay(&mut self, seconds: Option<u64>) {
+        self._omniscient_delay = seconds;
+    }
+
+    /// How many seconds bots spend thinking before each play.
+    pub fn set_think_delay(&mut self, seconds: u64) {
+        self._think_delay = seconds;
+    }
+
+    pub fn num_players(&self) -> usize {
+        self._seats.len()
+    }
+
+    /// The players at the table, by seat.
+    pub fn players(&self) -> Vec<PlayerId> {
+        self._game.players()
+    }
+
+    /// The name of the player in each seat, or `None` if it's free. Seats
+    /// given to bots are named after the kind of bot.
+    pub fn names(&self) -> Vec<Option<String>> {
+        self._seats
+            .iter()
+            .map(|s| s.name.clone().or_else(|| s.agent.map(|kind| format!("{} bot", kind))))
+            .collect()
+    }
+
+    /// What to call everyone at the table. Players are named after
+    /// whoever's sitting in their seat.
+    pub fn roster(&self) -> &Roster {
+        self._game.roster()
+    }
+
+    /// Give the game's players the names of whoever's sitting in their seats.
+    fn update_roster(&mut self) {
+        let mut roster = self._game.roster().clone();
+        for (&player, name) in self.players().iter().zip(self.names().into_iter()) {
+            roster.set_name(player, name);
+        }
+        self._game = self._game.with_roster(roster).expect("Roster has the wrong players");
+    }
+
+    pub fn variant(&self) -> Variant {
+        self._variant
+    }
+
+    /// Tokens needed to win.
+    pub fn winning_score(&self) -> u32 {
+        self._game.winning_score()
+    }
+
+    /// Has the first round been dealt?
+    pub fn is_started(&self) -> bool {
+        self._num_rounds > 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self._seats.iter().all(|s| s.name.is_some() || s.agent.is_some())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self._game.is_over()
+    }
+
+    /// Is a bot playing for the player in `seat`?
+    pub fn is_bot(&self, seat: usize) -> bool {
+        self._seats.get(seat).map_or(false, |s| s.bot)
+    }
+
+    /// Sit `name` in the first free seat. Once every seat is taken, the
+    /// first round is dealt.
+    ///
+    /// If a game is under way, the new player takes over from the bot that
+    /// was playing for whoever left. If there's no such seat, they take over
+    /// a seat that was given to a bot, from the start of the next round.
+    pub fn join(&mut self, name: &str) -> Result<(usize, Vec<Outgoing>), Error> {
+        let free = self._seats.iter().position(|s| s.name.is_none() && s.agent.is_none());
+        let seat = match free.or_else(|| self._seats.iter().position(|s| s.name.is_none())) {
+            Some(seat) => seat,
+            None => return Err(Error::TableFull),
+        };
+        let agent = self._seats[seat].agent;
+        self._seats[seat] = if agent.is_some() && self._round.is_some() {
+            Seat { name: Some(name.to_string()), disconnected_at: None, bot: true, agent: agent }
+        } else {
+            Seat { name: Some(name.to_string()), disconnected_at: None, bot: false, agent: None }
+        };
+        self.update_roster();
+        let mut messages = vec![
+            Outgoing::To(seat, ServerMessage::Welcome(seat, self.num_players())),
+            Outgoing::All(ServerMessage::Seated(self.names())),
+            ];
+        if self.is_full() && self._round.is_none() && !self.is_over() {
+            messages.push_all(self.start_round().as_slice());
+        } else if self._round.is_some() {
+            messages.push_all(self.catch_up(seat).as_slice());
+        }
+        Ok((seat, messages))
+    }
+
+    /// Free up `seat`. If a game is under way, a bot plays for them until
+    /// someone else sits down. If they were waiting to take over from a bot,
+    /// it carries on.
+    pub fn leave(&mut self, seat: usize) -> Result<Vec<Outgoing>, Error> {
+        try!(self.check_seated(seat));
+        let in_game = self.is_started() && !self.is_over();
+        let was_bot = self.is_bot(seat);
+        let agent = self._seats[seat].agent;
+        self._seats[seat] = Seat { name: None, disconnected_at: None, bot: in_game || agent.is_some(), agent: agent };
+        self.update_roster();
+        let mut messages = vec![Outgoing::All(ServerMessage::Seated(self.names()))];
+        if in_game && !was_bot {
+            let player = self.players()[seat];
+            messages.push(Outgoing::All(ServerMessage::BotTakeover(player)));
+            messages.push_all(self.play_bots().as_slice());
+        }
+        Ok(messages)
+    }
+
+    /// The player in `seat` has lost their connection, at time `now`.
+    pub fn disconnect(&mut self, seat: usize, now: u64) -> Result<(), Error> {
+        try!(self.check_seated(seat));
+        self._seats[seat].disconnected_at = Some(now);
+        Ok(())
+    }
+
+    /// The player in `seat` is back. Tells them everything that's happened
+    /// this round, and takes their seat back from the bot if need be. If
+    /// they were waiting to take over a seat given to a bot, they still
+    /// have to wait for the next round.
+    pub fn reconnect(&mut self, seat: usize) -> Result<Vec<Outgoing>, Error> {
+        try!(self.check_seated(seat));
+        self._seats[seat].disconnected_at = None;
+        let taking_over = self._seats[seat].agent.is_some() && self._round.is_some();
+        if self.is_bot(seat) && !taking_over {
+            self._seats[seat].bot = false;
+            if self.waiting_for() == Some(seat) {
+                // The bot might have been about to play.
+                self._bot_due = None;
+                self.start_turn_clock();
+            }
+        }
+        let mut messages = vec![
+            Outgoing::To(seat, ServerMessage::Welcome(seat, self.num_players())),
+            Outgoing::To(seat, ServerMessage::Seated(self.names())),
+            ];
+        messages.push_all(self.catch_up(seat).as_slice());
+        Ok(messages)
+    }
+
+    /// Time has passed. Hand the seats of anyone who's been gone longer
+    /// than the grace period to a bot, let any bot that's done thinking
+    /// play, and hurry along whoever's turn it is.
+    pub fn tick(&mut self, now: u64) -> Vec<Outgoing> {
+        self._now = now;
+        let mut messages = self.release_omniscient(false);
+        let players = self.players();
+        for seat in range(0, self.num_players()) {
+            let expired = match self._seats[seat].disconnected_at {
+                Some(at) => !self._seats[seat].bot && now >= at + self._grace_period,
+                None => false,
+            };
+            if expired && self.is_started() && !self.is_over() {
+                self._seats[seat].bot = true;
+                messages.push(Outgoing::All(ServerMessage::BotTakeover(players[seat])));
+            }
+        }
+        messages.push_all(self.play_bots().as_slice());
+        messages.push_all(self.check_turn_limit().as_slice());
+        messages
+    }
+
+    /// Give whoever's turn it is until the turn limit to play, if it's a
+    /// person's turn and there is a limit.
+    fn start_turn_clock(&mut self) {
+        let now = self._now;
+        self._deadline = match self.waiting_for() {
+            Some(seat) if !self.is_bot(seat) => self._turn_limit.map(|limit| now + limit),
+            _ => None,
+        };
+        self._warned = false;
+    }
+
+    /// Warn the player whose turn it is if they're running out of time, and
+    /// have a bot play for them if they've run out.
+    fn check_turn_limit(&mut self) -> Vec<Outgoing> {
+        let (seat, deadline) = match (self.waiting_for(), self._deadline) {
+            (Some(seat), Some(deadline)) if !self.is_bot(seat) => (seat, deadline),
+            _ => return vec![],
+        };
+        let now = self._now;
+        if now >= deadline {
+            let player = self.players()[seat];
+            let mut messages = vec![self.log(Outgoing::All(ServerMessage::TimedOut(player)))];
+            let (card, play) = self.bot_play(seat).expect("Waiting for a player with no turn");
+            let played = self.play_turn(seat, card, play).ok().expect("Bot made an invalid play");
+            messages.push_all(played.as_slice());
+            messages
+        } else if !self._warned && deadline - now <= TURN_WARNING {
+            self._warned = true;
+            vec![Outgoing::To(seat, ServerMessage::TurnWarning(deadline - now))]
+        } else {
+            vec![]
+        }
+    }
+
+    fn check_seated(&self, seat: usize) -> Result<(), Error> {
+        match self._seats.get(seat) {
+            Some(&Seat { name: Some(..), .. }) => Ok(()),
+            _ => Err(Error::EmptySeat(seat)),
+        }
+    }
+
+    /// What the player in `seat` needs to know to carry on with the round:
+    /// what's happened so far, what they can see, and whether it's their
+    /// turn.
+    fn catch_up(&self, seat: usize) -> Vec<Outgoing> {
+        let mut messages: Vec<Outgoing> = self._log
+            .iter()
+            .filter_map(|message| match *message {
+                Outgoing::All(ref m) => Some(Outgoing::To(seat, m.clone())),
+                Outgoing::To(s, ref m) if s == seat => Some(Outgoing::To(seat, m.clone())),
+                _ => None,
+            })
+            .collect();
+        let round = match self._round {
+            Some(ref round) => round,
+            None => return messages,
+        };
+        let player = self.players()[seat];
+        let message = match round.peek_turn() {
+            Some((ready, turn)) => if turn.player == player && !self.is_bot(seat) {
+                ServerMessage::YourTurn(ready.view(player).ok().expect("Player not in round"))
+            } else {
+                ServerMessage::View(round.view(player).ok().expect("Player not in round"))
+            },
+            None => ServerMessage::View(round.view(player).ok().expect("Player not in round")),
+        };
+        messages.push(Outgoing::To(seat, message));
+        messages
+    }
+
+    /// The player in `seat` wants to say `text` to the table.
+    pub fn chat(&mut self, seat: usize, text: &str) -> Vec<Outgoing> {
+        match chat::clean_message(text) {
+            Some(text) => self.speak(seat, |player| ServerMessage::Chat(player, text.clone())),
+            None => vec![Outgoing::To(seat, ServerMessage::Error("Nothing to say".to_string()))],
+        }
+    }
+
+    /// The player in `seat` is pulling a face.
+    pub fn emote(&mut self, seat: usize, emote: Emote) -> Vec<Outgoing> {
+        self.speak(seat, |player| ServerMessage::Emote(player, emote))
+    }
+
+    /// Pass on what the player in `seat` has to say, unless they've been
+    /// talking too much.
+    fn speak<F: Fn(PlayerId) -> ServerMessage>(&mut self, seat: usize, message: F) -> Vec<Outgoing> {
+        if self.check_seated(seat).is_err() {
+            return vec![Outgoing::To(seat, ServerMessage::Error("Not seated".to_string()))];
+        }
+        let now = self._now;
+        if !self._chat_limits[seat].allow(now) {
+            return vec![Outgoing::To(seat, ServerMessage::Error("Slow down".to_string()))];
+        }
+        let player = self.players()[seat];
+        vec![self.log(Outgoing::All(message(player)))]
+    }
+
+    /// What someone who's just started watching needs to know: who's
+    /// playing, what's happened so far this round, and what they can see.
+    pub fn spectate(&self) -> Vec<ServerMessage> {
+        let mut messages = vec![ServerMessage::Seated(self.names())];
+        for message in self._log.iter() {
+            match *message {
+                Outgoing::All(ref m) => messages.push(m.clone()),
+                _ => (),
+            }
+        }
+        match self._round {
+            Some(ref round) => messages.push(ServerMessage::SpectatorView(round.spectator_view(false))),
+            None => (),
+        }
+        messages
+    }
+
+    /// Show `view` to omniscient spectators, once they're allowed to see it.
+    fn show_omniscient(&mut self, view: SpectatorView) -> Vec<Outgoing> {
+        let message = ServerMessage::SpectatorView(view);
+        match self._omniscient_delay {
+            Some(0) => return vec![Outgoing::Omniscient(message)],
+            Some(delay) => {
+                let at = self._now + delay;
+                self._delayed.push((Some(at), message));
+            },
+            None => self._delayed.push((None, message)),
+        }
+        vec![]
+    }
+
+    /// Send omniscient spectators everything they've waited long enough
+    /// for, or everything, if the round is over.
+    fn release_omniscient(&mut self, round_over: bool) -> Vec<Outgoing> {
+        let now = self._now;
+        let delayed = mem::replace(&mut self._delayed, vec![]);
+        let (due, later): (Vec<(Option<u64>, ServerMessage)>, Vec<(Option<u64>, ServerMessage)>) =
+            delayed.into_iter().partition(|&(at, _)| round_over || at.map_or(false, |at| at <= now));
+        self._delayed = later;
+        due.into_iter().map(|(_, message)| Outgoing::Omniscient(message)).collect()
+    }
+
+    /// Remember `message` for players who come back later, and return it.
+    fn log(&mut self, message: Outgoing) -> Outgoing {
+        self._log.push(message.clone());
+        message
+    }
+
+    /// The seat of the player whose turn it is, if we're waiting on anyone.
+    pub fn waiting_for(&self) -> Option<usize> {
+        let players = self.players();
+        self._round
+            .as_ref()
+            .and_then(|round| round.peek_turn())
+            .and_then(|(_, turn)| players.iter().position(|&p| p == turn.player))
+    }
+
+    /// The player in `seat` wants to play `card`.
+    pub fn submit(&mut self, seat: usize, card: Card, play: SeatPlay) -> Vec<Outgoing> {
+        if self.waiting_for() != Some(seat) || self.is_bot(seat) {
+            return vec![Outgoing::To(seat, ServerMessage::Error("It's not your turn".to_string()))];
+        }
+        let play = match play.to_play(self.players().as_slice()) {
+            Some(play) => play,
+            None => return vec![Outgoing::To(seat, ServerMessage::Error("No such seat".to_string()))],
+        };
+        match self.play_turn(seat, card, play) {
+            Ok(messages) => messages,
+            Err(e) => vec![Outgoing::To(seat, ServerMessage::InvalidPlay(e))],
+        }
+    }
+
+    /// Play `card` for the player in `seat`, whose turn it is, and carry on
+    /// with the game.
+    fn play_turn(&mut self, seat: usize, card: Card, play: Play) -> Result<Vec<Outgoing>, PlayError> {
+        let round = self._round.clone().expect("Waiting for a player with no round");
+        let revealed = RefCell::new(None);
+        let result = round.handle_turn(
+            |_, _| (card, play), |player, card| *revealed.borrow_mut() = Some((player, card)));
+        match try!(result) {
+            None => panic!("Waiting for a player in a finished round"),
+            Some((new_round, outcome)) => {
+                let mut messages = vec![self.log(Outgoing::All(ServerMessage::Turn(outcome)))];
+                match revealed.into_inner() {
+                    Some((player, card)) =>
+                        messages.push(self.log(Outgoing::To(seat, ServerMessage::Reveal(player, card)))),
+                    None => (),
+                }
+                self._round = Some(new_round);
+                messages.push_all(self.advance().as_slice());
+                Ok(messages)
+            },
+        }
+    }
+
+    /// Sit a bot of the given kind in the first free seat. Once every seat
+    /// is taken, the first round is dealt.
+    pub fn add_bot(&mut self, kind: AgentKind) -> Result<(usize, Vec<Outgoing>), Error> {
+        let seat = match self._seats.iter().position(|s| s.name.is_none() && s.agent.is_none()) {
+            Some(seat) => seat,
+            None => return Err(Error::TableFull),
+        };
+        self._seats[seat] = Seat { name: None, disconnected_at: None, bot: true, agent: Some(kind) };
+        self.update_roster();
+        let mut messages = vec![Outgoing::All(ServerMessage::Seated(self.names()))];
+        if self.is_full() && self._round.is_none() && !self.is_over() {
+            messages.push_all(self.start_round().as_slice());
+        } else {
+            messages.push_all(self.play_bots().as_slice());
+        }
+        Ok((seat, messages))
+    }
+
+    /// What a bot would play in `seat`, if it's their turn. Seats that
+    /// weren't given to a particular kind of bot get a heuristic one.
+    fn bot_play(&self, seat: usize) -> Option<(Card, Play)> {
+        let player = self.players()[seat];
+        let agent = self._seats[seat].agent.unwrap_or(AgentKind::Heuristic).agent();
+        self._round
+            .as_ref()
+            .and_then(|round| round.peek_turn())
+            .map(|(ready, _)| agent.choose(&ready.view(player).ok().expect("Player not in round")))
+    }
+
+    /// If it's a bot's turn, and it's done thinking, play for it, until it's
+    /// a person's turn or the game is over.
+    fn play_bots(&mut self) -> Vec<Outgoing> {
+        let seat = match self.waiting_for() {
+            Some(seat) if self.is_bot(seat) => seat,
+            _ => return vec![],
+        };
+        if self._think_delay > 0 {
+            let now = self._now;
+            match self._bot_due {
+                Some(due) if due <= now => (),
+                Some(..) => return vec![],
+                None => {
+                    self._bot_due = Some(now + self._think_delay);
+                    return vec![];
+                },
+            }
+        }
+        let (card, play) = match self.bot_play(seat) {
+            Some(choice) => choice,
+            None => return vec![],
+        };
+        // The bot's turn ends with `advance`, which will call us again if
+        // the next player is a bot too.
+        self.play_turn(seat, card, play).ok().expect("Bot made an invalid play")
+    }
+
+    fn start_round(&mut self) -> Vec<Outgoing> {
+        for seat in self._seats.iter_mut() {
+            if seat.name.is_some() && seat.agent.is_some() {
+                seat.agent = None;
+                seat.bot = false;
+            }
+        }
+        let round = self._game.new_round();
+        self._log = vec![];
+        let started = Outgoing::All(ServerMessage::RoundStarted(self._num_rounds));
+        let mut messages = vec![self.log(started)];
+        self._num_rounds += 1;
+        self._round = Some(round);
+        messages.push_all(self.advance().as_slice());
+        messages
+    }
+
+    /// Send everyone their view, then either ask the next player to play or
+    /// finish the round. Players who bust out with the Minister don't get a
+    /// say, so their turns are played straight away, as are bots' turns.
+    fn advance(&mut self) -> Vec<Outgoing> {
+        let players = self.players();
+        let mut messages = vec![];
+        self._deadline = None;
+        self._bot_due = None;
+        loop {
+            let round = self._round.clone().expect("Advancing with no round");
+            for (seat, &player) in players.iter().enumerate() {
+                let view = round.view(player).ok().expect("Player not in round");
+                messages.push(Outgoing::To(seat, ServerMessage::View(view)));
+            }
+            messages.push(Outgoing::Spectators(ServerMessage::SpectatorView(round.spectator_view(false))));
+            let full = match round.peek_turn() {
+                Some((ready, _)) => ready.spectator_view(true),
+                None => round.spectator_view(true),
+            };
+            messages.push_all(self.show_omniscient(full).as_slice());
+            match round.peek_turn() {
+                Some((_, ref turn)) if turn.busts() => {
+                    match round.handle_turn(|_, _| panic!("Asked a busted player to play"), |_, _| ()) {
+                        Ok(Some((new_round, outcome))) => {
+                            messages.push(self.log(Outgoing::All(ServerMessage::Turn(outcome))));
+                            self._round = Some(new_round);
+                        },
+                        other => panic!("Could not bust out player: {:?}", other),
+                    }
+                },
+                Some((ready, turn)) => {
+                    let seat = players.iter().position(|&p| p == turn.player).expect("Unknown player");
+                    if self.is_bot(seat) {
+                        messages.push_all(self.play_bots().as_slice());
+                    } else {
+                        let view = ready.view(turn.player).ok().expect("Player not in round");
+                        messages.push(Outgoing::To(seat, ServerMessage::YourTurn(view)));
+                        self.start_turn_clock();
+                    }
+                    return messages;
+                },
+                None => {
+                    messages.push_all(self.finish_round(&round).as_slice());
+                    return messages;
+                },
+            }
+        }
+    }
+
+    fn finish_round(&mut self, round: &Round) -> Vec<Outgoing> {
+        let winners = round.winners();
+        let winner_ids: Vec<PlayerId> = winners.iter().map(|&(p, _)| p).collect();
+        self._game = self._game.players_won(winner_ids.as_slice());
+        self._round = None;
+        let mut messages = self.release_omniscient(true);
+        let round_over = Outgoing::All(ServerMessage::RoundOver(winners, self._game.scores()));
+        messages.push(self.log(round_over));
+        if self._game.is_over() {
+            let game_over = Outgoing::All(ServerMessage::GameOver(self._game.winners(), self._game.scores()));
+            messages.push(self.log(game_over));
+        } else {
+            messages.push_all(self.start_round().as_slice());
+        }
+        messages
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use agent::AgentKind;
+    use chat;
+    use chat::Emote;
+    use deck::Card;
+    use protocol::{SeatPlay, ServerMessage};
+    use super::{Error, Outgoing, Table};
+
+    fn full_table() -> Table {
+        let mut table = Table::new(2).unwrap();
+        table.join("alice").unwrap();
+        table.join("bob").unwrap();
+        table
+    }
+
+    fn your_turn(messages: &[Outgoing]) -> Option<usize> {
+        messages.iter().filter_map(|m| match *m {
+            Outgoing::To(seat, ServerMessage::YourTurn(..)) => Some(seat),
+            _ => None,
+        }).last()
+    }
+
+    #[test]
+    fn test_bad_number_of_players() {
+        assert!(Table::new(1).is_none());
+        assert!(Table::new(5).is_none());
+    }
+
+    #[test]
+    fn test_join_fills_seats() {
+        let mut table = Table::new(2).unwrap();
+        let (seat, messages) = table.join("alice").unwrap();
+        assert_eq!(0, seat);
+        assert_eq!(None, your_turn(messages.as_slice()));
+        assert_eq!(None, table.waiting_for());
+        let (seat, messages) = table.join("bob").unwrap();
+        assert_eq!(1, seat);
+        assert!(messages.contains(&Outgoing::All(ServerMessage::RoundStarted(0))));
+        assert_eq!(table.waiting_for(), your_turn(messages.as_slice()));
+        assert_eq!(Err(Error::TableFull), table.join("carol"));
+    }
+
+    #[test]
+    fn test_only_own_view() {
+        let mut table = Table::new(2).unwrap();
+        table.join("alice").unwrap();
+        let (_, messages) = table.join("bob").unwrap();
+        let players = table.players();
+        for message in messages.iter() {
+            match *message {
+                Outgoing::To(seat, ServerMessage::View(ref view)) |
+                Outgoing::To(seat, ServerMessage::YourTurn(ref view)) =>
+                    assert_eq!(players[seat], view.player),
+                Outgoing::All(ServerMessage::View(..)) |
+                Outgoing::All(ServerMessage::YourTurn(..)) =>
+                    panic!("Broadcast a private view: {:?}", message),
+                _ => (),
+            }
+        }
+    }
+
+    #[test]
+    fn test_submit_out_of_turn() {
+        let mut table = full_table();
+        let seat = table.waiting_for().unwrap();
+        let other = 1 - seat;
+        let messages = table.submit(other, Card::Priestess, SeatPlay::NoEffect);
+        match messages.as_slice() {
+            [Outgoing::To(s, ServerMessage::Error(..))] => assert_eq!(other, s),
+            _ => panic!("Unexpected messages: {:?}", messages),
+        }
+        assert_eq!(Some(seat), table.waiting_for());
+    }
+
+    #[test]
+    fn test_invalid_play_keeps_turn() {
+        let mut table = full_table();
+        let seat = table.waiting_for().unwrap();
+        // Nobody can play a Soldier on themselves, even if they have one.
+        let messages = table.submit(seat, Card::Soldier, SeatPlay::Guess(seat, Card::Princess));
+        match messages.as_slice() {
+            [Outgoing::To(s, ServerMessage::InvalidPlay(..))] => assert_eq!(seat, s),
+            _ => panic!("Unexpected messages: {:?}", messages),
+        }
+        assert_eq!(Some(seat), table.waiting_for());
+    }
+
+    #[test]
+    fn test_play_whole_game() {
+        let mut table = full_table();
+        let mut turns = 0;
+        while !table.is_over() {
+            let seat = table.waiting_for().expect("Nobody to play");
+            let players = table.players();
+            let (ready, _) = table._round.as_ref().unwrap().peek_turn().unwrap();
+            let view = ready.view(players[seat]).unwrap();
+            let (card, play) = view.legal_plays()[0];
+            let messages = table.submit(seat, card, SeatPlay::from_play(players.as_slice(), play));
+            assert!(messages.iter().any(|m| match *m {
+                Outgoing::All(ServerMessage::Turn(..)) => true,
+                _ => false,
+            }));
+            turns += 1;
+            assert!(turns < 1000);
+        }
+        assert_eq!(None, table.waiting_for());
+    }
+
+    #[test]
+    fn test_reconnect_catches_up() {
+        let mut table = full_table();
+        let seat = table.waiting_for().unwrap();
+        let other = 1 - seat;
+        table.disconnect(other, 0).unwrap();
+        let players = table.players();
+        let view = {
+            let (ready, _) = table._round.as_ref().unwrap().peek_turn().unwrap();
+            ready.view(players[seat]).unwrap()
+        };
+        let (card, play) = view.legal_plays()[0];
+        table.submit(seat, card, SeatPlay::from_play(players.as_slice(), play));
+        let messages = table.reconnect(other).unwrap();
+        assert!(messages.iter().all(|m| match *m {
+            Outgoing::To(s, _) => s == other,
+            Outgoing::All(..) | Outgoing::Spectators(..) | Outgoing::Omniscient(..) => false,
+        }));
+        assert!(messages.contains(&Outgoing::To(other, ServerMessage::RoundStarted(0))));
+        assert!(messages.iter().any(|m| match *m {
+            Outgoing::To(_, ServerMessage::Turn(..)) => true,
+            _ => false,
+        }));
+        assert!(messages.iter().any(|m| match *m {
+            Outgoing::To(_, ServerMessage::View(..)) | Outgoing::To(_, ServerMessage::YourTurn(..)) => true,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn test_bot_takes_over_after_grace_period() {
+        let mut table = full_table();
+        table.set_grace_period(30);
+        let seat = table.waiting_for().unwrap();
+        table.disconnect(seat, 100).unwrap();
+        assert_eq!(Vec::<Outgoing>::new(), table.tick(129));
+        assert_eq!(Some(seat), table.waiting_for());
+        let messages = table.tick(130);
+        assert!(table.is_bot(seat));
+        assert!(messages.contains(&Outgoing::All(ServerMessage::BotTakeover(table.players()[seat]))));
+        assert!(table.waiting_for() != Some(seat) || table.is_over());
+        // Coming back takes the seat back from the bot.
+        table.reconnect(seat).unwrap();
+        assert!(!table.is_bot(seat));
+    }
+
+    #[test]
+    fn test_game_goes_on_after_leaving() {
+        let mut table = full_table();
+        let seat = table.waiting_for().unwrap();
+        table.leave(seat).unwrap();
+        assert!(table.is_bot(seat));
+        assert_eq!(None, table.names()[seat]);
+        let (new_seat, _) = table.join("carol").unwrap();
+        assert_eq!(seat, new_seat);
+        assert!(!table.is_bot(seat));
+    }
+
+    #[test]
+    fn test_turn_limit() {
+        let mut table = Table::new(2).unwrap();
+        table.set_turn_limit(Some(30));
+        table.join("alice").unwrap();
+        table.join("bob").unwrap();
+        let seat = table.waiting_for().unwrap();
+        let player = table.players()[seat];
+        assert_eq!(Vec::<Outgoing>::new(), table.tick(19));
+        assert_eq!(vec![Outgoing::To(seat, ServerMessage::TurnWarning(10))], table.tick(20));
+        assert_eq!(Vec::<Outgoing>::new(), table.tick(25));
+        let messages = table.tick(30);
+        assert_eq!(Outgoing::All(ServerMessage::TimedOut(player)), messages[0]);
+        match messages[1] {
+            Outgoing::All(ServerMessage::Turn(..)) => (),
+            ref other => panic!("Expected the bot's turn, got {:?}", other),
+        }
+        // Running out of time once doesn't hand the seat to a bot.
+        assert!(!table.is_bot(seat));
+    }
+
+    #[test]
+    fn test_turn_limit_after_restore() {
+        let table = full_table();
+        let seat = table.waiting_for().unwrap();
+        let player = table.players()[seat];
+        let mut restored = Table::from_snapshot(&table.snapshot()).unwrap();
+        restored.set_grace_period(100);
+        restored.set_turn_limit(Some(30));
+        assert_eq!(vec![Outgoing::To(seat, ServerMessage::TurnWarning(10))], restored.tick(20));
+        let messages = restored.tick(30);
+        assert_eq!(Outgoing::All(ServerMessage::TimedOut(player)), messages[0]);
+    }
+
+    fn omniscient(messages: &[Outgoing]) -> Vec<Outgoing> {
+        messages.iter().filter(|m| match **m {
+            Outgoing::Omniscient(..) => true,
+            _ => false,
+        }).map(|m| m.clone()).collect()
+    }
+
+    #[test]
+    fn test_spectators_see_no_hands() {
+        let mut table = Table::new(2).unwrap();
+        table.join("alice").unwrap();
+        let (_, messages) = table.join("bob").unwrap();
+        for message in messages.iter() {
+            match *message {
+                Outgoing::Spectators(ServerMessage::SpectatorView(ref view)) => {
+                    assert_eq!(None, view.hands);
+                    assert_eq!(None, view.draw);
+                },
+                Outgoing::Spectators(ref other) => panic!("Unexpected message for spectators: {:?}", other),
+                _ => (),
+            }
+        }
+        // Without a delay, omniscient spectators wait for the end of the
+        // round.
+        assert_eq!(Vec::<Outgoing>::new(), omniscient(messages.as_slice()));
+        let watching = table.spectate();
+        assert_eq!(ServerMessage::Seated(table.names()), watching[0]);
+        assert!(watching.contains(&ServerMessage::RoundStarted(0)));
+    }
+
+    #[test]
+    fn test_omniscient_delay() {
+        let mut table = Table::new(2).unwrap();
+        table.set_omniscient_delay(Some(5));
+        table.join("alice").unwrap();
+        let (_, messages) = table.join("bob").unwrap();
+        assert_eq!(Vec::<Outgoing>::new(), omniscient(messages.as_slice()));
+        assert_eq!(Vec::<Outgoing>::new(), omniscient(table.tick(4).as_slice()));
+        let released = omniscient(table.tick(5).as_slice());
+        assert!(released.len() > 0);
+        for message in released.iter() {
+            match *message {
+                Outgoing::Omniscient(ServerMessage::SpectatorView(ref view)) => assert!(view.hands.is_some()),
+                ref other => panic!("Unexpected message: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_chat() {
+        let mut table = full_table();
+        let players = table.players();
+        let messages = table.chat(1, "  Nice guess. ");
+        assert_eq!(vec![Outgoing::All(ServerMessage::Chat(players[1], "Nice guess.".to_string()))], messages);
+        assert_eq!(vec![Outgoing::All(ServerMessage::Emote(players[0], Emote::Bow))], table.emote(0, Emote::Bow));
+        // It's kept for anyone who comes back.
+        let messages = table.reconnect(0).unwrap();
+        assert!(messages.contains(&Outgoing::To(0, ServerMessage::Chat(players[1], "Nice guess.".to_string()))));
+        match table.chat(0, "   ").as_slice() {
+            [Outgoing::To(0, ServerMessage::Error(..))] => (),
+            other => panic!("Unexpected messages: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_chat_is_rate_limited() {
+        let mut table = full_table();
+        for _ in range(0, chat::CHAT_BURST) {
+            table.emote(0, Emote::Laugh);
+        }
+        match table.emote(0, Emote::Laugh).as_slice() {
+            [Outgoing::To(0, ServerMessage::Error(..))] => (),
+            other => panic!("Unexpected messages: {:?}", other),
+        }
+        // Everyone has their own limit.
+        assert_eq!(1, table.emote(1, Emote::Laugh).len());
+        table.tick(chat::CHAT_WINDOW);
+        assert_eq!(1, table.emote(0, Emote::Laugh).len());
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut table = full_table();
+        let seat = table.waiting_for().unwrap();
+        let players = table.players();
+        let view = {
+            let (ready, _) = table._round.as_ref().unwrap().peek_turn().unwrap();
+            ready.view(players[seat]).unwrap()
+        };
+        let (card, play) = view.legal_plays()[0];
+        table.submit(seat, card, SeatPlay::from_play(players.as_slice(), play));
+        table.chat(0, "hello");
+        let snapshot = table.snapshot();
+        let restored = Table::from_snapshot(&snapshot).unwrap();
+        assert_eq!(snapshot, restored.snapshot());
+        assert_eq!(table.waiting_for(), restored.waiting_for());
+        assert_eq!(table.names(), restored.names());
+    }
+
+    #[test]
+    fn test_restored_players_can_reconnect() {
+        let table = full_table();
+        let mut restored = Table::from_snapshot(&table.snapshot()).unwrap();
+        restored.set_grace_period(10);
+        let seat = restored.waiting_for().unwrap();
+        let messages = restored.reconnect(seat).unwrap();
+        assert_eq!(Some(seat), your_turn(messages.as_slice()));
+        // Whoever doesn't come back is replaced by a bot.
+        let messages = restored.tick(10);
+        assert!(messages.contains(&Outgoing::All(ServerMessage::BotTakeover(restored.players()[1 - seat]))));
+    }
+
+    #[test]
+    fn test_bad_snapshot() {
+        let mut snapshot = full_table().snapshot();
+        snapshot.scores.push(0);
+        assert!(Table::from_snapshot(&snapshot).is_none());
+    }
+
+    /// Make the first legal play for whoever's turn it is.
+    fn play_first(table: &mut Table) -> Vec<Outgoing> {
+        let seat = table.waiting_for().expect("Nobody to play");
+        let players = table.players();
+        let view = {
+            let (ready, _) = table._round.as_ref().unwrap().peek_turn().unwrap();
+            ready.view(players[seat]).unwrap()
+        };
+        let (card, play) = view.legal_plays()[0];
+        table.submit(seat, card, SeatPlay::from_play(players.as_slice(), play))
+    }
+
+    #[test]
+    fn test_bots_fill_seats() {
+        let mut table = Table::new(3).unwrap();
+        let (bot, messages) = table.add_bot(AgentKind::Random).unwrap();
+        assert_eq!(0, bot);
+        assert_eq!(vec![Outgoing::All(ServerMessage::Seated(table.names()))], messages);
+        assert_eq!(Some("random bot".to_string()), table.names()[bot]);
+        assert_eq!("random bot", table.roster().name(table.players()[bot]).as_slice());
+        assert!(table.is_bot(bot));
+        table.join("alice").unwrap();
+        let (_, messages) = table.join("bob").unwrap();
+        assert!(messages.contains(&Outgoing::All(ServerMessage::RoundStarted(0))));
+        // The bot never holds anyone up.
+        assert!(table.waiting_for().map_or(true, |seat| seat != bot));
+        assert_eq!(Err(Error::TableFull), table.add_bot(AgentKind::Heuristic));
+    }
+
+    #[test]
+    fn test_join_takes_over_bot_between_rounds() {
+        let mut table = Table::new(2).unwrap();
+        table.add_bot(AgentKind::Heuristic).unwrap();
+        let (alice, _) = table.join("alice").unwrap();
+        let (bob, messages) = table.join("bob").unwrap();
+        assert!(alice != bob);
+        assert_eq!(None, your_turn(messages.as_slice()));
+        assert_eq!(Some("bob".to_string()), table.names()[bob]);
+        // The bot finishes the round.
+        assert!(table.is_bot(bob));
+        let mut turns = 0;
+        while table._num_rounds == 1 && !table.is_over() {
+            play_first(&mut table);
+            turns += 1;
+            assert!(turns < 100);
+        }
+        assert!(!table.is_bot(bob));
+        assert_eq!(None, table.snapshot().agents[bob]);
+    }
+
+    #[test]
+    fn test_reconnect_while_waiting_to_take_over() {
+        let mut table = Table::new(2).unwrap();
+        table.add_bot(AgentKind::Heuristic).unwrap();
+        table.join("alice").unwrap();
+        let (bob, _) = table.join("bob").unwrap();
+        table.disconnect(bob, 0).unwrap();
+        table.reconnect(bob).unwrap();
+        // The bot still finishes the round.
+        assert!(table.is_bot(bob));
+        assert!(table.waiting_for().map_or(true, |seat| seat != bob));
+    }
+
+    #[test]
+    fn test_think_delay() {
+        let mut table = Table::new(2).unwrap();
+        table.set_think_delay(3);
+        table.add_bot(AgentKind::Heuristic).unwrap();
+        table.join("alice").unwrap();
+        // Let the bot make its first play, if it goes first, then get to its
+        // next turn.
+        table.tick(10);
+        while table.waiting_for() == Some(1) {
+            play_first(&mut table);
+        }
+        assert_eq!(Some(0), table.waiting_for());
+        assert_eq!(Vec::<Outgoing>::new(), table.tick(12));
+        let messages = table.tick(13);
+        assert!(messages.iter().any(|m| match *m {
+            Outgoing::All(ServerMessage::Turn(..)) => true,
+            _ => false,
+        }));
+        assert!(table.waiting_for() != Some(0) || table.is_over());
+    }
+}
@@ -4,7 +4,8 @@ use action::Play::{Attack, Guess};
 use action::PlayError::{BadActionForCard, BadGuess, SelfTarget};
 use action::Action::{
     SwapHands, ForceDiscard, ForceReveal, EliminateWeaker, EliminateOnGuess};
-use action::play_to_action;
+use action::{play_to_action, plays_for_card};
+use deck;
 use player_id::{PlayerId, player_id_generator};
 
 
@@ -83,3 +84,29 @@ fn test_guess_soldier() {
     let result = play_to_action(player1, Soldier, Guess(player2, Soldier));
     assert_eq!(BadGuess, result.unwrap_err());
 }
+
+#[test]
+fn test_plays_for_card_all_valid() {
+    let (player1, player2) = make_players();
+    for &card in deck::CARDS.iter() {
+        for &play in plays_for_card(player1, card, &[player1, player2]).iter() {
+            assert!(play_to_action(player1, card, play).is_ok(), "{:?} {:?}", card, play);
+        }
+    }
+}
+
+#[test]
+fn test_plays_for_card_soldier() {
+    let (player1, player2) = make_players();
+    let plays = plays_for_card(player1, Soldier, &[player1, player2]);
+    assert_eq!(7, plays.len());
+    assert!(plays.iter().all(|&p| match p { Guess(target, _) => target == player2, _ => false }));
+}
+
+#[test]
+fn test_plays_for_card_wizard_self() {
+    let (player1, player2) = make_players();
+    assert_eq!(
+        vec![Attack(player1), Attack(player2)],
+        plays_for_card(player1, Wizard, &[player1, player2]));
+}
@@ -0,0 +1,522 @@
+/// A full-screen interface for playing Love Letter at a terminal.
+///
+/// The screen is laid out like a table: every player, with their discards
+/// and whether they're protected or out of the round, how many cards are
+/// left to draw, the hand of whoever's turn it is, and a log of what's
+/// happened, which can be scrolled back through.
+///
+/// Plays are picked with the keyboard, one step at a time: first the card,
+/// then who to play it on, then, for the Soldier, what to guess. The arrow
+/// keys (or `h`, `j`, `k` and `l`) move between choices, Enter picks one,
+/// Escape goes back a step, and Page Up and Page Down scroll the log. `?`
+/// shows a page of help instead of the table.
+///
+/// Laying out the screen and reacting to keys don't touch the terminal, so
+/// they can be tested. Only `Terminal` does that.
+
+use std::cmp;
+use std::io;
+use std::io::process::{Command, StdioContainer};
+use std::iter::repeat;
+use std::str;
+
+use action::Play;
+use deck::Card;
+use locale::Catalogue;
+use player_id::PlayerId;
+use roster::Roster;
+use view::PlayerView;
+
+
+/// The size we assume the terminal is if we can't find out.
+pub const DEFAULT_SIZE: (usize, usize) = (80, 24);
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Escape,
+    PageUp,
+    PageDown,
+    Char(char),
+}
+
+
+/// Work out which key was pressed from what the terminal sent. Returns
+/// `None` for anything we don't understand.
+pub fn parse_key(bytes: &[u8]) -> Option<Key> {
+    match bytes {
+        [0x1b, b'[', b'A', ..] => Some(Key::Up),
+        [0x1b, b'[', b'B', ..] => Some(Key::Down),
+        [0x1b, b'[', b'C', ..] => Some(Key::Right),
+        [0x1b, b'[', b'D', ..] => Some(Key::Left),
+        [0x1b, b'[', b'5', b'~', ..] => Some(Key::PageUp),
+        [0x1b, b'[', b'6', b'~', ..] => Some(Key::PageDown),
+        [0x1b] => Some(Key::Escape),
+        [b'\r', ..] | [b'\n', ..] => Some(Key::Enter),
+        [c, ..] if 0x20 <= c && c < 0x7f => Some(Key::Char(c as char)),
+        _ => None,
+    }
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone, Copy)]
+/// Something that can be picked while choosing a play.
+pub enum Choice {
+    Card(Card),
+    Target(PlayerId),
+    /// Play the card without a target.
+    Nobody,
+    Guess(Card),
+}
+
+
+#[derive(Show, Clone)]
+/// Picks one of the viewer's legal plays, a step at a time.
+pub struct Chooser {
+    _plays: Vec<(Card, Play)>,
+    _card: Option<Card>,
+    _target: Option<PlayerId>,
+    /// Which of the current choices is highlighted.
+    _index: usize,
+}
+
+
+impl Chooser {
+    /// Choose from the plays open to the viewer of `view`, whose turn it
+    /// must be.
+    pub fn new(view: &PlayerView) -> Chooser {
+        Chooser { _plays: view.legal_plays(), _card: None, _target: None, _index: 0 }
+    }
+
+    /// What's being asked for at this step.
+    pub fn question(&self, text: &Catalogue) -> String {
+        match (self._card, self._target) {
+            (None, _) => text.which_card(),
+            (Some(..), None) => text.which_target(),
+            (Some(..), Some(..)) => text.pick_guess(),
+        }
+    }
+
+    /// What can be picked at this step, in the order they're shown.
+    pub fn choices(&self) -> Vec<Choice> {
+        let mut choices = vec![];
+        for &(card, play) in self._plays.iter() {
+            let choice = match (self._card, self._target, play) {
+                (None, _, _) => Choice::Card(card),
+                (Some(c), _, _) if c != card => continue,
+                (Some(..), None, Play::NoEffect) => Choice::Nobody,
+                (Some(..), None, Play::Attack(p)) | (Some(..), None, Play::Guess(p, _)) => Choice::Target(p),
+                (Some(..), Some(t), Play::Guess(p, guess)) if t == p => Choice::Guess(guess),
+                _ => continue,
+            };
+            if !choices.contains(&choice) {
+                choices.push(choice);
+            }
+        }
+        choices
+    }
+
+    /// The choice that's highlighted.
+    pub fn selected(&self) -> Option<Choice> {
+        self.choices().get(self._index).map(|&c| c)
+    }
+
+    /// React to a key. Returns the play once one has been picked.
+    pub fn handle(&mut self, key: Key) -> Option<(Card, Play)> {
+        let num_choices = self.choices().len();
+        if num_choices == 0 {
+            return None;
+        }
+        match key {
+            Key::Left | Key::Up | Key::Char('h') | Key::Char('k') =>
+                self._index = (self._index + num_choices - 1) % num_choices,
+            Key::Right | Key::Down | Key::Char('l') | Key::Char('j') =>
+                self._index = (self._index + 1) % num_choices,
+            Key::Char(c) if c.is_digit(10) => {
+                let n = c.to_digit(10).unwrap();
+                if 1 <= n && n <= num_choices {
+                    self._index = n - 1;
+                }
+            },
+            Key::Escape => {
+                if self._target.is_some() {
+                    self._target = None;
+                } else {
+                    self._card = None;
+                }
+                self._index = 0;
+            },
+            Key::Enter => return self.pick(),
+            _ => (),
+        }
+        None
+    }
+
+    /// Take the highlighted choice, and move on to the next step, unless
+    /// that's the end of it.
+    fn pick(&mut self) -> Option<(Card, Play)> {
+        let choice = match self.selected() {
+            Some(choice) => choice,
+            None => return None,
+        };
+        self._index = 0;
+        match (choice, self._card) {
+            (Choice::Card(card), _) => {
+                self._card = Some(card);
+                match self.choices().as_slice() {
+                    [Choice::Nobody] => Some((card, Play::NoEffect)),
+                    _ => None,
+                }
+            },
+            (Choice::Nobody, Some(card)) => Some((card, Play::NoEffect)),
+            (Choice::Target(target), Some(card)) => {
+                if self._plays.contains(&(card, Play::Attack(target))) {
+                    Some((card, Play::Attack(target)))
+                } else {
+                    self._target = Some(target);
+                    None
+                }
+            },
+            (Choice::Guess(guess), Some(card)) =>
+                self._target.map(|target| (card, Play::Guess(target, guess))),
+            _ => None,
+        }
+    }
+
+    /// The question and the choices, with the highlighted one in brackets.
+    /// Players are called what `roster` calls them.
+    pub fn prompt(&self, text: &Catalogue, roster: &Roster) -> String {
+        let choices: Vec<String> = self.choices().iter().enumerate().map(|(i, choice)| {
+            let name = match *choice {
+                Choice::Card(card) | Choice::Guess(card) => text.card(card).to_string(),
+                Choice::Target(player) => roster.name(player),
+                Choice::Nobody => text.nobody(),
+            };
+            if i == self._index { format!("[{}]", name) } else { format!(" {} ", name) }
+        }).collect();
+        format!("{}: {}", self.question(text), choices.connect(" "))
+    }
+}
+
+
+/// Everything on screen that isn't part of the round itself.
+pub struct Ui {
+    _title: String,
+    _log: Vec<String>,
+    /// How many lines back from the end of the log we've scrolled.
+    _scroll: usize,
+}
+
+
+impl Ui {
+    pub fn new(title: &str) -> Ui {
+        Ui { _title: title.to_string(), _log: vec![], _scroll: 0 }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self._title = title.to_string();
+    }
+
+    /// Add `line` to the end of the log, and scroll down to it.
+    pub fn log(&mut self, line: &str) {
+        self._log.push(line.to_string());
+        self._scroll = 0;
+    }
+
+    /// Scroll the log if `key` is for scrolling. Returns whether it was.
+    pub fn scroll(&mut self, key: Key) -> bool {
+        match key {
+            Key::PageUp => self._scroll = cmp::min(self._scroll + 5, self._log.len()),
+            Key::PageDown => self._scroll = self._scroll - cmp::min(self._scroll, 5),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Lay out the screen for someone looking at `view`, `width` characters
+    /// wide and `height` lines high. The viewer's hand is only shown if
+    /// `show_hand` is set. `footer` goes on the last line. Players are
+    /// called what `roster` calls them, and everything else is in `text`.
+    pub fn render(&self, text: &Catalogue, view: &PlayerView, roster: &Roster, show_hand: bool,
+                  footer: &str, width: usize, height: usize)
+                  -> Vec<String> {
+        let rule: String = repeat('-').take(width).collect();
+        let deck = text.deck_size(view.deck_size);
+        let used = self._title.chars().count() + deck.chars().count();
+        let padding = cmp::max(1, width as isize - used as isize) as usize;
+        let mut lines = vec![format!("{}{}{}", self._title, repeat(' ').take(padding).collect::<String>(), deck)];
+        lines.push(rule.clone());
+        for player in view.players.iter() {
+            let marker = if show_hand && player.id == view.player { ">" } else { " " };
+            let status = if !player.active {
+                text.status_out()
+            } else if player.protected {
+                text.status_protected()
+            } else {
+                "".to_string()
+            };
+            let discards: Vec<&str> = player.discards.iter().map(|&c| text.card(c)).collect();
+            lines.push(format!("{} {:<12} {:<10} {}", marker, roster.name(player.id), status,
+                               discards.connect(", ")));
+        }
+        lines.push(rule.clone());
+        lines.push(match (show_hand, view.hand, view.draw) {
+            (false, _, _) => "".to_string(),
+            (true, None, _) => text.you_are_out(),
+            (true, Some(hand), None) => text.hand(hand),
+            (true, Some(hand), Some(draw)) => text.hand_and_draw(hand, draw),
+        });
+        lines.push(rule.clone());
+        // Whatever room is left, apart from the footer and its rule, is for
+        // the log.
+        let room = cmp::max(height as isize - lines.len() as isize - 2, 0) as usize;
+        let end = self._log.len() - cmp::min(self._scroll, self._log.len());
+        let start = end - cmp::min(room, end);
+        for line in self._log[start..end].iter() {
+            lines.push(line.clone());
+        }
+        for _ in range(end - start, room) {
+            lines.push("".to_string());
+        }
+        lines.push(rule);
+        lines.push(footer.to_string());
+        lines.into_iter().map(|line| line.chars().take(width).collect()).collect()
+    }
+}
+
+
+/// Lay out `page`, some text to be read on its own like the rules,
+/// `width` characters wide and `height` lines high, with `footer` on the
+/// last line. Long lines are wrapped, and whatever doesn't fit is cut off.
+pub fn render_page(page: &str, footer: &str, width: usize, height: usize) -> Vec<String> {
+    let room = cmp::max(height as isize - 2, 0) as usize;
+    let mut lines: Vec<String> = vec![];
+    for line in page.lines() {
+        lines.push_all(wrap(line, width).as_slice());
+    }
+    lines.truncate(room);
+    for _ in range(lines.len(), room) {
+        lines.push("".to_string());
+    }
+    lines.push(repeat('-').take(width).collect());
+    lines.push(footer.to_string());
+    lines.into_iter().map(|line| line.chars().take(width).collect()).collect()
+}
+
+
+/// Split `line` into lines no wider than `width`, breaking between words.
+/// Lines after the first are indented a little more than it was.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let indent: String = line.chars().take_while(|&c| c == ' ').collect();
+    let mut lines = vec![];
+    let mut current = indent.clone();
+    for word in line.words() {
+        let length = current.chars().count();
+        if length > indent.len() && length + 1 + word.chars().count() > width {
+            lines.push(current);
+            current = format!("{}  ", indent);
+        } else if length > indent.len() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+}
+
+
+/// The terminal, switched to reading keys as they're pressed, without
+/// echoing them. It's put back the way it was when dropped.
+pub struct Terminal {
+    _width: usize,
+    _height: usize,
+}
+
+
+impl Terminal {
+    pub fn new() -> Terminal {
+        stty(&["-icanon", "-echo", "min", "1"]);
+        // Hide the cursor.
+        print!("\x1b[?25l");
+        let (width, height) = terminal_size().unwrap_or(DEFAULT_SIZE);
+        Terminal { _width: width, _height: height }
+    }
+
+    /// How many characters wide, and lines high, the terminal is.
+    pub fn size(&self) -> (usize, usize) {
+        (self._width, self._height)
+    }
+
+    /// Replace whatever's on screen with `lines`.
+    pub fn draw(&mut self, lines: &[String]) {
+        print!("\x1b[H\x1b[2J{}", lines.connect("\n"));
+        io::stdio::flush();
+    }
+
+    /// Wait for a key to be pressed.
+    pub fn read_key(&mut self) -> Key {
+        let mut buffer = [0u8; 8];
+        loop {
+            let n = io::stdin().read(&mut buffer).ok().expect("Failed to read key");
+            match parse_key(&buffer[..n]) {
+                Some(key) => return key,
+                None => continue,
+            }
+        }
+    }
+}
+
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\n");
+        io::stdio::flush();
+        stty(&["icanon", "echo"]);
+    }
+}
+
+
+/// Change how the terminal handles input.
+fn stty(args: &[&str]) {
+    // XXX: If this doesn't work, the game is still playable, just ugly.
+    let _ = Command::new("stty").args(args).stdin(StdioContainer::InheritFd(0)).status();
+}
+
+
+/// Ask the terminal how big it is.
+fn terminal_size() -> Option<(usize, usize)> {
+    let output = match Command::new("stty").arg("size").stdin(StdioContainer::InheritFd(0)).output() {
+        Ok(output) => output,
+        Err(..) => return None,
+    };
+    let text = match str::from_utf8(output.output.as_slice()) {
+        Ok(text) => text.to_string(),
+        Err(..) => return None,
+    };
+    let numbers: Vec<usize> = text.split(' ').filter_map(|n| str::FromStr::from_str(n.trim())).collect();
+    match numbers.as_slice() {
+        [height, width] => Some((width, height)),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use action::Play;
+    use deck::Card;
+    use locale::Language;
+    use player_id::player_id_generator;
+    use roster::Roster;
+    use round::Round;
+    use view::PlayerView;
+    use super::{Choice, Chooser, Key, Ui, parse_key, render_page};
+
+    /// The first player holds `hand` and draws `draw`, with one card to go.
+    fn make_view(hand: Card, draw: Card) -> PlayerView {
+        let players: Vec<_> = player_id_generator().take(3).collect();
+        let round = Round::from_manual(
+            &[(players[0], Some(hand)), (players[1], Some(Card::Soldier)), (players[2], Some(Card::Clown))],
+            &[Card::Princess, draw], None).unwrap();
+        let (ready, turn) = round.peek_turn().unwrap();
+        ready.view(turn.player).unwrap()
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(Some(Key::Up), parse_key(b"\x1b[A"));
+        assert_eq!(Some(Key::PageDown), parse_key(b"\x1b[6~"));
+        assert_eq!(Some(Key::Escape), parse_key(b"\x1b"));
+        assert_eq!(Some(Key::Enter), parse_key(b"\n"));
+        assert_eq!(Some(Key::Char('q')), parse_key(b"q"));
+        assert_eq!(None, parse_key(b""));
+    }
+
+    #[test]
+    fn test_choose_card_without_target() {
+        let view = make_view(Card::Priestess, Card::Knight);
+        let mut chooser = Chooser::new(&view);
+        assert_eq!(vec![Choice::Card(Card::Priestess), Choice::Card(Card::Knight)], chooser.choices());
+        assert_eq!(Some((Card::Priestess, Play::NoEffect)), chooser.handle(Key::Enter));
+    }
+
+    #[test]
+    fn test_choose_guess() {
+        let view = make_view(Card::Priestess, Card::Soldier);
+        let target = view.players[2].id;
+        let mut chooser = Chooser::new(&view);
+        chooser.handle(Key::Right);
+        assert_eq!(Some(Choice::Card(Card::Soldier)), chooser.selected());
+        assert_eq!(None, chooser.handle(Key::Enter));
+        assert_eq!(2, chooser.choices().len());
+        chooser.handle(Key::Char('2'));
+        assert_eq!(Some(Choice::Target(target)), chooser.selected());
+        assert_eq!(None, chooser.handle(Key::Enter));
+        assert_eq!(Some(Choice::Guess(Card::Clown)), chooser.selected());
+        // Going back a step and forward again gets us to the same place.
+        chooser.handle(Key::Escape);
+        chooser.handle(Key::Down);
+        chooser.handle(Key::Enter);
+        chooser.handle(Key::Left);
+        assert_eq!(Some((Card::Soldier, Play::Guess(target, Card::Princess))), chooser.handle(Key::Enter));
+    }
+
+    #[test]
+    fn test_choose_attack() {
+        let view = make_view(Card::Priestess, Card::Knight);
+        let target = view.players[1].id;
+        let mut chooser = Chooser::new(&view);
+        chooser.handle(Key::Down);
+        chooser.handle(Key::Enter);
+        let text = Language::English.catalogue();
+        assert_eq!("Who are you playing it on?", chooser.question(text).as_slice());
+        let mut roster = Roster::new(&[target]);
+        roster.set_name(target, Some("bob".to_string()));
+        assert!(chooser.prompt(text, &roster).starts_with("Who are you playing it on?: [bob]"));
+        let french = chooser.prompt(Language::French.catalogue(), &roster);
+        assert!(french.starts_with("Sur qui la jouez-vous ?: [bob]"));
+        assert_eq!(Some((Card::Knight, Play::Attack(target))), chooser.handle(Key::Enter));
+    }
+
+    #[test]
+    fn test_render_fits_screen() {
+        let view = make_view(Card::Priestess, Card::Knight);
+        let mut ui = Ui::new("Love Letter");
+        for i in range(0, 50) {
+            ui.log(format!("Something happened, time number {}", i).as_slice());
+        }
+        let mut roster = Roster::new(&[view.player]);
+        roster.set_name(view.player, Some("alice".to_string()));
+        let text = Language::English.catalogue();
+        let lines = ui.render(text, &view, &roster, true, "Press Enter", 40, 20);
+        assert_eq!(20, lines.len());
+        assert!(lines.iter().all(|line| line.chars().count() <= 40));
+        assert_eq!("Press Enter", lines[19].as_slice());
+        assert!(lines.iter().any(|line| line.starts_with("Hand: Priestess")));
+        assert!(lines.iter().any(|line| line.contains("number 49")));
+        assert!(lines.iter().any(|line| line.starts_with("> alice")));
+        ui.scroll(Key::PageUp);
+        let lines = ui.render(text, &view, &roster, false, "", 40, 20);
+        assert!(!lines.iter().any(|line| line.contains("number 49")));
+        assert!(lines.iter().any(|line| line.contains("number 44")));
+        assert!(!lines.iter().any(|line| line.contains("Priestess")));
+    }
+
+    #[test]
+    fn test_render_page_wraps() {
+        let page = "Cards\n  1. Soldier (x5): Guess another player's card. If you're right, they're out.";
+        let lines = render_page(page, "Press Enter", 40, 10);
+        assert_eq!(10, lines.len());
+        assert!(lines.iter().all(|line| line.chars().count() <= 40));
+        assert_eq!("Cards", lines[0].as_slice());
+        assert!(lines[1].starts_with("  1. Soldier"));
+        assert!(lines[2].starts_with("    "));
+        assert!(lines.iter().any(|line| line.ends_with("they're out.")));
+        assert_eq!("Press Enter", lines[9].as_slice());
+    }
+}
@@ -0,0 +1,105 @@
+/// What a single player can see of a round of Love Letter.
+///
+/// A `PlayerView` holds everything that's public (discard piles, who is
+/// protected, who is still in, how many cards are left) plus the viewer's
+/// own hand. It never contains anybody else's hand, so it is safe to show
+/// to the viewer, or to give to an automated player.
+///
+/// A `SpectatorView` is what someone watching the round can see: the same
+/// public information, and, if they're allowed, every player's hand.
+
+use action;
+use action::Play;
+use deck;
+use deck::Card;
+use player_id::PlayerId;
+use util;
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+/// The public state of one player.
+pub struct PublicPlayer {
+    pub id: PlayerId,
+    /// Everything this player has discarded, oldest first.
+    pub discards: Vec<Card>,
+    /// Protected by the Priestess until their next turn.
+    pub protected: bool,
+    /// Still in the round.
+    pub active: bool,
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+pub struct PlayerView {
+    /// The player this view is for.
+    pub player: PlayerId,
+    /// The viewer's hand. `None` if they have been eliminated.
+    pub hand: Option<Card>,
+    /// The card the viewer has just drawn. Only set when it's their turn.
+    pub draw: Option<Card>,
+    /// Every player in the round, in order of play.
+    pub players: Vec<PublicPlayer>,
+    /// The number of cards left to draw.
+    pub deck_size: usize,
+}
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+pub struct SpectatorView {
+    /// Every player in the round, in order of play.
+    pub players: Vec<PublicPlayer>,
+    /// The number of cards left to draw.
+    pub deck_size: usize,
+    /// Everyone's hand, in order of play, or `None` if the spectator can't
+    /// see them. Eliminated players have no hand.
+    pub hands: Option<Vec<Option<Card>>>,
+    /// The card the current player has just drawn, if the spectator can
+    /// see hands.
+    pub draw: Option<Card>,
+}
+
+
+impl PlayerView {
+    /// The position of `player` in the order of play.
+    pub fn seat(&self, player: PlayerId) -> Option<usize> {
+        self.players.iter().position(|p| p.id == player)
+    }
+
+    /// The players still in the round, in order of play.
+    pub fn active_players(&self) -> Vec<PlayerId> {
+        self.players.iter().filter(|p| p.active).map(|p| p.id).collect()
+    }
+
+    /// The cards the viewer hasn't seen: other players' hands, the cards
+    /// left to draw, and the card that was burned at the start of the round.
+    pub fn unseen_cards(&self) -> Vec<Card> {
+        let mut seen: Vec<Card> = self.hand.iter().chain(self.draw.iter()).map(|&x| x).collect();
+        for player in self.players.iter() {
+            seen.push_all(player.discards.as_slice());
+        }
+        util::subtract_vector(deck::DECK.to_vec(), seen.as_slice())
+            .expect("Player has seen cards that aren't in the deck")
+    }
+
+    /// Every `(card, play)` the viewer could make right now.
+    ///
+    /// Empty unless it's the viewer's turn.
+    pub fn legal_plays(&self) -> Vec<(Card, Play)> {
+        let (hand, draw) = match (self.hand, self.draw) {
+            (Some(hand), Some(draw)) => (hand, draw),
+            _ => return vec![],
+        };
+        let targets = self.active_players();
+        let mut cards = vec![hand];
+        if draw != hand {
+            cards.push(draw);
+        }
+        let mut plays = vec![];
+        for &card in cards.iter() {
+            for &play in action::plays_for_card(self.player, card, targets.as_slice()).iter() {
+                plays.push((card, play));
+            }
+        }
+        plays
+    }
+}
@@ -0,0 +1,390 @@
+/// Just enough of the WebSocket protocol (RFC 6455) to carry the game's JSON
+/// messages to and from a browser.
+///
+/// Each protocol message goes in a single text frame. We answer pings and
+/// understand close frames, but don't support extensions or subprotocols.
+/// Frames are checked to be masked, or not, according to which end sent
+/// them.
+
+use std::ascii::AsciiExt;
+use std::io::{Buffer, IoError, IoErrorKind, IoResult};
+use std::num::Int;
+use std::rand::{Rng, thread_rng};
+
+use serialize::base64::{STANDARD, ToBase64};
+
+
+/// Appended to the client's key to make the accept key.
+const GUID: &'static str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Longest message we'll accept. Game messages are much smaller than this.
+const MAX_MESSAGE_SIZE: u64 = 1 << 20;
+
+
+#[derive(Show, PartialEq, Eq, Clone)]
+/// A complete message from the other end.
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close,
+}
+
+
+fn protocol_error(desc: &'static str) -> IoError {
+    IoError { kind: IoErrorKind::InvalidInput, desc: desc, detail: None }
+}
+
+
+/// Read an HTTP request or response head, up to the blank line, and return
+/// the first line and the headers. Header names are lower-cased.
+fn read_head<R: Buffer>(reader: &mut R) -> IoResult<(String, Vec<(String, String)>)> {
+    let first = try!(reader.read_line()).as_slice().trim().to_string();
+    let mut headers = vec![];
+    loop {
+        let line = try!(reader.read_line());
+        let line = line.as_slice().trim();
+        if line.len() == 0 {
+            return Ok((first, headers));
+        }
+        match line.find(':') {
+            Some(i) => headers.push((line.slice_to(i).trim().to_ascii_lowercase(),
+                                     line.slice_from(i + 1).trim().to_string())),
+            None => return Err(protocol_error("Malformed HTTP header")),
+        }
+    }
+}
+
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|&&(ref n, _)| n.as_slice() == name).map(|&(_, ref v)| v.as_slice())
+}
+
+
+/// The `Sec-WebSocket-Accept` value for a given `Sec-WebSocket-Key`.
+pub fn accept_key(key: &str) -> String {
+    let mut input = key.trim().to_string();
+    input.push_str(GUID);
+    sha1(input.as_bytes()).to_base64(STANDARD)
+}
+
+
+/// Read a client's opening handshake and accept it.
+pub fn server_handshake<R: Buffer, W: Writer>(reader: &mut R, writer: &mut W) -> IoResult<()> {
+    let (request, headers) = try!(read_head(reader));
+    if !request.as_slice().starts_with("GET ") {
+        return Err(protocol_error("WebSocket handshake must be a GET request"));
+    }
+    let upgrade = find_header(headers.as_slice(), "upgrade").map(|u| u.to_ascii_lowercase());
+    if upgrade != Some("websocket".to_string()) {
+        return Err(protocol_error("Not a WebSocket upgrade request"));
+    }
+    let key = match find_header(headers.as_slice(), "sec-websocket-key") {
+        Some(key) => key,
+        None => return Err(protocol_error("Missing Sec-WebSocket-Key")),
+    };
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n", accept_key(key));
+    try!(writer.write(response.as_bytes()));
+    writer.flush()
+}
+
+
+/// Ask the server at `host` to upgrade to WebSocket, and check that it
+/// agreed.
+pub fn client_handshake<R: Buffer, W: Writer>(reader: &mut R, writer: &mut W, host: &str) -> IoResult<()> {
+    let nonce: Vec<u8> = range(0, 16).map(|_| thread_rng().gen()).collect();
+    let key = nonce.to_base64(STANDARD);
+    let request = format!(
+        "GET / HTTP/1.1\r\n\
+         Host: {}\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Key: {}\r\n\
+         Sec-WebSocket-Version: 13\r\n\r\n", host, key);
+    try!(writer.write(request.as_bytes()));
+    try!(writer.flush());
+    let (status, headers) = try!(read_head(reader));
+    if !status.as_slice().contains(" 101 ") {
+        return Err(protocol_error("Server refused to switch to WebSocket"));
+    }
+    let expected = accept_key(key.as_slice());
+    if find_header(headers.as_slice(), "sec-websocket-accept") != Some(expected.as_slice()) {
+        return Err(protocol_error("Server sent the wrong accept key"));
+    }
+    Ok(())
+}
+
+
+/// Write a single, final frame. Clients must mask what they send; servers
+/// must not.
+pub fn write_frame<W: Writer>(writer: &mut W, opcode: u8, payload: &[u8], mask: Option<[u8; 4]>) -> IoResult<()> {
+    try!(writer.write_u8(0x80 | opcode));
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    let len = payload.len();
+    if len < 126 {
+        try!(writer.write_u8(mask_bit | len as u8));
+    } else if len < 1 << 16 {
+        try!(writer.write_u8(mask_bit | 126));
+        try!(writer.write_be_u16(len as u16));
+    } else {
+        try!(writer.write_u8(mask_bit | 127));
+        try!(writer.write_be_u64(len as u64));
+    }
+    match mask {
+        None => try!(writer.write(payload)),
+        Some(key) => {
+            try!(writer.write(&key));
+            let masked: Vec<u8> = payload.iter().enumerate().map(|(i, &b)| b ^ key[i % 4]).collect();
+            try!(writer.write(masked.as_slice()));
+        },
+    }
+    writer.flush()
+}
+
+
+/// Send a text message from the server.
+pub fn write_text<W: Writer>(writer: &mut W, text: &str) -> IoResult<()> {
+    write_frame(writer, OPCODE_TEXT, text.as_bytes(), None)
+}
+
+
+/// Send a text message from a client, with a fresh mask.
+pub fn write_masked_text<W: Writer>(writer: &mut W, text: &str) -> IoResult<()> {
+    let mut rng = thread_rng();
+    let mask = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
+    write_frame(writer, OPCODE_TEXT, text.as_bytes(), Some(mask))
+}
+
+
+/// Tell the other end we're done.
+pub fn write_close<W: Writer>(writer: &mut W) -> IoResult<()> {
+    write_frame(writer, OPCODE_CLOSE, &[], None)
+}
+
+
+/// Read one frame: whether it's the last of its message, its opcode and its
+/// unmasked payload. Frames from a client must be masked, and frames from
+/// a server mustn't be.
+fn read_frame<R: Reader>(reader: &mut R, from_client: bool) -> IoResult<(bool, u8, Vec<u8>)> {
+    let first = try!(reader.read_u8());
+    let second = try!(reader.read_u8());
+    let fin = first & 0x80 != 0;
+    let opcode = first & 0x0F;
+    let masked = second & 0x80 != 0;
+    if masked != from_client {
+        return Err(protocol_error(if from_client { "Client frame not masked" } else { "Server frame masked" }));
+    }
+    let len = match second & 0x7F {
+        126 => try!(reader.read_be_u16()) as u64,
+        127 => try!(reader.read_be_u64()),
+        n => n as u64,
+    };
+    if len > MAX_MESSAGE_SIZE {
+        return Err(protocol_error("WebSocket frame too large"));
+    }
+    let key = if masked { Some(try!(reader.read_exact(4))) } else { None };
+    let mut payload = try!(reader.read_exact(len as usize));
+    match key {
+        Some(key) => for (i, b) in payload.iter_mut().enumerate() {
+            *b ^= key[i % 4];
+        },
+        None => (),
+    }
+    Ok((fin, opcode, payload))
+}
+
+
+/// Read the next complete message from a client, joining up fragments.
+pub fn read_client_message<R: Reader>(reader: &mut R) -> IoResult<Message> {
+    read_message(reader, true)
+}
+
+
+/// Read the next complete message from a server, joining up fragments.
+pub fn read_server_message<R: Reader>(reader: &mut R) -> IoResult<Message> {
+    read_message(reader, false)
+}
+
+
+fn read_message<R: Reader>(reader: &mut R, from_client: bool) -> IoResult<Message> {
+    let (mut fin, opcode, mut payload) = try!(read_frame(reader, from_client));
+    while !fin {
+        let (next_fin, next_opcode, next_payload) = try!(read_frame(reader, from_client));
+        if next_opcode != OPCODE_CONTINUATION {
+            // XXX: Control frames may arrive in the middle of a fragmented
+            // message. Browsers don't fragment small messages, so we don't
+            // bother handling that.
+            return Err(protocol_error("Unexpected frame in fragmented message"));
+        }
+        payload.push_all(next_payload.as_slice());
+        if payload.len() as u64 > MAX_MESSAGE_SIZE {
+            return Err(protocol_error("WebSocket message too large"));
+        }
+        fin = next_fin;
+    }
+    match opcode {
+        OPCODE_TEXT => match String::from_utf8(payload) {
+            Ok(text) => Ok(Message::Text(text)),
+            Err(..) => Err(protocol_error("WebSocket text is not UTF-8")),
+        },
+        OPCODE_BINARY => Ok(Message::Binary(payload)),
+        OPCODE_CLOSE => Ok(Message::Close),
+        OPCODE_PING => Ok(Message::Ping(payload)),
+        OPCODE_PONG => Ok(Message::Pong(payload)),
+        _ => Err(protocol_error("Unknown WebSocket opcode")),
+    }
+}
+
+
+/// Answer a ping.
+pub fn write_pong<W: Writer>(writer: &mut W, payload: &[u8]) -> IoResult<()> {
+    write_frame(writer, OPCODE_PONG, payload, None)
+}
+
+
+/// The SHA-1 digest of `data`. Only used for the handshake.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    let bit_len = data.len() as u64 * 8;
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    for i in range(0, 8) {
+        message.push((bit_len >> (56 - 8 * i)) as u8);
+    }
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for i in range(0, 16) {
+            w[i] = (chunk[4 * i] as u32) << 24 | (chunk[4 * i + 1] as u32) << 16
+                | (chunk[4 * i + 2] as u32) << 8 | chunk[4 * i + 3] as u32;
+        }
+        for i in range(16, 80) {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let (mut a, mut b, mut c, mut d, mut e) = (h[0], h[1], h[2], h[3], h[4]);
+        for i in range(0, 80) {
+            let (f, k) = match i {
+                0...19 => ((b & c) | (!b & d), 0x5A827999),
+                20...39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40...59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5) + f + e + k + w[i];
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        h[0] += a;
+        h[1] += b;
+        h[2] += c;
+        h[3] += d;
+        h[4] += e;
+    }
+    let mut digest = [0u8; 20];
+    for (i, word) in h.iter().enumerate() {
+        for j in range(0, 4) {
+            digest[4 * i + j] = (*word >> (24 - 8 * j)) as u8;
+        }
+    }
+    digest
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufReader, MemReader, MemWriter};
+
+    use super::{Message, accept_key, read_client_message, read_server_message};
+    use super::{server_handshake, sha1};
+    use super::{write_close, write_masked_text, write_text};
+
+    #[test]
+    fn test_sha1() {
+        let digest = sha1(b"abc");
+        assert_eq!(
+            vec![0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e,
+                 0x25, 0x71, 0x78, 0x50, 0xc2, 0x6c, 0x9c, 0xd0, 0xd8, 0x9d],
+            digest.to_vec());
+    }
+
+    #[test]
+    fn test_accept_key() {
+        // The example from RFC 6455.
+        assert_eq!("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=", accept_key("dGhlIHNhbXBsZSBub25jZQ==").as_slice());
+    }
+
+    #[test]
+    fn test_server_handshake() {
+        let request = "GET /chat HTTP/1.1\r\n\
+                       Host: server.example.com\r\n\
+                       Upgrade: websocket\r\n\
+                       Connection: Upgrade\r\n\
+                       Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                       Sec-WebSocket-Version: 13\r\n\r\n";
+        let mut reader = BufReader::new(request.as_bytes());
+        let mut writer = MemWriter::new();
+        server_handshake(&mut reader, &mut writer).unwrap();
+        let response = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(response.as_slice().starts_with("HTTP/1.1 101 "));
+        assert!(response.as_slice().contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+    }
+
+    #[test]
+    fn test_reject_plain_http() {
+        let request = "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let mut reader = BufReader::new(request.as_bytes());
+        let mut writer = MemWriter::new();
+        assert!(server_handshake(&mut reader, &mut writer).is_err());
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let long: String = range(0, 300).map(|_| 'x').collect();
+        for text in ["hello", long.as_slice()].iter() {
+            let mut writer = MemWriter::new();
+            write_text(&mut writer, *text).unwrap();
+            write_close(&mut writer).unwrap();
+            let mut reader = MemReader::new(writer.into_inner());
+            assert_eq!(Message::Text(text.to_string()), read_server_message(&mut reader).unwrap());
+            assert_eq!(Message::Close, read_server_message(&mut reader).unwrap());
+            let mut writer = MemWriter::new();
+            write_masked_text(&mut writer, *text).unwrap();
+            let mut reader = MemReader::new(writer.into_inner());
+            assert_eq!(Message::Text(text.to_string()), read_client_message(&mut reader).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_fragmented_message() {
+        // "Hel" then "lo", from the RFC.
+        let frames = vec![0x01, 0x03, 0x48, 0x65, 0x6c, 0x80, 0x02, 0x6c, 0x6f];
+        let mut reader = MemReader::new(frames);
+        assert_eq!(Message::Text("Hello".to_string()), read_server_message(&mut reader).unwrap());
+    }
+
+    #[test]
+    fn test_masking_checked() {
+        let mut writer = MemWriter::new();
+        write_text(&mut writer, "hello").unwrap();
+        assert!(read_client_message(&mut MemReader::new(writer.into_inner())).is_err());
+        let mut writer = MemWriter::new();
+        write_masked_text(&mut writer, "hello").unwrap();
+        assert!(read_server_message(&mut MemReader::new(writer.into_inner())).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+// Added rust 1.0.0-alpha. So much unstable stuff.
+#![allow(unstable)]
+
+extern crate loveletter;
+
+use std::cell::RefCell;
+use std::io::{File, TempDir};
+use std::io::fs::PathExtensions;
+use std::io::process::{Command, ProcessExit, ProcessOutput};
+use std::os;
+
+use loveletter::{Card, Play, PlayerId};
+use loveletter::agent::{Agent, HeuristicAgent};
+use loveletter::game;
+use loveletter::record::GameRecord;
+
+
+/// The cards affectioncube offers as guesses, in the order it offers them.
+const GUESSES: [Card; 7] = [
+    Card::Clown, Card::Knight, Card::Priestess, Card::Wizard, Card::General, Card::Minister, Card::Princess];
+
+
+/// Where cargo put affectioncube: next to this test, or the directory above.
+fn affectioncube() -> Path {
+    let dir = os::self_exe_path().expect("Can't find test binary");
+    let path = dir.join("affectioncube");
+    if path.exists() { path } else { dir.dir_path().join("affectioncube") }
+}
+
+
+/// The number affectioncube shows next to `player` when asking for a target.
+fn position(players: &[PlayerId], player: PlayerId) -> usize {
+    players.iter().position(|&p| p == player).unwrap() + 1
+}
+
+
+/// Play a two-player game with the same seed affectioncube will be given,
+/// with the heuristic agent making every play. Returns the answers
+/// affectioncube needs to make the same plays, and the final scores.
+fn script_game(seed: u32) -> (Vec<String>, Vec<u32>) {
+    let mut rng = game::seeded_rng(seed);
+    let mut current_game = game::new_game(2).unwrap();
+    let players = current_game.players();
+    let answers = RefCell::new(vec![]);
+    loop {
+        let mut round = match current_game.next_round_with_rng(&mut rng) {
+            Some(r) => r,
+            None => break,
+        };
+        loop {
+            let result = round.handle_turn(
+                |round, turn| {
+                    let view = round.view(turn.player).ok().unwrap();
+                    let (card, play) = HeuristicAgent.choose(&view);
+                    let mut answers = answers.borrow_mut();
+                    answers.push(if card == turn.hand { 1 } else { 2 });
+                    match play {
+                        Play::NoEffect => (),
+                        Play::Attack(target) => answers.push(position(players.as_slice(), target)),
+                        Play::Guess(target, guess) => {
+                            answers.push(position(players.as_slice(), target));
+                            answers.push(GUESSES.iter().position(|&c| c == guess).unwrap() + 1);
+                        },
+                    }
+                    (card, play)
+                },
+                |_, _| ());
+            round = match result {
+                Ok(Some((round, _))) => round,
+                Ok(None) => break,
+                Err(e) => panic!("Heuristic agent made an invalid play: {:?}", e),
+            };
+        }
+        let winners: Vec<PlayerId> = round.winners().iter().map(|&(p, _)| p).collect();
+        current_game = current_game.players_won(winners.as_slice());
+    }
+    let answers = answers.borrow().iter().map(|a| a.to_string()).collect();
+    (answers, current_game.scores())
+}
+
+
+/// Run affectioncube on `answers` with `options`, from `dir`, so that it
+/// can't touch anything else.
+fn run_script_in(dir: &TempDir, seed: u32, answers: &[String], options: &[&str]) -> ProcessOutput {
+    let script = dir.path().join("moves.txt");
+    File::create(&script).write_str(answers.connect("\n").as_slice()).unwrap();
+    Command::new(affectioncube())
+        .arg(format!("--seed={}", seed))
+        .arg(format!("--script={}", script.display()))
+        .args(options)
+        .cwd(dir.path())
+        .output()
+        .unwrap()
+}
+
+
+fn run_script(seed: u32, answers: &[String]) -> ProcessOutput {
+    run_script_in(&TempDir::new("affectioncube").unwrap(), seed, answers, &[])
+}
+
+
+#[test]
+fn test_scripted_game() {
+    let (answers, scores) = script_game(7);
+    let result = run_script(7, answers.as_slice());
+    let output = String::from_utf8(result.output).unwrap();
+    assert!(result.status.success(), "affectioncube failed: {}", output);
+    let end = output.find_str("GAME OVER").expect("Game didn't finish");
+    for (i, score) in scores.iter().enumerate() {
+        assert!(output.slice_from(end).contains(format!("Player #{}: {}\n", i + 1, score).as_slice()));
+    }
+}
+
+
+#[test]
+fn test_scripted_game_in_french() {
+    let (answers, _) = script_game(7);
+    let dir = TempDir::new("affectioncube").unwrap();
+    let result = run_script_in(&dir, 7, answers.as_slice(), &["--language=fr"]);
+    let output = String::from_utf8(result.output).unwrap();
+    assert!(result.status.success(), "affectioncube failed: {}", output);
+    assert!(output.contains("NOUVELLE MANCHE"));
+    assert!(output.contains("PARTIE TERMINÉE"));
+    assert!(!output.contains("GAME OVER"));
+}
+
+
+#[test]
+fn test_help_while_playing() {
+    let (answers, _) = script_game(7);
+    let answers: Vec<String> = Some("help".to_string()).into_iter().chain(answers.into_iter()).collect();
+    let result = run_script(7, answers.as_slice());
+    let output = String::from_utf8(result.output).unwrap();
+    assert!(result.status.success(), "affectioncube failed: {}", output);
+    assert!(output.contains("1. Soldier (x5): "));
+    assert!(output.contains("Variant: quick"));
+}
+
+
+#[test]
+fn test_record_scripted_game() {
+    let (answers, scores) = script_game(7);
+    let dir = TempDir::new("affectioncube").unwrap();
+    let result = run_script_in(&dir, 7, answers.as_slice(), &["--record=game.txt"]);
+    assert!(result.status.success());
+    let record = GameRecord::load(&dir.path().join("game.txt")).unwrap();
+    assert_eq!(Some(7), record.seed);
+    assert_eq!(scores, record.replay().unwrap().scores());
+}
+
+
+#[test]
+fn test_replay_recorded_game() {
+    let (answers, _) = script_game(7);
+    let dir = TempDir::new("affectioncube").unwrap();
+    run_script_in(&dir, 7, answers.as_slice(), &["--record=game.txt"]);
+    let commands = ["n", "n", "p", "q"].iter().map(|c| c.to_string()).collect::<Vec<String>>();
+    let result = run_script_in(&dir, 7, commands.as_slice(), &["replay", "game.txt", "1"]);
+    let output = String::from_utf8(result.output).unwrap();
+    assert!(result.status.success(), "replay failed: {}", output);
+    assert!(output.contains("Round 1 of"));
+    assert!(output.contains(", turn 2 of"));
+    assert_eq!(2, output.matches_str(", turn 1 of").count());
+    // Player 1 can't see player 2's hand.
+    assert!(!output.contains("Player #2, holding"));
+}
+
+
+#[test]
+fn test_script_runs_out() {
+    let (answers, _) = script_game(7);
+    let result = run_script(7, answers.slice_to(answers.len() / 2));
+    assert_eq!(ProcessExit::ExitStatus(1), result.status);
+    assert!(String::from_utf8(result.error).unwrap().is_empty());
+    let output = String::from_utf8(result.output).unwrap();
+    assert!(output.contains("Could not read an answer"));
+    assert!(!output.contains("GAME OVER"));
+}
+
+
+#[test]
+fn test_bad_script() {
+    let result = run_script(7, &["Princess".to_string()]);
+    assert_eq!(ProcessExit::ExitStatus(1), result.status);
+    assert!(String::from_utf8(result.error).unwrap().is_empty());
+    assert!(String::from_utf8(result.output).unwrap().contains("Bad answer \"Princess\""));
+}
@@ -0,0 +1,112 @@
+// Added rust 1.0.0-alpha. So much unstable stuff.
+#![allow(unstable)]
+
+extern crate loveletter;
+extern crate serialize;
+
+use std::collections::BTreeMap;
+use std::io::{BufferedReader, IoResult, TcpStream};
+use std::io::timer::sleep;
+use std::thread::Thread;
+use std::time::Duration;
+
+use serialize::json;
+use serialize::json::Json;
+
+use loveletter::server;
+use loveletter::websocket;
+
+
+const TCP_ADDRESS: &'static str = "127.0.0.1:43210";
+const WEBSOCKET_ADDRESS: &'static str = "127.0.0.1:43211";
+
+
+/// Connect to the server, giving it a moment to start listening.
+fn connect(address: &str) -> TcpStream {
+    for _ in range(0, 100us) {
+        match TcpStream::connect(address) {
+            Ok(stream) => return stream,
+            Err(..) => sleep(Duration::milliseconds(20)),
+        }
+    }
+    panic!("Could not connect to {}", address);
+}
+
+
+fn send(stream: &mut TcpStream, message: Json) -> IoResult<()> {
+    websocket::write_masked_text(stream, message.to_string().as_slice())
+}
+
+
+/// Play a whole game as a browser would, always making the first legal play.
+///
+/// Returns every message received, in order.
+fn play_game(name: &str) -> Vec<Json> {
+    let mut writer = connect(WEBSOCKET_ADDRESS);
+    let mut reader = BufferedReader::new(writer.clone());
+    websocket::client_handshake(&mut reader, &mut writer, WEBSOCKET_ADDRESS).unwrap();
+    let mut join = BTreeMap::new();
+    join.insert("type".to_string(), Json::String("join".to_string()));
+    join.insert("name".to_string(), Json::String(name.to_string()));
+    send(&mut writer, Json::Object(join)).unwrap();
+
+    let mut received = vec![];
+    loop {
+        let text = match websocket::read_server_message(&mut reader).unwrap() {
+            websocket::Message::Text(text) => text,
+            other => panic!("Unexpected message: {:?}", other),
+        };
+        let message = json::from_str(text.as_slice()).unwrap();
+        let message_type = message.find("type").unwrap().as_string().unwrap().to_string();
+        received.push(message.clone());
+        match message_type.as_slice() {
+            "your_turn" => {
+                let play = message.find("legal_plays").unwrap().as_array().unwrap()[0].clone();
+                let mut obj = BTreeMap::new();
+                obj.insert("type".to_string(), Json::String("play".to_string()));
+                obj.insert("play".to_string(), play);
+                send(&mut writer, Json::Object(obj)).unwrap();
+            },
+            "invalid_play" | "error" => panic!("Server rejected a play: {}", text),
+            "game_over" => return received,
+            _ => (),
+        }
+    }
+}
+
+
+fn seat(messages: &[Json]) -> u64 {
+    messages
+        .iter()
+        .find(|m| m.find("type").unwrap().as_string() == Some("welcome"))
+        .and_then(|m| m.find("seat"))
+        .and_then(|s| s.as_u64())
+        .expect("Never welcomed")
+}
+
+
+#[test]
+fn test_full_game_over_websocket() {
+    let mut config = server::Config::new(TCP_ADDRESS);
+    config.websocket_address = Some(WEBSOCKET_ADDRESS.to_string());
+    let server = Thread::scoped(move || server::serve(&config));
+    let alice = Thread::scoped(move || play_game("alice"));
+    let bob = Thread::scoped(move || play_game("bob"));
+    let alice = alice.join().ok().expect("Alice's client failed");
+    let bob = bob.join().ok().expect("Bob's client failed");
+    server.join().ok().expect("Server failed").unwrap();
+
+    let game_over = |messages: &[Json]| messages.last().unwrap().clone();
+    assert_eq!(game_over(alice.as_slice()), game_over(bob.as_slice()));
+
+    // Nobody is ever shown anyone else's view.
+    for messages in [alice.as_slice(), bob.as_slice()].iter() {
+        let own_seat = seat(*messages);
+        for message in messages.iter() {
+            match message.find("view") {
+                Some(view) => assert_eq!(Some(own_seat), view.find("seat").and_then(|s| s.as_u64())),
+                None => (),
+            }
+        }
+    }
+}